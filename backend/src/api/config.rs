@@ -3,8 +3,14 @@ use axum::{
     response::{Json, Response},
     http::{StatusCode, header},
 };
-use crate::state::AppState;
-use rust_media_downloader_shared::config;
+use crate::{
+    disk::{get_disk_space, DiskLimits},
+    models::{BandwidthSettings, BandwidthStatus, ErrorResponse},
+    state::AppState,
+    validation::{validate_rate_limit, validate_rate_window, ValidationError},
+    worker::current_rate_limit,
+};
+use rust_media_downloader_shared::{bandwidth, config};
 use serde::Serialize;
 use std::path::PathBuf;
 
@@ -66,6 +72,9 @@ pub struct DiskInfo {
     pub disk_total: Option<u64>, // Espace disque total en bytes (si disponible)
     pub disk_used_percentage: Option<f64>, // Pourcentage d'utilisation (si disponible)
     pub download_directory: String,
+    pub library_quota: Option<u64>, // Quota on total_downloads_size in bytes (si configuré)
+    pub min_free_space: Option<u64>, // Seuil d'espace libre en bytes (si configuré)
+    pub low_space: bool, // Espace libre sous le seuil : les téléchargements sont en pause
 }
 
 pub async fn get_disk_info(
@@ -99,6 +108,8 @@ pub async fn get_disk_info(
     
     // Try to get disk space information
     let (disk_free, disk_total, disk_used_percentage) = get_disk_space(&download_dir_absolute);
+    let limits = DiskLimits::from_config(&config);
+    let low_space = matches!((disk_free, limits.min_free), (Some(free), Some(min_free)) if free < min_free);
     
     Json(DiskInfo {
         total_downloads_size: total_size,
@@ -107,73 +118,58 @@ pub async fn get_disk_info(
         disk_total,
         disk_used_percentage,
         download_directory: download_dir_absolute.to_string_lossy().to_string(),
+        library_quota: limits.quota,
+        min_free_space: limits.min_free,
+        low_space,
     })
 }
 
-fn get_disk_space(path: &PathBuf) -> (Option<u64>, Option<u64>, Option<f64>) {
-    #[cfg(unix)]
-    {
-        use std::ffi::CString;
-        use std::mem;
-        
-        extern "C" {
-            fn statvfs(path: *const libc::c_char, buf: *mut libc::statvfs) -> libc::c_int;
-        }
-        
-        unsafe {
-            let path_str = path.to_string_lossy().to_string();
-            if let Ok(c_path) = CString::new(path_str) {
-                let mut stat: libc::statvfs = mem::zeroed();
-                if statvfs(c_path.as_ptr(), &mut stat) == 0 {
-                    let block_size = stat.f_frsize as u64;
-                    let total = stat.f_blocks as u64 * block_size;
-                    let free = stat.f_bavail as u64 * block_size;
-                    let used = total - free;
-                    let used_percentage = if total > 0 {
-                        Some((used as f64 / total as f64) * 100.0)
-                    } else {
-                        None
-                    };
-                    return (Some(free), Some(total), used_percentage);
-                }
-            }
-        }
+/// Current bandwidth limits and how they split across active downloads
+pub async fn get_bandwidth(
+    State(state): State<AppState>,
+) -> Json<BandwidthStatus> {
+    Json(bandwidth_status(&state, &config::load_config()))
+}
+
+/// Change the global limit and/or its schedule; running downloads are restarted with their new share
+pub async fn update_bandwidth(
+    State(state): State<AppState>,
+    Json(request): Json<BandwidthSettings>,
+) -> Result<Json<BandwidthStatus>, (StatusCode, Json<ErrorResponse>)> {
+    let invalid = |e: ValidationError| (
+        StatusCode::BAD_REQUEST,
+        Json(ErrorResponse::new("invalid_rate_limit", e.message)),
+    );
+    if let Some(rate) = &request.rate_limit {
+        validate_rate_limit(rate).map_err(invalid)?;
     }
-    
-    #[cfg(windows)]
-    {
-        use std::os::windows::ffi::OsStrExt;
-        use std::mem;
-        
-        unsafe {
-            let path_wide: Vec<u16> = path.as_os_str()
-                .encode_wide()
-                .chain(Some(0))
-                .collect();
-            
-            let mut free_bytes: u64 = 0;
-            let mut total_bytes: u64 = 0;
-            
-            // Use GetDiskFreeSpaceExW through winapi
-            if winapi::um::fileapi::GetDiskFreeSpaceExW(
-                path_wide.as_ptr(),
-                &mut free_bytes as *mut _ as *mut winapi::um::winnt::ULARGE_INTEGER,
-                &mut total_bytes as *mut _ as *mut winapi::um::winnt::ULARGE_INTEGER,
-                std::ptr::null_mut(),
-            ) != 0 {
-                let used = total_bytes - free_bytes;
-                let used_percentage = if total_bytes > 0 {
-                    Some((used as f64 / total_bytes as f64) * 100.0)
-                } else {
-                    None
-                };
-                return (Some(free_bytes), Some(total_bytes), used_percentage);
-            }
-        }
+    for window in &request.schedule {
+        validate_rate_window(window).map_err(invalid)?;
+    }
+
+    let mut config = config::load_config();
+    config.rate_limit = request.rate_limit.filter(|rate| !rate.trim().is_empty());
+    config.rate_limit_schedule = request.schedule;
+    config::save_config(&config);
+    tracing::info!("Bandwidth limit set to {:?} ({} scheduled window(s))", config.rate_limit, config.rate_limit_schedule.len());
+    state.rebalance_bandwidth();
+
+    Ok(Json(bandwidth_status(&state, &config)))
+}
+
+fn bandwidth_status(state: &AppState, config: &config::Config) -> BandwidthStatus {
+    let effective_limit = current_rate_limit(config);
+    let (active_downloads, _) = state.queue_workers();
+    BandwidthStatus {
+        settings: BandwidthSettings {
+            rate_limit: config.rate_limit.clone(),
+            schedule: config.rate_limit_schedule.clone(),
+        },
+        effective_limit,
+        active_downloads,
+        // A download starting now joins the running ones
+        per_download_limit: bandwidth::share_for(effective_limit, active_downloads + 1, None),
     }
-    
-    // Fallback: return None if platform not supported or call failed
-    (None, None, None)
 }
 
 /// Serve the DISCLAIMER.md file
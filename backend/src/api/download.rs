@@ -5,11 +5,16 @@ use axum::{
     body::Body,
 };
 use crate::{
-    models::{DownloadRequest, DownloadResponse, DownloadStatus, DownloadType, ErrorResponse, PaginatedResponse, PaginationParams, UpdateMetadataRequest, ConvertFileRequest},
-    state::AppState,
-    validation::validate_url,
+    api::presets::apply_preset,
+    models::{DownloadRequest, DownloadResponse, DownloadStatus, ErrorResponse, PaginatedResponse, PaginationParams, UpdateMetadataRequest, ConvertFileRequest, SplitFileRequest},
+    converter::tracks_from_timestamps,
+    state::{AppState, StopReason},
+    validation::{validate_rate_limit, validate_url},
+    scheduler::resolve_schedule,
+    worker::format_selector,
 };
-use rust_media_downloader_shared::{download_video, download_audio};
+use chrono::{DateTime, Utc};
+use rust_media_downloader_shared::{output_template, DuplicatePolicy, MediaId};
 use utoipa;
 
 #[utoipa::path(
@@ -24,7 +29,7 @@ use utoipa;
 )]
 pub async fn create_download(
     State(state): State<AppState>,
-    Json(request): Json<DownloadRequest>,
+    Json(mut request): Json<DownloadRequest>,
 ) -> Result<Json<DownloadResponse>, (StatusCode, Json<ErrorResponse>)> {
     // Validate URL
     if let Err(e) = validate_url(&request.url) {
@@ -34,111 +39,62 @@ pub async fn create_download(
         ));
     }
 
+    // Preset options fill whatever the request leaves unset, then the result is validated as a whole
+    apply_preset(&state, &mut request).await?;
+
+    // Reject option combinations yt-dlp cannot honor instead of falling back to "best"
+    if let Err(e) = format_selector(&request) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new("invalid_format_options", e.to_string())),
+        ));
+    }
+
+    if let Some(Err(e)) = request.rate_limit.as_deref().map(validate_rate_limit) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new("invalid_rate_limit", e.message)),
+        ));
+    }
+
+    if let Some(Err(e)) = request.output_template.as_deref().map(output_template::validate_template) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new("invalid_output_template", e)),
+        ));
+    }
+
+    let scheduled_at = match resolve_schedule(request.scheduled_at, request.recurrence.as_deref(), Utc::now()) {
+        Ok(scheduled_at) => scheduled_at,
+        Err(e) => return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new("invalid_schedule", e)),
+        )),
+    };
+
     // Create download response
-    let download = DownloadResponse::new(request.url.clone(), request.download_type.clone());
+    let mut download = DownloadResponse::new(request.url.clone(), request.download_type.clone());
+    // Metadata already fetched by the preview is shown right away, the download refines it
+    if let Some(info) = state.get_video_cache().get(&request.url).await {
+        download.apply_video_info(&info);
+    }
     let download_id = download.id.clone();
-    
-    // Add to state
-    state.add_download(download.clone()).await;
-
-    // Spawn background task for actual download
-    let state_clone = state.clone();
-    let request_clone = request.clone();
-    tokio::spawn(async move {
-        // Update status to Downloading
-        if let Some(mut dl) = state_clone.get_download(&download_id).await {
-            dl.set_status(
-                DownloadStatus::Downloading, 
-                "Downloading from YouTube... This may take 30-60 seconds depending on video size. Please wait.".to_string()
-            );
-            dl.progress = 10.0; // Start at 10% when downloading begins
-            state_clone.update_download(&download_id, dl).await;
-        }
 
-        // Spawn a task to update progress approximately every 2 seconds
-        let state_progress = state_clone.clone();
-        let download_id_progress = download_id.clone();
-        let progress_handle = tokio::spawn(async move {
-            let mut elapsed = 0u64;
-            loop {
-                tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
-                elapsed += 2;
-                
-                // Simulate progress: start at 10%, gradually increase to 90% over ~60 seconds
-                // This gives a reasonable approximation for most videos
-                let current_progress = if elapsed < 60 {
-                    10.0 + (elapsed as f32 / 60.0) * 80.0 // 10% to 90% over 60 seconds
-                } else {
-                    90.0 // Cap at 90% until actual completion
-                };
-                
-                if let Some(mut dl) = state_progress.get_download(&download_id_progress).await {
-                    // Only update if still downloading
-                    if dl.status == DownloadStatus::Downloading {
-                        dl.progress = current_progress;
-                        state_progress.update_download(&download_id_progress, dl).await;
-                    } else {
-                        // Download finished, stop updating progress
-                        break;
-                    }
-                } else {
-                    // Download not found, stop
-                    break;
-                }
-            }
-        });
+    // Only a valid request may be answered with an earlier download
+    if let Some(existing) = check_duplicate(&state, &mut download, &request).await {
+        return Ok(Json(existing));
+    }
+    apply_schedule(&mut download, &request, scheduled_at);
 
-        // Perform the actual download
-        let result = perform_download(request_clone, download_id.clone()).await;
-        
-        // Cancel progress update task
-        progress_handle.abort();
-        
-        // Update download status based on result
-        if let Some(mut dl) = state_clone.get_download(&download_id).await {
-            match result {
-                Ok(file_path) => {
-                    dl.set_status(DownloadStatus::Completed, "Download completed successfully".to_string());
-                    dl.file_path = Some(file_path.clone());
-                    dl.progress = 100.0;
-                    let dl_clone = dl.clone();
-                    state_clone.update_download(&download_id, dl).await;
-                    
-                    // Trigger webhook for completed download
-                    let state_webhook = state_clone.clone();
-                    tokio::spawn(async move {
-                        let event_data = serde_json::json!({
-                            "download_id": download_id,
-                            "status": "completed",
-                            "file_path": dl_clone.file_path,
-                            "url": dl_clone.url,
-                            "title": dl_clone.title,
-                        });
-                        crate::api::webhooks::trigger_webhooks(&state_webhook, "download.completed", event_data).await;
-                    });
-                }
-                Err(e) => {
-                    let error_msg = format!("Download failed: {}", e);
-                    dl.set_status(DownloadStatus::Failed, error_msg.clone());
-                    let dl_clone = dl.clone();
-                    state_clone.update_download(&download_id, dl).await;
-                    
-                    // Trigger webhook for failed download
-                    let state_webhook = state_clone.clone();
-                    tokio::spawn(async move {
-                        let event_data = serde_json::json!({
-                            "download_id": download_id,
-                            "status": "failed",
-                            "error": error_msg,
-                            "url": dl_clone.url,
-                            "title": dl_clone.title,
-                        });
-                        crate::api::webhooks::trigger_webhooks(&state_webhook, "download.failed", event_data).await;
-                    });
-                }
-            }
-        }
-    });
+    // Add to state and hand the job to the queue (or the scheduler)
+    state.add_download(download.clone()).await;
+    if let Err(e) = submit_download(&state, &download, &request).await {
+        tracing::error!("Failed to enqueue download {}: {}", download_id, e);
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::new("queue_error", format!("Failed to enqueue download: {}", e))),
+        ));
+    }
 
     Ok(Json(download))
 }
@@ -150,8 +106,10 @@ pub async fn create_batch_downloads(
     let mut created = 0;
     let mut errors = 0;
     let mut error_details = Vec::new();
+    let mut skipped = 0;
+    let mut skipped_details = Vec::new();
     
-    for request in requests {
+    for mut request in requests {
         // Validate URL
         if let Err(e) = validate_url(&request.url) {
             errors += 1;
@@ -162,110 +120,82 @@ pub async fn create_batch_downloads(
             continue;
         }
 
+        if let Err((_, Json(e))) = apply_preset(&state, &mut request).await {
+            errors += 1;
+            error_details.push(serde_json::json!({
+                "url": request.url,
+                "error": e.message
+            }));
+            continue;
+        }
+
+        if let Err(e) = format_selector(&request) {
+            errors += 1;
+            error_details.push(serde_json::json!({
+                "url": request.url,
+                "error": e.to_string()
+            }));
+            continue;
+        }
+
+        if let Some(Err(e)) = request.rate_limit.as_deref().map(validate_rate_limit) {
+            errors += 1;
+            error_details.push(serde_json::json!({
+                "url": request.url,
+                "error": e.message
+            }));
+            continue;
+        }
+
+        if let Some(Err(e)) = request.output_template.as_deref().map(output_template::validate_template) {
+            errors += 1;
+            error_details.push(serde_json::json!({
+                "url": request.url,
+                "error": e
+            }));
+            continue;
+        }
+
+        let scheduled_at = match resolve_schedule(request.scheduled_at, request.recurrence.as_deref(), Utc::now()) {
+            Ok(scheduled_at) => scheduled_at,
+            Err(e) => {
+                errors += 1;
+                error_details.push(serde_json::json!({
+                    "url": request.url,
+                    "error": e
+                }));
+                continue;
+            }
+        };
+
         // Create download response
-        let download = DownloadResponse::new(request.url.clone(), request.download_type.clone());
+        let mut download = DownloadResponse::new(request.url.clone(), request.download_type.clone());
+        if let Some(info) = state.get_video_cache().get(&request.url).await {
+            download.apply_video_info(&info);
+        }
         let download_id = download.id.clone();
-        
-        // Add to state
-        state.add_download(download.clone()).await;
 
-        // Spawn background task for actual download
-        let state_clone = state.clone();
-        let request_clone = request.clone();
-        tokio::spawn(async move {
-            // Update status to Downloading
-            if let Some(mut dl) = state_clone.get_download(&download_id).await {
-                dl.set_status(
-                    DownloadStatus::Downloading, 
-                    "Downloading from YouTube... This may take 30-60 seconds depending on video size. Please wait.".to_string()
-                );
-                dl.progress = 10.0;
-                state_clone.update_download(&download_id, dl).await;
-            }
+        if let Some(existing) = check_duplicate(&state, &mut download, &request).await {
+            skipped += 1;
+            skipped_details.push(serde_json::json!({
+                "url": request.url,
+                "existing_id": existing.id
+            }));
+            continue;
+        }
+        apply_schedule(&mut download, &request, scheduled_at);
 
-            // Spawn a task to update progress approximately every 2 seconds
-            let state_progress = state_clone.clone();
-            let download_id_progress = download_id.clone();
-            let progress_handle = tokio::spawn(async move {
-                let mut elapsed = 0u64;
-                loop {
-                    tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
-                    elapsed += 2;
-                    
-                    let current_progress = if elapsed < 60 {
-                        10.0 + (elapsed as f32 / 60.0) * 80.0
-                    } else {
-                        90.0
-                    };
-                    
-                    if let Some(mut dl) = state_progress.get_download(&download_id_progress).await {
-                        if dl.status == DownloadStatus::Downloading {
-                            dl.progress = current_progress;
-                            state_progress.update_download(&download_id_progress, dl).await;
-                        } else {
-                            break;
-                        }
-                    } else {
-                        break;
-                    }
-                }
-            });
-
-            // Perform actual download
-            match perform_download(request_clone, download_id.clone()).await {
-                Ok(file_path) => {
-                    progress_handle.abort();
-                    if let Some(mut dl) = state_clone.get_download(&download_id).await {
-                        dl.status = DownloadStatus::Completed;
-                        dl.progress = 100.0;
-                        dl.message = "Download completed successfully".to_string();
-                        dl.file_path = Some(file_path.clone());
-                        dl.completed_at = Some(chrono::Utc::now());
-                        let dl_clone = dl.clone();
-                        state_clone.update_download(&download_id, dl).await;
-                        
-                        // Trigger webhook
-                        let state_webhook = state_clone.clone();
-                        let download_id_webhook = download_id.clone();
-                        tokio::spawn(async move {
-                            let event_data = serde_json::json!({
-                                "download_id": download_id_webhook,
-                                "status": "completed",
-                                "file_path": dl_clone.file_path,
-                                "url": dl_clone.url,
-                                "title": dl_clone.title,
-                            });
-                            crate::api::webhooks::trigger_webhooks(&state_webhook, "download.completed", event_data).await;
-                        });
-                    }
-                }
-                Err(e) => {
-                    progress_handle.abort();
-                    tracing::error!("Download failed for {}: {}", download_id, e);
-                    if let Some(mut dl) = state_clone.get_download(&download_id).await {
-                        dl.status = DownloadStatus::Failed;
-                        let error_msg = format!("Download failed: {}", e);
-                        dl.message = error_msg.clone();
-                        let dl_clone = dl.clone();
-                        state_clone.update_download(&download_id, dl).await;
-                        
-                        // Trigger webhook
-                        let state_webhook = state_clone.clone();
-                        let download_id_webhook = download_id.clone();
-                        tokio::spawn(async move {
-                            let event_data = serde_json::json!({
-                                "download_id": download_id_webhook,
-                                "status": "failed",
-                                "error": error_msg,
-                                "url": dl_clone.url,
-                                "title": dl_clone.title,
-                            });
-                            crate::api::webhooks::trigger_webhooks(&state_webhook, "download.failed", event_data).await;
-                        });
-                    }
-                }
-            }
-        });
+        // Add to state and hand the job to the queue (or the scheduler)
+        state.add_download(download.clone()).await;
+        if let Err(e) = submit_download(&state, &download, &request).await {
+            tracing::error!("Failed to enqueue download {}: {}", download_id, e);
+            errors += 1;
+            error_details.push(serde_json::json!({
+                "url": request.url,
+                "error": format!("Failed to enqueue download: {}", e)
+            }));
+            continue;
+        }
 
         created += 1;
     }
@@ -274,74 +204,12 @@ pub async fn create_batch_downloads(
         "created": created,
         "errors": errors,
         "error_details": error_details,
-        "total": created + errors
+        "skipped": skipped,
+        "skipped_details": skipped_details,
+        "total": created + errors + skipped
     })))
 }
 
-async fn perform_download(request: DownloadRequest, _id: String) -> anyhow::Result<String> {
-    use rust_media_downloader_shared::config;
-    use std::path::PathBuf;
-    
-    let download_playlist = request.download_playlist.unwrap_or(false);
-    
-    match request.download_type {
-        DownloadType::Video => {
-            let format = request.format.as_deref().unwrap_or("mp4");
-            // Note: resolution and audio_quality are not supported by download_video
-            // download_subtitles and subtitle_language are also not supported
-            
-            download_video(
-                &request.url,
-                format,
-                false, // keep_files
-                request.custom_filename,
-                request.cookies_browser,
-                download_playlist,
-            ).await?;
-            
-            // Construct file path from download directory
-            let config = config::load_config();
-            let download_dir = PathBuf::from(config.download_directory);
-            // Since we don't know the exact filename, return the directory
-            // The actual filename will be determined by yt-dlp
-            Ok(download_dir.to_string_lossy().to_string())
-        }
-        DownloadType::Audio => {
-            let format = request.format.as_deref().unwrap_or("mp3");
-            // Note: audio_quality is not supported by download_audio
-            
-            download_audio(
-                &request.url,
-                format,
-                false, // extract_instrumental
-                request.custom_filename,
-                request.cookies_browser,
-                download_playlist,
-            ).await?;
-            
-            let config = config::load_config();
-            let download_dir = PathBuf::from(config.download_directory);
-            Ok(download_dir.to_string_lossy().to_string())
-        }
-        DownloadType::Instrumental => {
-            let format = request.format.as_deref().unwrap_or("mp3");
-            
-            download_audio(
-                &request.url,
-                format,
-                true, // extract_instrumental = true
-                request.custom_filename,
-                request.cookies_browser,
-                download_playlist,
-            ).await?;
-            
-            let config = config::load_config();
-            let download_dir = PathBuf::from(config.download_directory);
-            Ok(download_dir.to_string_lossy().to_string())
-        }
-    }
-}
-
 pub async fn get_download(
     State(state): State<AppState>,
     Path(id): Path<String>,
@@ -355,6 +223,20 @@ pub async fn get_download(
     }
 }
 
+/// Entries of a playlist download, in playlist order
+pub async fn get_playlist_items(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<DownloadResponse>>, (StatusCode, Json<ErrorResponse>)> {
+    if state.get_download(&id).await.is_none() {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::new("not_found", "Download not found")),
+        ));
+    }
+    Ok(Json(state.get_playlist_items(&id).await))
+}
+
 pub async fn list_downloads(
     State(state): State<AppState>,
     Query(params): Query<PaginationParams>,
@@ -380,10 +262,58 @@ pub async fn get_all_downloads(
     Json(state.get_all_downloads().await)
 }
 
+pub async fn get_queue(
+    State(state): State<AppState>,
+) -> Json<serde_json::Value> {
+    let (active_workers, max_workers) = state.queue_workers();
+    let jobs = state.get_queued_jobs().await;
+
+    Json(serde_json::json!({
+        "active_workers": active_workers,
+        "max_workers": max_workers,
+        "queued": jobs.len(),
+        "jobs": jobs,
+    }))
+}
+
+#[derive(serde::Deserialize)]
+pub struct DeleteParams {
+    /// Also delete every file tracked for the download (only inside the download directory)
+    #[serde(default)]
+    delete_files: bool,
+}
+
 pub async fn delete_download(
     State(state): State<AppState>,
     Path(id): Path<String>,
+    Query(params): Query<DeleteParams>,
 ) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    // Stop the yt-dlp process (if any) so it does not keep writing files
+    state.request_stop(&id, StopReason::Cancel);
+
+    if params.delete_files {
+        let Some(download) = state.get_download(&id).await else {
+            return Err((
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse::new("not_found", "Download not found")),
+            ));
+        };
+        // The entry is kept when a file could not be deleted, so the request can be retried
+        if let Err(errors) = state.delete_download_files(&download).await {
+            return Err((
+                StatusCode::CONFLICT,
+                Json(ErrorResponse::new("files_not_deleted", format!("Download kept, some files could not be deleted: {}", errors.join("; ")))),
+            ));
+        }
+    }
+
+    // Queued and paused jobs keep .part files and a staging directory that the job row alone tracks
+    for item in state.get_playlist_items(&id).await {
+        state.request_stop(&item.id, StopReason::Cancel);
+        state.discard_queued_download(&item.id).await.map_err(queue_error)?;
+    }
+    state.discard_queued_download(&id).await.map_err(queue_error)?;
+
     match state.remove_download(&id).await {
         Some(_) => Ok(StatusCode::NO_CONTENT),
         None => Err((
@@ -393,6 +323,220 @@ pub async fn delete_download(
     }
 }
 
+/// Apply the configured duplicate policy to a new download.
+///
+/// Returns the earlier download of the same media when the policy is `skip`;
+/// with `warn` the new download goes ahead and points to it in `duplicate_of`.
+async fn check_duplicate(state: &AppState, download: &mut DownloadResponse, request: &DownloadRequest) -> Option<DownloadResponse> {
+    if download.media_id().is_none() {
+        if let Some(media_id) = MediaId::from_url(&download.url) {
+            download.set_media_id(media_id);
+        }
+    }
+
+    let policy = state.config().duplicate_policy;
+    // A playlist URL may also name one of its videos, it is not a duplicate of it
+    if policy == DuplicatePolicy::Allow || request.download_playlist == Some(true) {
+        return None;
+    }
+    let media_id = download.media_id()?;
+    let existing = state.find_duplicate(&media_id, &download.download_type).await?;
+
+    if policy == DuplicatePolicy::Skip {
+        tracing::info!("Skipping {}: {} was already downloaded as {}", download.url, media_id, existing.id);
+        return Some(existing);
+    }
+    tracing::warn!("Downloading {} again: {} was already downloaded as {}", download.url, media_id, existing.id);
+    download.message = format!("Download queued (already downloaded as {})", existing.id);
+    download.duplicate_of = Some(existing.id);
+    None
+}
+
+/// Hold the download until later when the request asks for it
+/// Mark a download due at `scheduled_at` (from `resolve_schedule`) as scheduled
+fn apply_schedule(download: &mut DownloadResponse, request: &DownloadRequest, scheduled_at: Option<DateTime<Utc>>) {
+    let Some(scheduled_at) = scheduled_at else {
+        return;
+    };
+    download.scheduled_at = Some(scheduled_at);
+    download.recurrence = request.recurrence.clone().filter(|r| !r.trim().is_empty());
+    download.set_status(DownloadStatus::Scheduled, format!("Scheduled for {}", scheduled_at.to_rfc3339()));
+}
+
+async fn submit_download(state: &AppState, download: &DownloadResponse, request: &DownloadRequest) -> anyhow::Result<()> {
+    if download.status == DownloadStatus::Scheduled {
+        state.schedule_download(&download.id, request).await
+    } else {
+        state.enqueue_download(&download.id, request).await
+    }
+}
+
+fn invalid_state(download: &DownloadResponse, action: &str) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::CONFLICT,
+        Json(ErrorResponse::new(
+            "invalid_state",
+            format!("Cannot {} a download that is {:?}", action, download.status).to_lowercase(),
+        )),
+    )
+}
+
+fn queue_error(e: anyhow::Error) -> (StatusCode, Json<ErrorResponse>) {
+    tracing::error!("Queue operation failed: {}", e);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ErrorResponse::new("queue_error", format!("Queue operation failed: {}", e))),
+    )
+}
+
+pub async fn cancel_download(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<DownloadResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let mut download = state.get_download(&id).await.ok_or_else(|| (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse::new("not_found", "Download not found")),
+    ))?;
+
+    // Cancelling a playlist cancels the entries that have not finished yet
+    if download.is_playlist && download.status == DownloadStatus::Processing {
+        for mut item in state.get_playlist_items(&id).await {
+            match item.status {
+                DownloadStatus::Downloading | DownloadStatus::Processing => {
+                    state.request_stop(&item.id, StopReason::Cancel);
+                }
+                DownloadStatus::Pending | DownloadStatus::Paused => {
+                    state.discard_queued_download(&item.id).await.map_err(queue_error)?;
+                    item.set_status(DownloadStatus::Cancelled, "Download cancelled".to_string());
+                    state.update_download(&item.id.clone(), item).await;
+                }
+                _ => {}
+            }
+        }
+        let download = state.get_download(&id).await.unwrap_or(download);
+        return Ok(Json(download));
+    }
+
+    match download.status {
+        DownloadStatus::Downloading | DownloadStatus::Processing => {
+            // The worker kills yt-dlp, removes the partial files and marks the download cancelled
+            if state.request_stop(&id, StopReason::Cancel) {
+                download.message = "Cancelling...".to_string();
+                return Ok(Json(download));
+            }
+            Err(invalid_state(&download, "cancel"))
+        }
+        DownloadStatus::Pending | DownloadStatus::Paused | DownloadStatus::Scheduled => {
+            state.discard_queued_download(&id).await.map_err(queue_error)?;
+            download.set_status(DownloadStatus::Cancelled, "Download cancelled".to_string());
+            state.update_download(&id, download.clone()).await;
+            Ok(Json(download))
+        }
+        _ => Err(invalid_state(&download, "cancel")),
+    }
+}
+
+pub async fn pause_download(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<DownloadResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let mut download = state.get_download(&id).await.ok_or_else(|| (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse::new("not_found", "Download not found")),
+    ))?;
+
+    match download.status {
+        DownloadStatus::Downloading => {
+            // The worker stops yt-dlp and keeps the .part files for resuming
+            if state.request_stop(&id, StopReason::Pause) {
+                download.message = "Pausing...".to_string();
+                return Ok(Json(download));
+            }
+            Err(invalid_state(&download, "pause"))
+        }
+        DownloadStatus::Pending => {
+            // Only possible while no worker has claimed the job yet
+            if !state.pause_queued_download(&id).await.map_err(queue_error)? {
+                return Err(invalid_state(&download, "pause"));
+            }
+            download.set_status(DownloadStatus::Paused, "Download paused".to_string());
+            state.update_download(&id, download.clone()).await;
+            Ok(Json(download))
+        }
+        _ => Err(invalid_state(&download, "pause")),
+    }
+}
+
+pub async fn resume_download(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<DownloadResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let mut download = state.get_download(&id).await.ok_or_else(|| (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse::new("not_found", "Download not found")),
+    ))?;
+
+    if download.status != DownloadStatus::Paused {
+        return Err(invalid_state(&download, "resume"));
+    }
+
+    // Status goes back to pending before the job is visible to the dispatcher
+    download.set_status(DownloadStatus::Pending, "Download queued".to_string());
+    state.update_download(&id, download.clone()).await;
+
+    if !state.resume_download(&id).await.map_err(queue_error)? {
+        download.set_status(DownloadStatus::Paused, "Download paused".to_string());
+        state.update_download(&id, download.clone()).await;
+        return Err((
+            StatusCode::CONFLICT,
+            Json(ErrorResponse::new("not_queued", "No paused job found for this download")),
+        ));
+    }
+
+    Ok(Json(download))
+}
+
+/// Manually retry a failed download, giving it a fresh set of automatic retries
+pub async fn retry_download(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<DownloadResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let download = state.get_download(&id).await.ok_or_else(|| (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse::new("not_found", "Download not found")),
+    ))?;
+
+    if download.status != DownloadStatus::Failed {
+        return Err(invalid_state(&download, "retry"));
+    }
+
+    // A playlist is retried through its failed entries, never re-expanded
+    if download.is_playlist {
+        for item in state.get_playlist_items(&id).await {
+            if item.status == DownloadStatus::Failed {
+                requeue_failed(&state, item).await?;
+            }
+        }
+        let download = state.get_download(&id).await.unwrap_or(download);
+        return Ok(Json(download));
+    }
+
+    Ok(Json(requeue_failed(&state, download).await?))
+}
+
+async fn requeue_failed(state: &AppState, mut download: DownloadResponse) -> Result<DownloadResponse, (StatusCode, Json<ErrorResponse>)> {
+    // Status goes back to pending before the job is visible to the dispatcher
+    download.set_status(DownloadStatus::Pending, "Download queued".to_string());
+    download.retry_count = Some(0);
+    download.progress = 0.0;
+    download.downloaded_bytes = None;
+    download.total_bytes = None;
+    state.update_download(&download.id.clone(), download.clone()).await;
+
+    state.retry_download(&download).await.map_err(queue_error)?;
+    Ok(download)
+}
+
 pub async fn update_metadata(
     State(state): State<AppState>,
     Path(id): Path<String>,
@@ -539,7 +683,7 @@ pub async fn import_downloads(
     let mut errors = 0;
     let total = downloads.len();
     
-    for download in downloads {
+    for mut download in downloads {
         // Check if download already exists
         if state.get_download(&download.id).await.is_some() {
             skipped += 1;
@@ -552,6 +696,13 @@ pub async fn import_downloads(
             continue;
         }
         
+        // Same media already in the history (under another id or URL variant)
+        let request = DownloadRequest::for_url(download.url.clone(), download.download_type.clone());
+        if check_duplicate(&state, &mut download, &request).await.is_some() {
+            skipped += 1;
+            continue;
+        }
+
         // Insert download
         state.add_download(download).await;
         
@@ -589,6 +740,35 @@ pub async fn convert_download(
     }
 }
 
+pub async fn split_download(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(request): Json<SplitFileRequest>,
+) -> Result<Json<Vec<DownloadResponse>>, (StatusCode, Json<ErrorResponse>)> {
+    let points: Vec<(String, Option<String>)> = request.tracks.into_iter()
+        .map(|point| (point.start, point.title))
+        .collect();
+    let tracks = tracks_from_timestamps(&points).map_err(|e| (
+        StatusCode::BAD_REQUEST,
+        Json(ErrorResponse::new("invalid_timestamps", e)),
+    ))?;
+
+    if state.get_download(&id).await.is_none() {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::new("not_found", "Download not found")),
+        ));
+    }
+
+    match state.split_download(&id, &tracks).await {
+        Ok(children) => Ok(Json(children)),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::new("split_error", format!("Split failed: {}", e))),
+        )),
+    }
+}
+
 pub async fn toggle_favorite(
     State(state): State<AppState>,
     Path(id): Path<String>,
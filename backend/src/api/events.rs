@@ -0,0 +1,55 @@
+use axum::{
+    extract::{Query, State},
+    response::sse::{Event, KeepAlive, Sse},
+};
+use futures::stream::{self, Stream};
+use serde::Deserialize;
+use std::convert::Infallible;
+use tokio::sync::broadcast::error::RecvError;
+use crate::state::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct EventsQuery {
+    /// Only stream events for this download
+    pub download_id: Option<String>,
+}
+
+/// Server-Sent Events stream of download changes.
+///
+/// Each SSE `event:` is the event type (created, progress, status, completed,
+/// failed, updated, deleted) and `data:` the JSON event. A `resync` event is
+/// sent when the client fell behind and should refetch `/api/downloads`.
+pub async fn stream_download_events(
+    State(state): State<AppState>,
+    Query(query): Query<EventsQuery>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let receiver = state.subscribe_events();
+    let filter = query.download_id;
+
+    let events = stream::unfold(receiver, move |mut receiver| {
+        let filter = filter.clone();
+        async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => {
+                        if filter.as_ref().is_some_and(|id| *id != event.download_id) {
+                            continue;
+                        }
+                        let sse_event = Event::default()
+                            .event(event.kind.as_str())
+                            .json_data(&event)
+                            .unwrap_or_else(|_| Event::default().event("error"));
+                        return Some((Ok(sse_event), receiver));
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!("Event stream subscriber lagged, {} events skipped", skipped);
+                        return Some((Ok(Event::default().event("resync").data(skipped.to_string())), receiver));
+                    }
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        }
+    });
+
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
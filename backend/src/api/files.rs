@@ -100,62 +100,20 @@ pub async fn serve_file(
 
     tracing::info!("Serving file: {} (full path: {})", file_path, full_path.display());
 
-    // Check if file exists, if not try to find alternative extensions
-    let mut actual_path = full_path.clone();
-    if !actual_path.exists() {
-        // Try to find file with different extensions (common case: .f251.webm -> .mp4 or .webm)
-        let parent_opt = actual_path.parent().map(|p| p.to_path_buf());
-        let stem_opt = actual_path.file_stem().map(|s| s.to_string_lossy().to_string());
-        
-        if let (Some(parent), Some(stem_str)) = (parent_opt, stem_opt) {
-            // Remove .f251 or similar format codes from stem
-            let clean_stem = stem_str
-                .replace(".f251", "")
-                .replace(".f140", "")
-                .replace(".f137", "")
-                .replace(".f248", "")
-                .replace(".f249", "")
-                .replace(".f250", "");
-            
-            // Try common video/audio extensions
-            let extensions = ["mp4", "webm", "mkv", "mp3", "m4a", "wav", "flac"];
-            for ext in &extensions {
-                let candidate = parent.join(format!("{}.{}", clean_stem, ext));
-                if candidate.exists() {
-                    tracing::info!("Found alternative file: {} -> {}", full_path.display(), candidate.display());
-                    actual_path = candidate;
-                    break;
-                }
-            }
-            
-            // If still not found, try with original stem but different extensions
-            if !actual_path.exists() {
-                for ext in &extensions {
-                    let candidate = parent.join(format!("{}.{}", stem_str, ext));
-                    if candidate.exists() {
-                        tracing::info!("Found alternative file with original stem: {} -> {}", full_path.display(), candidate.display());
-                        actual_path = candidate;
-                        break;
-                    }
-                }
-            }
-        }
-    }
-
-    // Final check if file exists
-    if !actual_path.exists() {
-        tracing::error!("File not found: {} (searched at: {})", file_path, actual_path.display());
+    // file_path is the exact output reported by yt-dlp (older rows may hold a directory)
+    if !full_path.is_file() {
+        tracing::error!("File not found: {} (searched at: {})", file_path, full_path.display());
         return Err((
             StatusCode::NOT_FOUND,
-            axum::Json(ErrorResponse::new("file_not_found", &format!("File does not exist on disk: {}. Searched at: {}", file_path, actual_path.display()))),
+            axum::Json(ErrorResponse::new("file_not_found", &format!("File does not exist on disk: {}. Searched at: {}", file_path, full_path.display()))),
         ));
     }
 
-    // Determine content type based on file extension (use actual_path, not full_path)
-    let content_type = get_content_type(&actual_path);
+    // Determine content type based on file extension
+    let content_type = get_content_type(&full_path);
 
-    // Open file for streaming (use actual_path, not full_path)
-    match tokio::fs::File::open(&actual_path).await {
+    // Open file for streaming
+    match tokio::fs::File::open(&full_path).await {
         Ok(file) => {
             // Get file metadata for content length
             let metadata = file.metadata().await.map_err(|e| {
@@ -187,8 +145,8 @@ pub async fn serve_file(
             };
             
             // Reopen file and seek to start position
-            let mut file = tokio::fs::File::open(&actual_path).await.map_err(|e| {
-                tracing::error!("Failed to reopen file {}: {}", actual_path.display(), e);
+            let mut file = tokio::fs::File::open(&full_path).await.map_err(|e| {
+                tracing::error!("Failed to reopen file {}: {}", full_path.display(), e);
                 (
                     StatusCode::INTERNAL_SERVER_ERROR,
                     axum::Json(ErrorResponse::new("read_error", "Failed to open file")),
@@ -6,12 +6,22 @@ pub mod config;
 pub mod tags;
 pub mod statistics;
 pub mod webhooks;
+pub mod events;
+pub mod schedules;
+pub mod subscriptions;
+pub mod presets;
+pub mod retention;
 
-pub use download::{create_download, create_batch_downloads, get_download, list_downloads, get_all_downloads, delete_download, update_metadata, convert_download, toggle_favorite, export_downloads, import_downloads};
+pub use download::{create_download, create_batch_downloads, get_download, get_playlist_items, list_downloads, get_all_downloads, get_queue, delete_download, cancel_download, pause_download, resume_download, retry_download, update_metadata, convert_download, split_download, toggle_favorite, export_downloads, import_downloads};
 pub use video::get_video_info_endpoint;
 pub use logs::get_logs;
 pub use files::serve_file;
-pub use config::{get_config_info, get_disk_info, get_disclaimer, get_license};
+pub use config::{get_config_info, get_disk_info, get_bandwidth, update_bandwidth, get_disclaimer, get_license};
 pub use tags::{create_tag, get_tag, list_tags, update_tag, delete_tag, get_download_tags, add_tag_to_download, remove_tag_from_download, set_download_tags};
 pub use statistics::get_statistics;
 pub use webhooks::{create_webhook, list_webhooks, delete_webhook};
+pub use events::stream_download_events;
+pub use schedules::{list_scheduled, reschedule_download, cancel_scheduled};
+pub use subscriptions::{create_subscription, list_subscriptions, get_subscription, update_subscription, delete_subscription, check_subscription_now, get_subscription_history};
+pub use presets::{list_presets, get_preset, create_preset, update_preset, delete_preset};
+pub use retention::{list_retention_rules, get_retention_rule, create_retention_rule, update_retention_rule, delete_retention_rule, dry_run_retention_rule, run_retention_rule, list_retention_reports};
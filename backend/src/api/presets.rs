@@ -0,0 +1,140 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use crate::{
+    models::{CreatePresetRequest, DownloadRequest, ErrorResponse, UpdatePresetRequest},
+    state::AppState,
+    validation::validate_rate_limit,
+};
+use rust_media_downloader_shared::{output_template, Preset};
+
+type ApiError = (StatusCode, Json<ErrorResponse>);
+
+/// Built-in presets followed by user presets
+pub async fn list_presets(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<Preset>>, ApiError> {
+    state.get_all_presets().await.map(Json).map_err(storage_error)
+}
+
+pub async fn get_preset(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Preset>, ApiError> {
+    find(&state, &id).await.map(Json)
+}
+
+pub async fn create_preset(
+    State(state): State<AppState>,
+    Json(request): Json<CreatePresetRequest>,
+) -> Result<Json<Preset>, ApiError> {
+    let preset = Preset {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: request.name.trim().to_string(),
+        description: request.description.filter(|d| !d.trim().is_empty()),
+        kind: request.kind,
+        options: request.options,
+        builtin: false,
+    };
+    validate_preset(&preset)?;
+    state.create_preset(&preset).await.map_err(storage_error)?;
+
+    Ok(Json(preset))
+}
+
+pub async fn update_preset(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(request): Json<UpdatePresetRequest>,
+) -> Result<Json<Preset>, ApiError> {
+    let mut preset = find_editable(&state, &id).await?;
+
+    if let Some(name) = request.name {
+        preset.name = name.trim().to_string();
+    }
+    if let Some(description) = request.description {
+        preset.description = Some(description).filter(|d| !d.trim().is_empty());
+    }
+    if let Some(options) = request.options {
+        preset.options = options;
+    }
+    validate_preset(&preset)?;
+    state.update_preset(&preset).await.map_err(storage_error)?;
+
+    Ok(Json(preset))
+}
+
+pub async fn delete_preset(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    // Requests already submitted with the preset keep the options they were resolved with
+    find_editable(&state, &id).await?;
+    state.delete_preset(&id).await.map_err(storage_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Resolve `request.preset_id` into the request's unset fields before it is validated
+pub async fn apply_preset(state: &AppState, request: &mut DownloadRequest) -> Result<(), ApiError> {
+    let Some(preset_id) = request.preset_id.clone() else {
+        return Ok(());
+    };
+    let preset = state.get_preset(&preset_id).await.map_err(storage_error)?.ok_or_else(|| (
+        StatusCode::BAD_REQUEST,
+        Json(ErrorResponse::new("invalid_preset", format!("Preset '{}' does not exist", preset_id))),
+    ))?;
+
+    request.apply_preset(&preset).map_err(|e| (
+        StatusCode::BAD_REQUEST,
+        Json(ErrorResponse::new("invalid_preset", e)),
+    ))
+}
+
+async fn find(state: &AppState, id: &str) -> Result<Preset, ApiError> {
+    state.get_preset(id).await.map_err(storage_error)?.ok_or_else(|| (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse::new("not_found", "Preset not found")),
+    ))
+}
+
+async fn find_editable(state: &AppState, id: &str) -> Result<Preset, ApiError> {
+    let preset = find(state, id).await?;
+    if preset.builtin {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(ErrorResponse::new("builtin_preset", "Built-in presets cannot be modified; create a new preset instead")),
+        ));
+    }
+    Ok(preset)
+}
+
+fn validate_preset(preset: &Preset) -> Result<(), ApiError> {
+    let invalid = |error: &str, message: String| (
+        StatusCode::BAD_REQUEST,
+        Json(ErrorResponse::new(error, message)),
+    );
+
+    if preset.name.is_empty() {
+        return Err(invalid("validation_error", "Preset name cannot be empty".to_string()));
+    }
+    if let Err(e) = preset.format_selector() {
+        return Err(invalid("invalid_format_options", e.to_string()));
+    }
+    if let Some(Err(e)) = preset.options.rate_limit.as_deref().map(validate_rate_limit) {
+        return Err(invalid("invalid_rate_limit", e.message));
+    }
+    if let Some(Err(e)) = preset.options.output_template.as_deref().map(output_template::validate_template) {
+        return Err(invalid("invalid_output_template", e));
+    }
+    Ok(())
+}
+
+fn storage_error(e: anyhow::Error) -> ApiError {
+    tracing::error!("Preset storage failed: {}", e);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ErrorResponse::new("database_error", format!("Preset storage failed: {}", e))),
+    )
+}
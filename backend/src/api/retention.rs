@@ -0,0 +1,141 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use crate::{
+    models::{CreateRetentionRuleRequest, ErrorResponse, RetentionReport, RetentionRule, UpdateRetentionRuleRequest},
+    retention::validate_criteria,
+    state::AppState,
+};
+
+type ApiError = (StatusCode, Json<ErrorResponse>);
+
+pub async fn list_retention_rules(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<RetentionRule>>, ApiError> {
+    state.get_all_retention_rules().await.map(Json).map_err(storage_error)
+}
+
+pub async fn get_retention_rule(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<RetentionRule>, ApiError> {
+    find(&state, &id).await.map(Json)
+}
+
+pub async fn create_retention_rule(
+    State(state): State<AppState>,
+    Json(request): Json<CreateRetentionRuleRequest>,
+) -> Result<Json<RetentionRule>, ApiError> {
+    let rule = RetentionRule {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: request.name.trim().to_string(),
+        enabled: request.enabled.unwrap_or(false),
+        criteria: request.criteria,
+        created_at: chrono::Utc::now(),
+        last_run_at: None,
+    };
+    validate_rule(&state, &rule).await?;
+    state.create_retention_rule(&rule).await.map_err(storage_error)?;
+
+    Ok(Json(rule))
+}
+
+pub async fn update_retention_rule(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(request): Json<UpdateRetentionRuleRequest>,
+) -> Result<Json<RetentionRule>, ApiError> {
+    let mut rule = find(&state, &id).await?;
+
+    if let Some(name) = request.name {
+        rule.name = name.trim().to_string();
+    }
+    if let Some(enabled) = request.enabled {
+        rule.enabled = enabled;
+    }
+    if let Some(criteria) = request.criteria {
+        rule.criteria = criteria;
+    }
+    validate_rule(&state, &rule).await?;
+    state.update_retention_rule(&rule).await.map_err(storage_error)?;
+
+    Ok(Json(rule))
+}
+
+pub async fn delete_retention_rule(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    match state.delete_retention_rule(&id).await.map_err(storage_error)? {
+        true => Ok(StatusCode::NO_CONTENT),
+        false => Err(not_found()),
+    }
+}
+
+/// What the rule would remove right now, without touching anything (works on disabled rules)
+pub async fn dry_run_retention_rule(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<RetentionReport>, ApiError> {
+    let mut rule = find(&state, &id).await?;
+    state.run_retention_rule(&mut rule, true).await.map(Json).map_err(storage_error)
+}
+
+/// Apply the rule now instead of waiting for the cleaner
+pub async fn run_retention_rule(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<RetentionReport>, ApiError> {
+    let mut rule = find(&state, &id).await?;
+    state.run_retention_rule(&mut rule, false).await.map(Json).map_err(storage_error)
+}
+
+/// Reports of the latest cleanup runs, newest first
+pub async fn list_retention_reports(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<RetentionReport>>, ApiError> {
+    state.get_retention_reports().await.map(Json).map_err(storage_error)
+}
+
+async fn find(state: &AppState, id: &str) -> Result<RetentionRule, ApiError> {
+    state.get_retention_rule(id).await.map_err(storage_error)?.ok_or_else(not_found)
+}
+
+async fn validate_rule(state: &AppState, rule: &RetentionRule) -> Result<(), ApiError> {
+    let invalid = |message: String| (
+        StatusCode::BAD_REQUEST,
+        Json(ErrorResponse::new("validation_error", message)),
+    );
+
+    if rule.name.is_empty() {
+        return Err(invalid("Rule name cannot be empty".to_string()));
+    }
+    validate_criteria(&rule.criteria).map_err(invalid)?;
+
+    if let Some(tag_id) = &rule.criteria.tag_id {
+        if state.get_tag(tag_id).await.is_none() {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse::new("invalid_tag", format!("Tag '{}' does not exist", tag_id))),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn not_found() -> ApiError {
+    (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse::new("not_found", "Retention rule not found")),
+    )
+}
+
+fn storage_error(e: anyhow::Error) -> ApiError {
+    tracing::error!("Retention storage failed: {}", e);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ErrorResponse::new("database_error", format!("Retention storage failed: {}", e))),
+    )
+}
@@ -0,0 +1,90 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use chrono::Utc;
+use crate::{
+    models::{DownloadResponse, DownloadStatus, ErrorResponse, RescheduleRequest},
+    scheduler::resolve_schedule,
+    state::AppState,
+};
+
+/// Downloads waiting for their scheduled time, soonest first
+pub async fn list_scheduled(
+    State(state): State<AppState>,
+) -> Json<Vec<DownloadResponse>> {
+    Json(state.get_scheduled_downloads().await)
+}
+
+/// Change when a scheduled download runs and/or its recurrence
+pub async fn reschedule_download(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(request): Json<RescheduleRequest>,
+) -> Result<Json<DownloadResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let mut download = get_scheduled(&state, &id).await?;
+
+    if request.scheduled_at.is_none() && request.recurrence.is_none() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new("invalid_schedule", "Provide scheduled_at and/or recurrence")),
+        ));
+    }
+
+    // An empty recurrence turns a recurring download into a one-off
+    let recurrence = match request.recurrence {
+        Some(recurrence) => Some(recurrence).filter(|r| !r.trim().is_empty()),
+        None => download.recurrence.clone(),
+    };
+    let now = Utc::now();
+    let scheduled_at = resolve_schedule(request.scheduled_at, recurrence.as_deref(), now)
+        .map_err(|e| (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new("invalid_schedule", e)),
+        ))?
+        // A time in the past runs on the next scheduler pass
+        .unwrap_or(now);
+
+    download.scheduled_at = Some(scheduled_at);
+    download.recurrence = recurrence;
+    download.message = format!("Scheduled for {}", scheduled_at.to_rfc3339());
+    state.update_download(&id, download.clone()).await;
+
+    Ok(Json(download))
+}
+
+/// Cancel a scheduled download (and with it the following runs of a recurrence)
+pub async fn cancel_scheduled(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<DownloadResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let mut download = get_scheduled(&state, &id).await?;
+
+    state.discard_queued_download(&id).await.map_err(|e| {
+        tracing::error!("Failed to cancel scheduled download {}: {}", id, e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::new("queue_error", format!("Queue operation failed: {}", e))),
+        )
+    })?;
+    download.set_status(DownloadStatus::Cancelled, "Scheduled download cancelled".to_string());
+    state.update_download(&id, download.clone()).await;
+
+    Ok(Json(download))
+}
+
+async fn get_scheduled(state: &AppState, id: &str) -> Result<DownloadResponse, (StatusCode, Json<ErrorResponse>)> {
+    let download = state.get_download(id).await.ok_or_else(|| (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse::new("not_found", "Download not found")),
+    ))?;
+
+    if download.status != DownloadStatus::Scheduled {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(ErrorResponse::new("invalid_state", "Download is not scheduled")),
+        ));
+    }
+    Ok(download)
+}
@@ -0,0 +1,170 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use crate::{
+    api::presets::apply_preset,
+    models::{CreateSubscriptionRequest, DownloadResponse, ErrorResponse, Subscription, SubscriptionCheckResult, UpdateSubscriptionRequest, DownloadRequest},
+    state::AppState,
+    subscriptions::{DEFAULT_CHECK_INTERVAL_SECS, MIN_CHECK_INTERVAL_SECS},
+    validation::{validate_rate_limit, validate_url},
+    worker::format_selector,
+};
+use rust_media_downloader_shared::output_template;
+
+type ApiError = (StatusCode, Json<ErrorResponse>);
+
+pub async fn create_subscription(
+    State(state): State<AppState>,
+    Json(mut request): Json<CreateSubscriptionRequest>,
+) -> Result<Json<Subscription>, ApiError> {
+    // The preset is resolved once: later edits to it do not change the subscription
+    apply_preset(&state, &mut request.request).await?;
+    validate_template(&request.request)?;
+    let check_interval_secs = validate_interval(request.check_interval_secs.unwrap_or(DEFAULT_CHECK_INTERVAL_SECS))?;
+
+    let subscription = Subscription {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: request.name,
+        request: request.request,
+        check_interval_secs,
+        enabled: request.enabled.unwrap_or(true),
+        download_existing: request.download_existing.unwrap_or(false),
+        created_at: chrono::Utc::now(),
+        last_checked_at: None,
+        last_error: None,
+    };
+    state.create_subscription(&subscription).await.map_err(storage_error)?;
+
+    Ok(Json(subscription))
+}
+
+pub async fn list_subscriptions(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<Subscription>>, ApiError> {
+    state.get_all_subscriptions().await.map(Json).map_err(storage_error)
+}
+
+pub async fn get_subscription(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Subscription>, ApiError> {
+    find(&state, &id).await.map(Json)
+}
+
+pub async fn update_subscription(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(request): Json<UpdateSubscriptionRequest>,
+) -> Result<Json<Subscription>, ApiError> {
+    let mut subscription = find(&state, &id).await?;
+
+    if let Some(name) = request.name {
+        subscription.name = Some(name).filter(|n| !n.trim().is_empty());
+    }
+    if let Some(interval) = request.check_interval_secs {
+        subscription.check_interval_secs = validate_interval(interval)?;
+    }
+    if let Some(enabled) = request.enabled {
+        subscription.enabled = enabled;
+    }
+    if let Some(mut options) = request.options {
+        apply_preset(&state, &mut options).await?;
+        validate_template(&options)?;
+        subscription.request = options;
+    }
+    state.update_subscription(&subscription).await.map_err(storage_error)?;
+
+    Ok(Json(subscription))
+}
+
+pub async fn delete_subscription(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    // Downloads already created by the subscription are kept
+    match state.delete_subscription(&id).await.map_err(storage_error)? {
+        true => Ok(StatusCode::NO_CONTENT),
+        false => Err(not_found()),
+    }
+}
+
+/// Poll the subscription now instead of waiting for its interval
+pub async fn check_subscription_now(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<SubscriptionCheckResult>, ApiError> {
+    let mut subscription = find(&state, &id).await?;
+
+    state.check_subscription(&mut subscription).await.map(Json).map_err(|e| (
+        StatusCode::BAD_GATEWAY,
+        Json(ErrorResponse::new("check_failed", format!("Failed to list subscription entries: {}", e))),
+    ))
+}
+
+/// Downloads created by the subscription, newest first
+pub async fn get_subscription_history(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<DownloadResponse>>, ApiError> {
+    find(&state, &id).await?;
+    state.get_subscription_history(&id).await.map(Json).map_err(storage_error)
+}
+
+async fn find(state: &AppState, id: &str) -> Result<Subscription, ApiError> {
+    state.get_subscription(id).await.ok_or_else(not_found)
+}
+
+fn validate_template(request: &DownloadRequest) -> Result<(), ApiError> {
+    if let Err(e) = validate_url(&request.url) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new("validation_error", e.message)),
+        ));
+    }
+    if let Some(Err(e)) = request.rate_limit.as_deref().map(validate_rate_limit) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new("invalid_rate_limit", e.message)),
+        ));
+    }
+    if let Some(Err(e)) = request.output_template.as_deref().map(output_template::validate_template) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new("invalid_output_template", e)),
+        ));
+    }
+    format_selector(request).map(|_| ()).map_err(|e| (
+        StatusCode::BAD_REQUEST,
+        Json(ErrorResponse::new("invalid_format_options", e.to_string())),
+    ))
+}
+
+fn validate_interval(interval: u64) -> Result<u64, ApiError> {
+    if interval < MIN_CHECK_INTERVAL_SECS {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(
+                "validation_error",
+                format!("check_interval_secs must be at least {}", MIN_CHECK_INTERVAL_SECS),
+            )),
+        ));
+    }
+    Ok(interval)
+}
+
+fn not_found() -> ApiError {
+    (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse::new("not_found", "Subscription not found")),
+    )
+}
+
+fn storage_error(e: anyhow::Error) -> ApiError {
+    tracing::error!("Subscription storage failed: {}", e);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ErrorResponse::new("database_error", format!("Subscription storage failed: {}", e))),
+    )
+}
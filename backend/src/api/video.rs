@@ -4,7 +4,7 @@ use axum::{
     response::Json,
 };
 use serde::Deserialize;
-use rust_media_downloader_shared::{get_video_info, VideoInfo};
+use rust_media_downloader_shared::VideoInfo;
 use crate::models::ErrorResponse;
 use crate::state::AppState;
 
@@ -43,7 +43,7 @@ pub async fn get_video_info_endpoint(
     }
 
     // Fetch from API
-    match get_video_info(&params.url, params.cookies_browser.as_deref()).await {
+    match state.media_backend().fetch_info(&params.url, params.cookies_browser.as_deref()).await {
         Ok(info) => {
             // Store in cache
             cache.set(params.url, info.clone()).await;
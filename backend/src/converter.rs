@@ -3,6 +3,8 @@ use tokio::process::Command;
 use tokio::io::{BufReader, AsyncBufReadExt};
 use anyhow::{Result, Context};
 use tracing::{info, error};
+use rust_media_downloader_shared::Chapter;
+use rust_media_downloader_shared::output_template::sanitize_component;
 
 /// Supported conversion formats
 #[derive(Debug, Clone, Copy)]
@@ -226,6 +228,119 @@ pub async fn convert_file(
     Ok(output_path)
 }
 
+/// One output file of a split: from `start` to `end` in seconds, `None` runs to the end of the input
+#[derive(Debug, Clone, PartialEq)]
+pub struct SplitTrack {
+    pub start: f64,
+    pub end: Option<f64>,
+    pub title: String,
+}
+
+/// Tracks following the video's chapters; untitled chapters are numbered
+pub fn tracks_from_chapters(chapters: &[Chapter]) -> Vec<SplitTrack> {
+    chapters.iter().enumerate().map(|(index, chapter)| SplitTrack {
+        start: chapter.start_time,
+        end: chapter.end_time.or_else(|| chapters.get(index + 1).map(|next| next.start_time)),
+        title: chapter.title.clone().filter(|t| !t.trim().is_empty()).unwrap_or_else(|| format!("Track {:02}", index + 1)),
+    }).collect()
+}
+
+/// Tracks starting at each timestamp and ending at the next one, the last runs to the end of the file.
+///
+/// Timestamps are `hh:mm:ss`, `mm:ss` or seconds (fractions allowed) and must be strictly increasing.
+pub fn tracks_from_timestamps(points: &[(String, Option<String>)]) -> Result<Vec<SplitTrack>, String> {
+    let mut starts = Vec::with_capacity(points.len());
+    for (timestamp, _) in points {
+        let start = parse_timestamp(timestamp).ok_or_else(|| format!("Invalid timestamp '{}' (expected hh:mm:ss, mm:ss or seconds)", timestamp))?;
+        if starts.last().is_some_and(|previous| start <= *previous) {
+            return Err(format!("Timestamp '{}' must come after the previous one", timestamp));
+        }
+        starts.push(start);
+    }
+
+    Ok(points.iter().enumerate().map(|(index, (_, title))| SplitTrack {
+        start: starts[index],
+        end: starts.get(index + 1).copied(),
+        title: title.clone().filter(|t| !t.trim().is_empty()).unwrap_or_else(|| format!("Track {:02}", index + 1)),
+    }).collect())
+}
+
+/// `1:02:03.5`, `02:03` or `123.5` in seconds
+pub fn parse_timestamp(timestamp: &str) -> Option<f64> {
+    let parts: Vec<&str> = timestamp.trim().split(':').collect();
+    if parts.len() > 3 || parts.iter().any(|part| part.is_empty()) {
+        return None;
+    }
+    let (seconds, whole) = parts.split_last()?;
+    let mut total = 0.0;
+    for part in whole {
+        let value: u32 = part.parse().ok()?;
+        total = total * 60.0 + value as f64;
+    }
+    let seconds: f64 = seconds.parse().ok().filter(|s: &f64| s.is_finite() && *s >= 0.0)?;
+    if !whole.is_empty() && seconds >= 60.0 {
+        return None;
+    }
+    Some(total * 60.0 + seconds)
+}
+
+/// Cut a media file into one file per track with ffmpeg, without re-encoding.
+///
+/// The tracks land in a folder named after the input, as `NN - title.ext`, tagged with
+/// their title, track number and the input's title as album.
+pub async fn split_file(input_path: &Path, tracks: &[SplitTrack], album: Option<&str>, max_filename_length: usize) -> Result<Vec<PathBuf>> {
+    if !input_path.exists() {
+        anyhow::bail!("Input file does not exist: {}", input_path.display());
+    }
+    let stem = input_path.file_stem().and_then(|s| s.to_str()).unwrap_or("tracks");
+    let extension = get_extension(input_path).unwrap_or_else(|| "mkv".to_string());
+    let output_dir = input_path.with_file_name(sanitize_component(stem, max_filename_length));
+    std::fs::create_dir_all(&output_dir)
+        .with_context(|| format!("Failed to create {}", output_dir.display()))?;
+
+    info!("Splitting {} into {} tracks", input_path.display(), tracks.len());
+
+    let mut outputs = Vec::with_capacity(tracks.len());
+    for (index, track) in tracks.iter().enumerate() {
+        let number = index + 1;
+        let name = sanitize_component(&format!("{:02} - {}", number, track.title), max_filename_length.saturating_sub(extension.len() + 1));
+        let output_path = output_dir.join(format!("{}.{}", name, extension));
+
+        let mut command = Command::new("ffmpeg");
+        command.args(["-hide_banner", "-loglevel", "error", "-y"]);
+        // Seeking before -i is fast; with stream copy cuts land on the nearest keyframe
+        command.arg("-ss").arg(track.start.to_string());
+        if let Some(end) = track.end {
+            command.arg("-t").arg((end - track.start).to_string());
+        }
+        command.arg("-i").arg(input_path);
+        command.args(["-map", "0", "-c", "copy", "-map_metadata", "0", "-map_chapters", "-1"]);
+        command.arg("-metadata").arg(format!("title={}", track.title));
+        command.arg("-metadata").arg(format!("track={}/{}", number, tracks.len()));
+        if let Some(album) = album {
+            command.arg("-metadata").arg(format!("album={}", album));
+        }
+        command.arg(&output_path);
+
+        let output = match command.output().await {
+            Ok(output) => output,
+            Err(e) => {
+                error!("Failed to spawn ffmpeg process: {}. Is ffmpeg installed?", e);
+                anyhow::bail!("Failed to start ffmpeg: {}. Make sure ffmpeg is installed and in your PATH.", e);
+            }
+        };
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            error!("ffmpeg split failed on track {}: {}", number, stderr);
+            anyhow::bail!("ffmpeg split failed on track {} (exit code {}): {}", number, output.status.code().unwrap_or(-1), stderr.trim());
+        }
+        outputs.push(output_path);
+    }
+
+    info!("Split completed: {} tracks in {}", outputs.len(), output_dir.display());
+    Ok(outputs)
+}
+
 /// Get available conversion formats based on input file type
 #[allow(dead_code)]
 pub fn get_available_formats(input_path: &Path) -> Vec<ConversionFormat> {
@@ -247,3 +362,38 @@ pub fn get_available_formats(input_path: &Path) -> Vec<ConversionFormat> {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_timestamp() {
+        assert_eq!(parse_timestamp("1:02:03.5"), Some(3723.5));
+        assert_eq!(parse_timestamp("02:03"), Some(123.0));
+        assert_eq!(parse_timestamp(" 95 "), Some(95.0));
+        assert_eq!(parse_timestamp("1:75"), None);
+        assert_eq!(parse_timestamp("1::03"), None);
+        assert_eq!(parse_timestamp("-5"), None);
+        assert_eq!(parse_timestamp("1:2:3:4"), None);
+    }
+
+    #[test]
+    fn test_tracks_from_timestamps_and_chapters() {
+        let points = vec![("0:00".to_string(), Some("Opening".to_string())), ("3:10".to_string(), None)];
+        assert_eq!(tracks_from_timestamps(&points).unwrap(), vec![
+            SplitTrack { start: 0.0, end: Some(190.0), title: "Opening".to_string() },
+            SplitTrack { start: 190.0, end: None, title: "Track 02".to_string() },
+        ]);
+        let unordered = vec![("3:10".to_string(), None), ("1:00".to_string(), None)];
+        assert!(tracks_from_timestamps(&unordered).is_err());
+
+        let chapters = vec![
+            Chapter { start_time: 0.0, end_time: None, title: Some("Intro".to_string()) },
+            Chapter { start_time: 60.0, end_time: Some(90.0), title: None },
+        ];
+        assert_eq!(tracks_from_chapters(&chapters), vec![
+            SplitTrack { start: 0.0, end: Some(60.0), title: "Intro".to_string() },
+            SplitTrack { start: 60.0, end: Some(90.0), title: "Track 02".to_string() },
+        ]);
+    }
+}
@@ -1,7 +1,9 @@
 use sqlx::{SqlitePool, sqlite::SqlitePoolOptions};
-use crate::models::{DownloadResponse, DownloadStatus, DownloadType, Tag, DownloadTrendPoint, TypeDistribution, StatusDistribution, SpaceEvolutionPoint, StatisticsResponse};
+use crate::models::{DownloadRequest, DownloadResponse, QueuedJob, RetentionReport, RetentionRule, Subscription, DownloadStatus, DownloadType, Tag, DownloadTrendPoint, TypeDistribution, StatusDistribution, SpaceEvolutionPoint, StatisticsResponse};
 use anyhow::Result;
+use rust_media_downloader_shared::{builtin_presets, DownloadError, MediaId, Preset, PresetKind};
 
+#[derive(Clone)]
 pub struct Database {
     pool: SqlitePool,
 }
@@ -12,6 +14,26 @@ impl Database {
             .max_connections(5)
             .connect(database_url)
             .await?;
+        Self::open(pool).await
+    }
+
+    /// Private database for a test, gone with its last handle
+    #[cfg(test)]
+    pub async fn in_memory() -> Self {
+        // Each connection to `sqlite::memory:` has its own database: keep a single one alive
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .min_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect("sqlite::memory:")
+            .await
+            .expect("Failed to open in-memory database");
+        Self::open(pool).await.expect("Failed to create test database")
+    }
+
+    /// Create or migrate the schema, then seed the built-in presets
+    async fn open(pool: SqlitePool) -> Result<Self> {
 
         // Run migrations
         sqlx::query(
@@ -53,6 +75,24 @@ impl Database {
         let _ = sqlx::query("ALTER TABLE downloads ADD COLUMN notes TEXT").execute(&pool).await;
         let _ = sqlx::query("ALTER TABLE downloads ADD COLUMN original_file_path TEXT").execute(&pool).await;
         let _ = sqlx::query("ALTER TABLE downloads ADD COLUMN is_favorite BOOLEAN DEFAULT 0").execute(&pool).await;
+        let _ = sqlx::query("ALTER TABLE downloads ADD COLUMN downloaded_bytes INTEGER").execute(&pool).await;
+        let _ = sqlx::query("ALTER TABLE downloads ADD COLUMN total_bytes INTEGER").execute(&pool).await;
+        let _ = sqlx::query("ALTER TABLE downloads ADD COLUMN speed REAL").execute(&pool).await;
+        let _ = sqlx::query("ALTER TABLE downloads ADD COLUMN eta INTEGER").execute(&pool).await;
+        let _ = sqlx::query("ALTER TABLE downloads ADD COLUMN output_files TEXT").execute(&pool).await;
+        let _ = sqlx::query("ALTER TABLE downloads ADD COLUMN parent_id TEXT").execute(&pool).await;
+        let _ = sqlx::query("ALTER TABLE downloads ADD COLUMN playlist_index INTEGER").execute(&pool).await;
+        let _ = sqlx::query("ALTER TABLE downloads ADD COLUMN scheduled_at TEXT").execute(&pool).await;
+        let _ = sqlx::query("ALTER TABLE downloads ADD COLUMN recurrence TEXT").execute(&pool).await;
+        let _ = sqlx::query("ALTER TABLE downloads ADD COLUMN extractor TEXT").execute(&pool).await;
+        let _ = sqlx::query("ALTER TABLE downloads ADD COLUMN video_id TEXT").execute(&pool).await;
+        let _ = sqlx::query("ALTER TABLE downloads ADD COLUMN duplicate_of TEXT").execute(&pool).await;
+        let _ = sqlx::query("ALTER TABLE downloads ADD COLUMN output_path TEXT").execute(&pool).await;
+        let _ = sqlx::query("ALTER TABLE downloads ADD COLUMN files_removed_at TEXT").execute(&pool).await;
+        let _ = sqlx::query("ALTER TABLE downloads ADD COLUMN error_code TEXT").execute(&pool).await;
+        let _ = sqlx::query("ALTER TABLE downloads ADD COLUMN error_message TEXT").execute(&pool).await;
+        let _ = sqlx::query("ALTER TABLE downloads ADD COLUMN post_processing TEXT").execute(&pool).await;
+        let _ = sqlx::query("CREATE INDEX IF NOT EXISTS idx_downloads_media ON downloads (video_id, extractor)").execute(&pool).await;
         // Initialize existing rows to 0 (false) for is_favorite
         let _ = sqlx::query("UPDATE downloads SET is_favorite = 0 WHERE is_favorite IS NULL").execute(&pool).await;
 
@@ -108,14 +148,116 @@ impl Database {
         .execute(&database.pool)
         .await?;
 
+        // Create download_jobs table (persistent queue feeding the worker pool)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS download_jobs (
+                download_id TEXT PRIMARY KEY,
+                request TEXT NOT NULL,
+                priority INTEGER NOT NULL DEFAULT 0,
+                state TEXT NOT NULL DEFAULT 'queued',
+                enqueued_at TEXT NOT NULL,
+                FOREIGN KEY (download_id) REFERENCES downloads(id) ON DELETE CASCADE
+            )
+            "#
+        )
+        .execute(&database.pool)
+        .await?;
+        let _ = sqlx::query("ALTER TABLE download_jobs ADD COLUMN partial_files TEXT").execute(&database.pool).await;
+        // Retried jobs are not claimed before this time
+        let _ = sqlx::query("ALTER TABLE download_jobs ADD COLUMN available_at TEXT").execute(&database.pool).await;
+
+        // Create subscriptions table (channels/playlists polled for new uploads)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS subscriptions (
+                id TEXT PRIMARY KEY,
+                name TEXT,
+                request TEXT NOT NULL,
+                check_interval_secs INTEGER NOT NULL,
+                enabled BOOLEAN NOT NULL DEFAULT 1,
+                download_existing BOOLEAN NOT NULL DEFAULT 0,
+                created_at TEXT NOT NULL,
+                last_checked_at TEXT,
+                last_error TEXT
+            )
+            "#
+        )
+        .execute(&database.pool)
+        .await?;
+
+        // Per-subscription download archive: every entry seen, with the download it produced
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS subscription_entries (
+                subscription_id TEXT NOT NULL,
+                video_id TEXT NOT NULL,
+                download_id TEXT,
+                seen_at TEXT NOT NULL,
+                PRIMARY KEY (subscription_id, video_id),
+                FOREIGN KEY (subscription_id) REFERENCES subscriptions(id) ON DELETE CASCADE
+            )
+            "#
+        )
+        .execute(&database.pool)
+        .await?;
+
+        // Create presets table (reusable request options; built-in rows are refreshed on startup)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS presets (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                description TEXT,
+                preset_type TEXT NOT NULL,
+                options TEXT NOT NULL,
+                builtin BOOLEAN NOT NULL DEFAULT 0,
+                created_at TEXT NOT NULL
+            )
+            "#
+        )
+        .execute(&database.pool)
+        .await?;
+
+        // Create retention tables (library cleanup rules and the report of every run)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS retention_rules (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                enabled BOOLEAN NOT NULL DEFAULT 0,
+                criteria TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                last_run_at TEXT
+            )
+            "#
+        )
+        .execute(&database.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS retention_reports (
+                id TEXT PRIMARY KEY,
+                rule_id TEXT NOT NULL,
+                ran_at TEXT NOT NULL,
+                report TEXT NOT NULL
+            )
+            "#
+        )
+        .execute(&database.pool)
+        .await?;
+
+        database.seed_builtin_presets().await?;
+
         Ok(database)
     }
 
     pub async fn insert_download(&self, download: &DownloadResponse) -> Result<()> {
         sqlx::query(
             r#"
-            INSERT INTO downloads (id, url, download_type, status, progress, message, created_at, completed_at, file_path, is_playlist, total_items, completed_items, title, thumbnail, duration, author, file_size, retry_count, max_retries, notes, original_file_path, is_favorite)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            INSERT INTO downloads (id, url, download_type, status, progress, message, created_at, completed_at, file_path, is_playlist, total_items, completed_items, title, thumbnail, duration, author, file_size, retry_count, max_retries, notes, original_file_path, is_favorite, downloaded_bytes, total_bytes, speed, eta, output_files, parent_id, playlist_index, scheduled_at, recurrence, extractor, video_id, duplicate_of, output_path, files_removed_at, error_code, error_message, post_processing)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(&download.id)
@@ -140,6 +282,23 @@ impl Database {
         .bind(&download.notes)
         .bind(&download.original_file_path)
         .bind(download.is_favorite)
+        .bind(download.downloaded_bytes.map(|b| b as i64))
+        .bind(download.total_bytes.map(|b| b as i64))
+        .bind(download.speed)
+        .bind(download.eta.map(|e| e as i64))
+        .bind(string_list_to_json(&download.output_files)?)
+        .bind(&download.parent_id)
+        .bind(download.playlist_index.map(|i| i as i64))
+        .bind(download.scheduled_at.map(|dt| dt.to_rfc3339()))
+        .bind(&download.recurrence)
+        .bind(&download.extractor)
+        .bind(&download.video_id)
+        .bind(&download.duplicate_of)
+        .bind(&download.output_path)
+        .bind(download.files_removed_at.map(|dt| dt.to_rfc3339()))
+        .bind(download.error_code.map(|e| e.code()))
+        .bind(&download.error_message)
+        .bind(string_list_to_json(&download.post_processing)?)
         .execute(&self.pool)
         .await?;
 
@@ -150,7 +309,7 @@ impl Database {
         sqlx::query(
             r#"
             UPDATE downloads
-            SET status = ?, progress = ?, message = ?, completed_at = ?, file_path = ?, is_playlist = ?, total_items = ?, completed_items = ?, title = ?, thumbnail = ?, duration = ?, author = ?, file_size = ?, retry_count = ?, max_retries = ?, notes = ?, original_file_path = ?, is_favorite = ?
+            SET status = ?, progress = ?, message = ?, completed_at = ?, file_path = ?, is_playlist = ?, total_items = ?, completed_items = ?, title = ?, thumbnail = ?, duration = ?, author = ?, file_size = ?, retry_count = ?, max_retries = ?, notes = ?, original_file_path = ?, is_favorite = ?, downloaded_bytes = ?, total_bytes = ?, speed = ?, eta = ?, output_files = ?, scheduled_at = ?, recurrence = ?, extractor = ?, video_id = ?, output_path = ?, files_removed_at = ?, error_code = ?, error_message = ?, post_processing = ?
             WHERE id = ?
             "#,
         )
@@ -172,6 +331,20 @@ impl Database {
         .bind(&download.notes)
         .bind(&download.original_file_path)
         .bind(download.is_favorite)
+        .bind(download.downloaded_bytes.map(|b| b as i64))
+        .bind(download.total_bytes.map(|b| b as i64))
+        .bind(download.speed)
+        .bind(download.eta.map(|e| e as i64))
+        .bind(string_list_to_json(&download.output_files)?)
+        .bind(download.scheduled_at.map(|dt| dt.to_rfc3339()))
+        .bind(&download.recurrence)
+        .bind(&download.extractor)
+        .bind(&download.video_id)
+        .bind(&download.output_path)
+        .bind(download.files_removed_at.map(|dt| dt.to_rfc3339()))
+        .bind(download.error_code.map(|e| e.code()))
+        .bind(&download.error_message)
+        .bind(string_list_to_json(&download.post_processing)?)
         .bind(&download.id)
         .execute(&self.pool)
         .await?;
@@ -179,6 +352,27 @@ impl Database {
         Ok(())
     }
 
+    /// Write only the live progress columns, leaving status and metadata untouched
+    pub async fn update_progress(&self, id: &str, progress: f32, downloaded_bytes: Option<u64>, total_bytes: Option<u64>, speed: Option<f64>, eta: Option<u64>) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE downloads
+            SET progress = ?, downloaded_bytes = ?, total_bytes = ?, speed = ?, eta = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(progress)
+        .bind(downloaded_bytes.map(|b| b as i64))
+        .bind(total_bytes.map(|b| b as i64))
+        .bind(speed)
+        .bind(eta.map(|e| e as i64))
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
     pub async fn update_metadata(&self, id: &str, title: Option<&str>, author: Option<&str>, notes: Option<&str>) -> Result<()> {
         // Build dynamic query based on what fields are provided
         let mut query_parts = Vec::new();
@@ -237,6 +431,15 @@ impl Database {
         }
     }
 
+    /// Current status of a download without loading the whole row
+    pub async fn get_download_status(&self, id: &str) -> Result<Option<DownloadStatus>> {
+        let status = sqlx::query_scalar::<_, String>("SELECT status FROM downloads WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(status.map(|s| string_to_status(&s)))
+    }
+
     pub async fn get_all_downloads(&self) -> Result<Vec<DownloadResponse>> {
         let rows = sqlx::query_as::<_, DownloadRow>(
             "SELECT * FROM downloads ORDER BY created_at DESC"
@@ -295,10 +498,73 @@ impl Database {
         Ok(result.rows_affected() > 0)
     }
 
-    #[allow(dead_code)]
+    /// Entries of a playlist download, in playlist order
+    pub async fn get_child_downloads(&self, parent_id: &str) -> Result<Vec<DownloadResponse>> {
+        let rows = sqlx::query_as::<_, DownloadRow>(
+            "SELECT * FROM downloads WHERE parent_id = ? ORDER BY playlist_index ASC"
+        )
+        .bind(parent_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|r| r.into()).collect())
+    }
+
+    /// Earliest download of the same media that did not fail or get cancelled
+    pub async fn find_download_by_media(&self, media_id: &MediaId, download_type: &DownloadType) -> Result<Option<DownloadResponse>> {
+        let row = sqlx::query_as::<_, DownloadRow>(
+            r#"
+            SELECT * FROM downloads
+            WHERE video_id = ? AND extractor = ? COLLATE NOCASE AND download_type = ?
+              AND status NOT IN ('failed', 'cancelled')
+              AND files_removed_at IS NULL
+            ORDER BY created_at ASC
+            LIMIT 1
+            "#
+        )
+        .bind(&media_id.id)
+        .bind(&media_id.extractor)
+        .bind(download_type_to_string(download_type))
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|r| r.into()))
+    }
+
+    /// Total size of completed downloads, as counted against the library quota
+    pub async fn get_library_size(&self) -> Result<u64> {
+        let (size,): (i64,) = sqlx::query_as("SELECT COALESCE(SUM(file_size), 0) FROM downloads WHERE status = 'completed' AND files_removed_at IS NULL")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(size.max(0) as u64)
+    }
+
+    /// Scheduled downloads, soonest first
+    pub async fn get_scheduled_downloads(&self) -> Result<Vec<DownloadResponse>> {
+        let rows = sqlx::query_as::<_, DownloadRow>(
+            "SELECT * FROM downloads WHERE status = 'scheduled' ORDER BY scheduled_at ASC"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|r| r.into()).collect())
+    }
+
+    /// Scheduled downloads whose time has come
+    pub async fn get_due_scheduled_downloads(&self, now: chrono::DateTime<chrono::Utc>) -> Result<Vec<DownloadResponse>> {
+        let rows = sqlx::query_as::<_, DownloadRow>(
+            "SELECT * FROM downloads WHERE status = 'scheduled' AND scheduled_at <= ? ORDER BY scheduled_at ASC"
+        )
+        .bind(now.to_rfc3339())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|r| r.into()).collect())
+    }
+
     pub async fn get_active_downloads(&self) -> Result<Vec<DownloadResponse>> {
         let rows = sqlx::query_as::<_, DownloadRow>(
-            "SELECT * FROM downloads WHERE status IN ('pending', 'downloading', 'processing') ORDER BY created_at DESC"
+            "SELECT * FROM downloads WHERE status IN ('pending', 'downloading', 'processing', 'converting') ORDER BY created_at DESC"
         )
         .fetch_all(&self.pool)
         .await?;
@@ -331,6 +597,23 @@ struct DownloadRow {
     notes: Option<String>,
     original_file_path: Option<String>,
     is_favorite: Option<bool>,
+    downloaded_bytes: Option<i64>,
+    total_bytes: Option<i64>,
+    speed: Option<f64>,
+    eta: Option<i64>,
+    output_files: Option<String>,
+    parent_id: Option<String>,
+    playlist_index: Option<i64>,
+    scheduled_at: Option<String>,
+    recurrence: Option<String>,
+    extractor: Option<String>,
+    video_id: Option<String>,
+    duplicate_of: Option<String>,
+    output_path: Option<String>,
+    files_removed_at: Option<String>,
+    error_code: Option<String>,
+    error_message: Option<String>,
+    post_processing: Option<String>,
 }
 
 impl From<DownloadRow> for DownloadResponse {
@@ -365,10 +648,42 @@ impl From<DownloadRow> for DownloadResponse {
             tags: None, // Will be populated separately
             original_file_path: row.original_file_path,
             is_favorite: row.is_favorite.unwrap_or(false),
+            downloaded_bytes: row.downloaded_bytes.map(|b| b as u64),
+            total_bytes: row.total_bytes.map(|b| b as u64),
+            speed: row.speed,
+            eta: row.eta.map(|e| e as u64),
+            output_files: row.output_files.and_then(|json| serde_json::from_str(&json).ok()),
+            parent_id: row.parent_id,
+            playlist_index: row.playlist_index.map(|i| i as u32),
+            scheduled_at: row.scheduled_at.and_then(|s| {
+                chrono::DateTime::parse_from_rfc3339(&s)
+                    .ok()
+                    .map(|dt| dt.with_timezone(&chrono::Utc))
+            }),
+            recurrence: row.recurrence,
+            extractor: row.extractor,
+            video_id: row.video_id,
+            duplicate_of: row.duplicate_of,
+            output_path: row.output_path,
+            files_removed_at: row.files_removed_at.and_then(|s| {
+                chrono::DateTime::parse_from_rfc3339(&s)
+                    .ok()
+                    .map(|dt| dt.with_timezone(&chrono::Utc))
+            }),
+            error_code: row.error_code.and_then(|code| DownloadError::from_code(&code)),
+            error_message: row.error_message,
+            post_processing: row.post_processing.and_then(|json| serde_json::from_str(&json).ok()),
         }
     }
 }
 
+fn string_list_to_json(list: &Option<Vec<String>>) -> Result<Option<String>> {
+    Ok(match list {
+        Some(items) => Some(serde_json::to_string(items)?),
+        None => None,
+    })
+}
+
 // Tags CRUD operations
 impl Database {
     /// Initialize default/predefined tags if they don't exist
@@ -837,6 +1152,531 @@ impl Database {
     }
 }
 
+// Job queue methods
+impl Database {
+    pub async fn enqueue_job(&self, download_id: &str, request: &DownloadRequest, priority: i32) -> Result<()> {
+        self.insert_job(download_id, request, priority, "queued").await
+    }
+
+    /// Store a job the dispatcher ignores until the scheduler moves it to `queued`
+    pub async fn schedule_job(&self, download_id: &str, request: &DownloadRequest, priority: i32) -> Result<()> {
+        self.insert_job(download_id, request, priority, "scheduled").await
+    }
+
+    async fn insert_job(&self, download_id: &str, request: &DownloadRequest, priority: i32, state: &str) -> Result<()> {
+        let request_json = serde_json::to_string(request)?;
+
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO download_jobs (download_id, request, priority, state, enqueued_at)
+            VALUES (?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(download_id)
+        .bind(&request_json)
+        .bind(priority)
+        .bind(state)
+        .bind(chrono::Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_job_request(&self, download_id: &str) -> Result<Option<DownloadRequest>> {
+        let request_json = sqlx::query_scalar::<_, String>("SELECT request FROM download_jobs WHERE download_id = ?")
+            .bind(download_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        request_json
+            .map(|json| serde_json::from_str(&json).map_err(Into::into))
+            .transpose()
+    }
+
+    /// Pick the next queued job (highest priority first, then FIFO) and mark it as running
+    pub async fn claim_next_job(&self) -> Result<Option<QueuedJob>> {
+        let mut tx = self.pool.begin().await?;
+
+        let row = sqlx::query_as::<_, JobRow>(
+            r#"
+            SELECT download_id, request, priority, enqueued_at, available_at FROM download_jobs
+            WHERE state = 'queued' AND (available_at IS NULL OR available_at <= ?)
+            ORDER BY priority DESC, enqueued_at ASC
+            LIMIT 1
+            "#
+        )
+        .bind(chrono::Utc::now().to_rfc3339())
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(row) = row else {
+            return Ok(None);
+        };
+        let download_id = row.0.clone();
+
+        sqlx::query("UPDATE download_jobs SET state = 'running' WHERE download_id = ?")
+            .bind(&download_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(Some(job_from_row(row)?))
+    }
+
+    pub async fn finish_job(&self, download_id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM download_jobs WHERE download_id = ?")
+            .bind(download_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Move a job from `from` to `to` state, returns false if it was not in `from`
+    pub async fn update_job_state(&self, download_id: &str, from: &str, to: &str) -> Result<bool> {
+        let result = sqlx::query("UPDATE download_jobs SET state = ? WHERE download_id = ? AND state = ?")
+            .bind(to)
+            .bind(download_id)
+            .bind(from)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Put a running or failed job back in line, claimable from `available_at` on
+    pub async fn requeue_job(&self, download_id: &str, available_at: Option<chrono::DateTime<chrono::Utc>>) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE download_jobs SET state = 'queued', available_at = ?
+            WHERE download_id = ? AND state IN ('running', 'failed')
+            "#
+        )
+        .bind(available_at.map(|at| at.to_rfc3339()))
+        .bind(download_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn set_job_partial_files(&self, download_id: &str, partial_files: &[String]) -> Result<()> {
+        sqlx::query("UPDATE download_jobs SET partial_files = ? WHERE download_id = ?")
+            .bind(serde_json::to_string(partial_files)?)
+            .bind(download_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_job_partial_files(&self, download_id: &str) -> Result<Vec<String>> {
+        let partial_files = sqlx::query_scalar::<_, Option<String>>(
+            "SELECT partial_files FROM download_jobs WHERE download_id = ?"
+        )
+        .bind(download_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .flatten();
+
+        Ok(partial_files
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default())
+    }
+
+    pub async fn get_job_state(&self, download_id: &str) -> Result<Option<String>> {
+        let state = sqlx::query_scalar::<_, String>("SELECT state FROM download_jobs WHERE download_id = ?")
+            .bind(download_id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(state)
+    }
+
+    pub async fn get_job_ids_in_state(&self, state: &str) -> Result<Vec<String>> {
+        let ids = sqlx::query_scalar::<_, String>("SELECT download_id FROM download_jobs WHERE state = ?")
+            .bind(state)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(ids)
+    }
+
+    /// Partial files kept by every stored job (paused downloads waiting to resume)
+    pub async fn get_all_job_partial_files(&self) -> Result<Vec<String>> {
+        let rows = sqlx::query_scalar::<_, String>(
+            "SELECT partial_files FROM download_jobs WHERE partial_files IS NOT NULL"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter()
+            .filter_map(|json| serde_json::from_str::<Vec<String>>(json).ok())
+            .flatten()
+            .collect())
+    }
+
+    pub async fn get_queued_jobs(&self) -> Result<Vec<QueuedJob>> {
+        let rows = sqlx::query_as::<_, JobRow>(
+            r#"
+            SELECT download_id, request, priority, enqueued_at, available_at FROM download_jobs
+            WHERE state = 'queued'
+            ORDER BY priority DESC, enqueued_at ASC
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(job_from_row).collect()
+    }
+}
+
+// Subscription methods
+impl Database {
+    pub async fn create_subscription(&self, subscription: &Subscription) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO subscriptions (id, name, request, check_interval_secs, enabled, download_existing, created_at, last_checked_at, last_error)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(&subscription.id)
+        .bind(&subscription.name)
+        .bind(serde_json::to_string(&subscription.request)?)
+        .bind(subscription.check_interval_secs as i64)
+        .bind(subscription.enabled)
+        .bind(subscription.download_existing)
+        .bind(subscription.created_at.to_rfc3339())
+        .bind(subscription.last_checked_at.map(|at| at.to_rfc3339()))
+        .bind(&subscription.last_error)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn update_subscription(&self, subscription: &Subscription) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE subscriptions
+            SET name = ?, request = ?, check_interval_secs = ?, enabled = ?, download_existing = ?, last_checked_at = ?, last_error = ?
+            WHERE id = ?
+            "#
+        )
+        .bind(&subscription.name)
+        .bind(serde_json::to_string(&subscription.request)?)
+        .bind(subscription.check_interval_secs as i64)
+        .bind(subscription.enabled)
+        .bind(subscription.download_existing)
+        .bind(subscription.last_checked_at.map(|at| at.to_rfc3339()))
+        .bind(&subscription.last_error)
+        .bind(&subscription.id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn get_subscription(&self, id: &str) -> Result<Option<Subscription>> {
+        let row = sqlx::query_as::<_, SubscriptionRow>(
+            "SELECT id, name, request, check_interval_secs, enabled, download_existing, created_at, last_checked_at, last_error FROM subscriptions WHERE id = ?"
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(subscription_from_row).transpose()
+    }
+
+    pub async fn get_all_subscriptions(&self) -> Result<Vec<Subscription>> {
+        let rows = sqlx::query_as::<_, SubscriptionRow>(
+            "SELECT id, name, request, check_interval_secs, enabled, download_existing, created_at, last_checked_at, last_error FROM subscriptions ORDER BY created_at ASC"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(subscription_from_row).collect()
+    }
+
+    pub async fn delete_subscription(&self, id: &str) -> Result<bool> {
+        sqlx::query("DELETE FROM subscription_entries WHERE subscription_id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        let result = sqlx::query("DELETE FROM subscriptions WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Add an entry to the subscription's archive, returns false if it was already there
+    pub async fn record_subscription_entry(&self, subscription_id: &str, video_id: &str, download_id: Option<&str>) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO subscription_entries (subscription_id, video_id, download_id, seen_at)
+            VALUES (?, ?, ?, ?)
+            "#
+        )
+        .bind(subscription_id)
+        .bind(video_id)
+        .bind(download_id)
+        .bind(chrono::Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Downloads created by a subscription, newest first
+    pub async fn get_subscription_downloads(&self, subscription_id: &str) -> Result<Vec<DownloadResponse>> {
+        let rows = sqlx::query_as::<_, DownloadRow>(
+            r#"
+            SELECT downloads.* FROM downloads
+            JOIN subscription_entries ON subscription_entries.download_id = downloads.id
+            WHERE subscription_entries.subscription_id = ?
+            ORDER BY downloads.created_at DESC
+            "#
+        )
+        .bind(subscription_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|r| r.into()).collect())
+    }
+}
+
+// Preset methods
+impl Database {
+    /// Insert the built-in presets, or bring existing rows in line with the current definitions
+    async fn seed_builtin_presets(&self) -> Result<()> {
+        for preset in builtin_presets() {
+            sqlx::query(
+                r#"
+                INSERT INTO presets (id, name, description, preset_type, options, builtin, created_at)
+                VALUES (?, ?, ?, ?, ?, 1, ?)
+                ON CONFLICT(id) DO UPDATE SET name = excluded.name, description = excluded.description,
+                    preset_type = excluded.preset_type, options = excluded.options, builtin = 1
+                "#
+            )
+            .bind(&preset.id)
+            .bind(&preset.name)
+            .bind(&preset.description)
+            .bind(preset_kind_to_string(preset.kind))
+            .bind(serde_json::to_string(&preset.options)?)
+            .bind(chrono::Utc::now().to_rfc3339())
+            .execute(&self.pool)
+            .await?;
+        }
+        Ok(())
+    }
+
+    pub async fn create_preset(&self, preset: &Preset) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO presets (id, name, description, preset_type, options, builtin, created_at)
+            VALUES (?, ?, ?, ?, ?, 0, ?)
+            "#
+        )
+        .bind(&preset.id)
+        .bind(&preset.name)
+        .bind(&preset.description)
+        .bind(preset_kind_to_string(preset.kind))
+        .bind(serde_json::to_string(&preset.options)?)
+        .bind(chrono::Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Update a user preset; built-in presets are left untouched
+    pub async fn update_preset(&self, preset: &Preset) -> Result<()> {
+        sqlx::query("UPDATE presets SET name = ?, description = ?, options = ? WHERE id = ? AND builtin = 0")
+            .bind(&preset.name)
+            .bind(&preset.description)
+            .bind(serde_json::to_string(&preset.options)?)
+            .bind(&preset.id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_preset(&self, id: &str) -> Result<Option<Preset>> {
+        let row = sqlx::query_as::<_, PresetRow>(
+            "SELECT id, name, description, preset_type, options, builtin FROM presets WHERE id = ?"
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(preset_from_row).transpose()
+    }
+
+    /// Built-in presets first, then user presets by name
+    pub async fn get_all_presets(&self) -> Result<Vec<Preset>> {
+        let rows = sqlx::query_as::<_, PresetRow>(
+            "SELECT id, name, description, preset_type, options, builtin FROM presets ORDER BY builtin DESC, created_at ASC, name ASC"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(preset_from_row).collect()
+    }
+
+    pub async fn delete_preset(&self, id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM presets WHERE id = ? AND builtin = 0")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+// Retention methods
+impl Database {
+    pub async fn create_retention_rule(&self, rule: &RetentionRule) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO retention_rules (id, name, enabled, criteria, created_at, last_run_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(&rule.id)
+        .bind(&rule.name)
+        .bind(rule.enabled)
+        .bind(serde_json::to_string(&rule.criteria)?)
+        .bind(rule.created_at.to_rfc3339())
+        .bind(rule.last_run_at.map(|at| at.to_rfc3339()))
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn update_retention_rule(&self, rule: &RetentionRule) -> Result<()> {
+        sqlx::query("UPDATE retention_rules SET name = ?, enabled = ?, criteria = ?, last_run_at = ? WHERE id = ?")
+            .bind(&rule.name)
+            .bind(rule.enabled)
+            .bind(serde_json::to_string(&rule.criteria)?)
+            .bind(rule.last_run_at.map(|at| at.to_rfc3339()))
+            .bind(&rule.id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_retention_rule(&self, id: &str) -> Result<Option<RetentionRule>> {
+        let row = sqlx::query_as::<_, RetentionRuleRow>(
+            "SELECT id, name, enabled, criteria, created_at, last_run_at FROM retention_rules WHERE id = ?"
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(retention_rule_from_row).transpose()
+    }
+
+    pub async fn get_all_retention_rules(&self) -> Result<Vec<RetentionRule>> {
+        let rows = sqlx::query_as::<_, RetentionRuleRow>(
+            "SELECT id, name, enabled, criteria, created_at, last_run_at FROM retention_rules ORDER BY created_at ASC"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(retention_rule_from_row).collect()
+    }
+
+    /// Delete a rule; the reports of its past runs are kept
+    pub async fn delete_retention_rule(&self, id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM retention_rules WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn insert_retention_report(&self, report: &RetentionReport) -> Result<()> {
+        sqlx::query("INSERT INTO retention_reports (id, rule_id, ran_at, report) VALUES (?, ?, ?, ?)")
+            .bind(&report.id)
+            .bind(&report.rule_id)
+            .bind(report.ran_at.to_rfc3339())
+            .bind(serde_json::to_string(report)?)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Most recent reports first
+    pub async fn get_retention_reports(&self, limit: u32) -> Result<Vec<RetentionReport>> {
+        let rows: Vec<(String,)> = sqlx::query_as("SELECT report FROM retention_reports ORDER BY ran_at DESC LIMIT ?")
+            .bind(limit as i64)
+            .fetch_all(&self.pool)
+            .await?;
+
+        rows.into_iter().map(|(report,)| Ok(serde_json::from_str(&report)?)).collect()
+    }
+}
+
+/// id, name, enabled, criteria, created_at, last_run_at
+type RetentionRuleRow = (String, String, bool, String, String, Option<String>);
+
+fn retention_rule_from_row((id, name, enabled, criteria, created_at, last_run_at): RetentionRuleRow) -> Result<RetentionRule> {
+    Ok(RetentionRule {
+        id,
+        name,
+        enabled,
+        criteria: serde_json::from_str(&criteria)?,
+        created_at: chrono::DateTime::parse_from_rfc3339(&created_at)?
+            .with_timezone(&chrono::Utc),
+        last_run_at: last_run_at
+            .and_then(|at| chrono::DateTime::parse_from_rfc3339(&at).ok())
+            .map(|at| at.with_timezone(&chrono::Utc)),
+    })
+}
+
+/// id, name, description, preset_type, options, builtin
+type PresetRow = (String, String, Option<String>, String, String, bool);
+
+fn preset_from_row((id, name, description, preset_type, options, builtin): PresetRow) -> Result<Preset> {
+    Ok(Preset {
+        id,
+        name,
+        description,
+        kind: string_to_preset_kind(&preset_type),
+        options: serde_json::from_str(&options)?,
+        builtin,
+    })
+}
+
+/// id, name, request, check_interval_secs, enabled, download_existing, created_at, last_checked_at, last_error
+type SubscriptionRow = (String, Option<String>, String, i64, bool, bool, String, Option<String>, Option<String>);
+
+fn subscription_from_row(row: SubscriptionRow) -> Result<Subscription> {
+    let (id, name, request_json, check_interval_secs, enabled, download_existing, created_at, last_checked_at, last_error) = row;
+    Ok(Subscription {
+        id,
+        name,
+        request: serde_json::from_str(&request_json)?,
+        check_interval_secs: check_interval_secs.max(0) as u64,
+        enabled,
+        download_existing,
+        created_at: chrono::DateTime::parse_from_rfc3339(&created_at)?
+            .with_timezone(&chrono::Utc),
+        last_checked_at: last_checked_at
+            .and_then(|at| chrono::DateTime::parse_from_rfc3339(&at).ok())
+            .map(|at| at.with_timezone(&chrono::Utc)),
+        last_error,
+    })
+}
+
+/// download_id, request, priority, enqueued_at, available_at
+type JobRow = (String, String, i32, String, Option<String>);
+
+fn job_from_row((download_id, request_json, priority, enqueued_at, available_at): JobRow) -> Result<QueuedJob> {
+    Ok(QueuedJob {
+        download_id,
+        request: serde_json::from_str(&request_json)?,
+        priority,
+        enqueued_at: chrono::DateTime::parse_from_rfc3339(&enqueued_at)?
+            .with_timezone(&chrono::Utc),
+        available_at: available_at
+            .and_then(|at| chrono::DateTime::parse_from_rfc3339(&at).ok())
+            .map(|at| at.with_timezone(&chrono::Utc)),
+    })
+}
+
 #[derive(sqlx::FromRow)]
 struct TagRow {
     id: String,
@@ -877,27 +1717,68 @@ fn string_to_download_type(s: &str) -> DownloadType {
     }
 }
 
+fn preset_kind_to_string(kind: PresetKind) -> &'static str {
+    match kind {
+        PresetKind::Video => "video",
+        PresetKind::Audio => "audio",
+        PresetKind::Instrumental => "instrumental",
+    }
+}
+
+fn string_to_preset_kind(s: &str) -> PresetKind {
+    match s {
+        "audio" => PresetKind::Audio,
+        "instrumental" => PresetKind::Instrumental,
+        _ => PresetKind::Video,
+    }
+}
+
 fn status_to_string(status: &DownloadStatus) -> String {
     match status {
         DownloadStatus::Pending => "pending".to_string(),
+        DownloadStatus::Scheduled => "scheduled".to_string(),
         DownloadStatus::Downloading => "downloading".to_string(),
         DownloadStatus::Processing => "processing".to_string(),
         DownloadStatus::Completed => "completed".to_string(),
         DownloadStatus::Failed => "failed".to_string(),
         DownloadStatus::Converting => "converting".to_string(),
+        DownloadStatus::Paused => "paused".to_string(),
+        DownloadStatus::Cancelled => "cancelled".to_string(),
     }
 }
 
 fn string_to_status(s: &str) -> DownloadStatus {
     match s {
         "pending" => DownloadStatus::Pending,
+        "scheduled" => DownloadStatus::Scheduled,
         "downloading" => DownloadStatus::Downloading,
         "processing" => DownloadStatus::Processing,
         "completed" => DownloadStatus::Completed,
         "failed" => DownloadStatus::Failed,
         "converting" => DownloadStatus::Converting,
+        "paused" => DownloadStatus::Paused,
+        "cancelled" => DownloadStatus::Cancelled,
         _ => DownloadStatus::Pending,
     }
 }
 
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_update_progress_is_saved() {
+        let db = Database::in_memory().await;
+        let download = DownloadResponse::new("https://www.youtube.com/watch?v=progress".to_string(), DownloadType::Video);
+        db.insert_download(&download).await.unwrap();
+
+        db.update_progress(&download.id, 42.5, Some(4_250), Some(10_000), Some(1_024.0), Some(6)).await.unwrap();
+
+        let saved = db.get_download(&download.id).await.unwrap().unwrap();
+        assert_eq!(saved.progress, 42.5);
+        assert_eq!((saved.downloaded_bytes, saved.total_bytes), (Some(4_250), Some(10_000)));
+        assert_eq!((saved.speed, saved.eta), (Some(1_024.0), Some(6)));
+    }
+}
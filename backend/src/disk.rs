@@ -0,0 +1,401 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use crate::models::{DownloadRequest, DownloadResponse, DownloadType};
+use crate::state::AppState;
+use rust_media_downloader_shared::{Config, VideoInfo};
+
+/// How often free space is checked while downloads run
+const MONITOR_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Downloads paused for low space resume once this much more than the threshold is free,
+/// so a disk hovering around the threshold does not flap
+const RESUME_MARGIN: u64 = 512 * 1024 * 1024;
+
+/// Library quota and free-space threshold from the config; unparsable values are ignored
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DiskLimits {
+    pub quota: Option<u64>,
+    pub min_free: Option<u64>,
+}
+
+impl DiskLimits {
+    pub fn from_config(config: &Config) -> Self {
+        let size = |name: &str, value: &Option<String>| match value.as_deref().map(parse_size) {
+            Some(Ok(size)) => size,
+            Some(Err(e)) => {
+                tracing::warn!("Ignoring {}: {}", name, e);
+                None
+            }
+            None => None,
+        };
+        Self {
+            quota: size("library_quota", &config.library_quota),
+            min_free: size("min_free_space", &config.min_free_space),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.quota.is_some() || self.min_free.is_some()
+    }
+}
+
+/// Space already taken or promised when a download is about to start
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DiskUsage {
+    pub library_size: u64, // Completed downloads
+    pub reserved: u64, // Estimated size of the downloads running right now
+    pub free: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Preflight {
+    Proceed,
+    /// Could fit later, once running downloads finish or space is freed
+    Defer(String),
+    /// Cannot fit in the quota whatever happens to running downloads
+    Reject(String),
+}
+
+/// Parse a size such as `500M`, `20G` or `1.5T` (powers of 1024); empty or `0` means no limit
+pub fn parse_size(size: &str) -> Result<Option<u64>, String> {
+    let normalized = size.trim().to_uppercase();
+    if normalized.is_empty() || normalized == "0" {
+        return Ok(None);
+    }
+
+    let value = normalized.trim_end_matches("IB").trim_end_matches('B');
+    let split = value.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let multiplier: u64 = match unit.trim() {
+        "" => 1,
+        "K" => 1 << 10,
+        "M" => 1 << 20,
+        "G" => 1 << 30,
+        "T" => 1 << 40,
+        _ => return Err(format!("Invalid size '{}' (expected e.g. 500M, 20G)", size.trim())),
+    };
+    let number: f64 = number.parse().map_err(|_| format!("Invalid size '{}' (expected e.g. 500M, 20G)", size.trim()))?;
+    let bytes = (number * multiplier as f64) as u64;
+    Ok((bytes > 0).then_some(bytes))
+}
+
+/// Expected size of a download from the formats yt-dlp reported: best video stream
+/// within the requested resolution plus best audio, or the best audio alone.
+///
+/// `None` when no format carries a size (live streams, some extractors).
+pub fn estimate_size(info: &VideoInfo, request: &DownloadRequest) -> Option<u64> {
+    let formats = info.formats.as_ref()?;
+    let is_none = |codec: &Option<String>| codec.as_deref() == Some("none");
+    let max_height = request.resolution.as_deref()
+        .and_then(|r| r.trim().trim_end_matches(['p', 'P']).parse::<u32>().ok());
+
+    let best_audio = formats.iter()
+        .filter(|f| is_none(&f.vcodec) && !is_none(&f.acodec))
+        .filter_map(|f| f.filesize)
+        .max();
+
+    match request.download_type {
+        DownloadType::Audio | DownloadType::Instrumental => best_audio,
+        DownloadType::Video => {
+            let best_video = formats.iter()
+                .filter(|f| !is_none(&f.vcodec) && f.height.is_some())
+                .filter(|f| max_height.is_none_or(|max| f.height.is_some_and(|h| h <= max)))
+                .filter_map(|f| f.filesize.map(|size| (size, is_none(&f.acodec))))
+                .max();
+            match best_video {
+                // Video-only stream: merged with the best audio
+                Some((size, true)) => Some(size + best_audio.unwrap_or(0)),
+                Some((size, false)) => Some(size),
+                None => best_audio,
+            }
+        }
+    }
+}
+
+/// Whether a download of `estimate` bytes may start now
+pub fn preflight(limits: &DiskLimits, usage: &DiskUsage, estimate: u64) -> Preflight {
+    if let Some(quota) = limits.quota {
+        if usage.library_size + estimate > quota {
+            return Preflight::Reject(format!(
+                "Download (~{}) would exceed the library quota ({} of {} used)",
+                format_size(estimate), format_size(usage.library_size), format_size(quota),
+            ));
+        }
+        if usage.library_size + usage.reserved + estimate > quota {
+            return Preflight::Defer(format!(
+                "Waiting for running downloads to finish: library quota of {} nearly reached",
+                format_size(quota),
+            ));
+        }
+    }
+
+    if let (Some(min_free), Some(free)) = (limits.min_free, usage.free) {
+        if free.saturating_sub(usage.reserved).saturating_sub(estimate) < min_free {
+            return Preflight::Defer(format!(
+                "Waiting for disk space: {} free, {} must stay free",
+                format_size(free), format_size(min_free),
+            ));
+        }
+    }
+
+    Preflight::Proceed
+}
+
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// Background task pausing running downloads when free space drops under the threshold.
+///
+/// Publishes `disk.low` once per episode and resumes the downloads it paused when
+/// space comes back.
+pub async fn run(state: AppState) {
+    tracing::info!("💾 Disk space monitor started");
+    let mut paused_for_space: Option<Vec<String>> = None;
+
+    loop {
+        tokio::time::sleep(MONITOR_INTERVAL).await;
+
+        let config = state.config();
+        let Some(min_free) = DiskLimits::from_config(&config).min_free else {
+            continue;
+        };
+        let Some(free) = get_disk_space(Path::new(&config.download_directory)).0 else {
+            continue;
+        };
+
+        match paused_for_space.take() {
+            None if free < min_free => {
+                let paused = state.pause_running_downloads();
+                tracing::warn!("💾 Low disk space: {} free, {} required; paused {} download(s)",
+                    format_size(free), format_size(min_free), paused.len());
+                state.notify_disk_low(serde_json::json!({
+                    "free_bytes": free,
+                    "min_free_bytes": min_free,
+                    "download_directory": config.download_directory,
+                    "paused_downloads": paused,
+                }));
+                paused_for_space = Some(paused);
+            }
+            Some(paused) if free >= min_free + RESUME_MARGIN => {
+                tracing::info!("💾 Disk space back to {}, resuming {} download(s)", format_size(free), paused.len());
+                state.resume_paused_downloads(&paused).await;
+            }
+            still_low => paused_for_space = still_low,
+        }
+    }
+}
+
+/// Every file recorded for a download: its outputs, the main file, the pre-conversion original
+/// and what yt-dlp left next to the output path (subtitles, thumbnails, `.part` files)
+pub fn tracked_files(download: &DownloadResponse, root: &Path) -> Vec<PathBuf> {
+    let absolute = |path: &str| {
+        let path = PathBuf::from(path);
+        if path.is_absolute() { path } else { root.join(path) }
+    };
+    let mut files: Vec<PathBuf> = download.output_files.iter().flatten()
+        .chain(download.file_path.iter())
+        .chain(download.original_file_path.iter())
+        .map(|path| absolute(path))
+        .collect();
+    if let Some(output_path) = &download.output_path {
+        files.extend(sidecar_files(&absolute(output_path)));
+    }
+
+    let mut unique = Vec::with_capacity(files.len());
+    for file in files {
+        if !unique.contains(&file) {
+            unique.push(file);
+        }
+    }
+    unique
+}
+
+/// Files named `stem.<ext>[.<ext>...]` with short extensions, e.g. `stem.fr.vtt` or `stem.f137.mp4.part`,
+/// but not `stem. 2.mp4` which belongs to another title
+fn sidecar_files(stem_path: &Path) -> Vec<PathBuf> {
+    let (Some(dir), Some(stem)) = (stem_path.parent(), stem_path.file_name()) else {
+        return Vec::new();
+    };
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let prefix = format!("{}.", stem.to_string_lossy());
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            name.strip_prefix(&prefix).is_some_and(|extensions| {
+                extensions.split('.').all(|ext| !ext.is_empty() && ext.len() <= 10 && ext.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+            })
+        })
+        .collect()
+}
+
+/// Delete the files of a download, refusing anything outside the download directory.
+///
+/// Files already gone are fine; directories (recorded by older rows) are left alone.
+pub async fn delete_download_files(download: &DownloadResponse, root: &Path) -> Result<(), Vec<String>> {
+    let root = tokio::fs::canonicalize(root).await
+        .map_err(|e| vec![format!("Download directory {} is not accessible: {}", root.display(), e)])?;
+    let mut errors = Vec::new();
+
+    for path in tracked_files(download, &root) {
+        let Ok(resolved) = tokio::fs::canonicalize(&path).await else {
+            continue;
+        };
+        if !resolved.starts_with(&root) {
+            errors.push(format!("{} is outside the download directory, not deleted", path.display()));
+            continue;
+        }
+        if !resolved.is_file() {
+            continue;
+        }
+        match tokio::fs::remove_file(&resolved).await {
+            Ok(()) => tracing::info!("Deleted {}", resolved.display()),
+            Err(e) => errors.push(format!("Failed to delete {}: {}", resolved.display(), e)),
+        }
+    }
+
+    if errors.is_empty() { Ok(()) } else { Err(errors) }
+}
+
+/// Free, total and used percentage of the filesystem holding `path`, when the platform reports them
+pub fn get_disk_space(path: &Path) -> (Option<u64>, Option<u64>, Option<f64>) {
+    #[cfg(unix)]
+    {
+        use std::ffi::CString;
+        use std::mem;
+
+        extern "C" {
+            fn statvfs(path: *const libc::c_char, buf: *mut libc::statvfs) -> libc::c_int;
+        }
+
+        unsafe {
+            let path_str = path.to_string_lossy().to_string();
+            if let Ok(c_path) = CString::new(path_str) {
+                let mut stat: libc::statvfs = mem::zeroed();
+                if statvfs(c_path.as_ptr(), &mut stat) == 0 {
+                    let block_size = stat.f_frsize as u64;
+                    let total = stat.f_blocks as u64 * block_size;
+                    let free = stat.f_bavail as u64 * block_size;
+                    let used = total - free;
+                    let used_percentage = if total > 0 {
+                        Some((used as f64 / total as f64) * 100.0)
+                    } else {
+                        None
+                    };
+                    return (Some(free), Some(total), used_percentage);
+                }
+            }
+        }
+    }
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::ffi::OsStrExt;
+        use std::mem;
+
+        unsafe {
+            let path_wide: Vec<u16> = path.as_os_str()
+                .encode_wide()
+                .chain(Some(0))
+                .collect();
+
+            let mut free_bytes: u64 = 0;
+            let mut total_bytes: u64 = 0;
+
+            // Use GetDiskFreeSpaceExW through winapi
+            if winapi::um::fileapi::GetDiskFreeSpaceExW(
+                path_wide.as_ptr(),
+                &mut free_bytes as *mut _ as *mut winapi::um::winnt::ULARGE_INTEGER,
+                &mut total_bytes as *mut _ as *mut winapi::um::winnt::ULARGE_INTEGER,
+                std::ptr::null_mut(),
+            ) != 0 {
+                let used = total_bytes - free_bytes;
+                let used_percentage = if total_bytes > 0 {
+                    Some((used as f64 / total_bytes as f64) * 100.0)
+                } else {
+                    None
+                };
+                return (Some(free_bytes), Some(total_bytes), used_percentage);
+            }
+        }
+    }
+
+    // Fallback: return None if platform not supported or call failed
+    (None, None, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GIB: u64 = 1 << 30;
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("500M"), Ok(Some(500 << 20)));
+        assert_eq!(parse_size("20GiB"), Ok(Some(20 * GIB)));
+        assert_eq!(parse_size("1.5t"), Ok(Some(3 << 39)));
+        assert_eq!(parse_size("0"), Ok(None));
+        assert!(parse_size("lots").is_err());
+    }
+
+    #[test]
+    fn test_preflight() {
+        let limits = DiskLimits { quota: Some(10 * GIB), min_free: Some(GIB) };
+        let usage = DiskUsage { library_size: 8 * GIB, reserved: GIB, free: Some(50 * GIB) };
+
+        assert_eq!(preflight(&limits, &usage, GIB / 2), Preflight::Proceed);
+        // Fits once the running download is done
+        assert!(matches!(preflight(&limits, &usage, GIB + GIB / 2), Preflight::Defer(_)));
+        // Never fits in the quota
+        assert!(matches!(preflight(&limits, &usage, 3 * GIB), Preflight::Reject(_)));
+
+        let low = DiskUsage { free: Some(2 * GIB), ..usage };
+        assert!(matches!(preflight(&limits, &low, GIB / 2), Preflight::Defer(_)));
+        assert_eq!(preflight(&DiskLimits::default(), &low, 100 * GIB), Preflight::Proceed);
+    }
+
+    #[tokio::test]
+    async fn test_delete_download_files_stays_in_root() {
+        let base = std::env::temp_dir().join(format!("rmd-disk-{}", uuid::Uuid::new_v4()));
+        let root = base.join("downloads");
+        std::fs::create_dir_all(root.join("Artist")).unwrap();
+        for name in ["Song.mp3", "Song.webm", "Song.fr.vtt", "Song. 2.mp3", "Other.mp3"] {
+            std::fs::write(root.join("Artist").join(name), b"x").unwrap();
+        }
+        std::fs::write(base.join("outside.mp3"), b"x").unwrap();
+
+        let mut download = DownloadResponse::new("https://example.com/v".to_string(), DownloadType::Audio);
+        download.file_path = Some("Artist/Song.mp3".to_string());
+        download.original_file_path = Some(root.join("Artist/Song.webm").to_string_lossy().to_string());
+        download.output_path = Some(root.join("Artist/Song").to_string_lossy().to_string());
+        assert!(delete_download_files(&download, &root).await.is_ok());
+        let mut left: Vec<String> = std::fs::read_dir(root.join("Artist")).unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        left.sort();
+        assert_eq!(left, vec!["Other.mp3", "Song. 2.mp3"]);
+
+        download.output_files = Some(vec!["../outside.mp3".to_string()]);
+        assert!(delete_download_files(&download, &root).await.is_err());
+        assert!(base.join("outside.mp3").exists());
+
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+}
@@ -0,0 +1,140 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::broadcast;
+use crate::models::{DownloadResponse, DownloadStatus};
+
+/// Events buffered per subscriber before it starts lagging
+const EVENT_BUFFER: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DownloadEventKind {
+    Created,
+    Progress,
+    Status,
+    Completed,
+    Failed,
+    Updated,
+    Deleted,
+    /// Not tied to a download: free space fell under the configured threshold
+    #[serde(rename = "disk.low")]
+    DiskLow,
+}
+
+impl DownloadEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DownloadEventKind::Created => "created",
+            DownloadEventKind::Progress => "progress",
+            DownloadEventKind::Status => "status",
+            DownloadEventKind::Completed => "completed",
+            DownloadEventKind::Failed => "failed",
+            DownloadEventKind::Updated => "updated",
+            DownloadEventKind::Deleted => "deleted",
+            DownloadEventKind::DiskLow => "disk.low",
+        }
+    }
+
+    /// Webhook event name fired for this kind (progress is too chatty for webhooks)
+    pub fn webhook_event(&self) -> Option<String> {
+        match self {
+            DownloadEventKind::Progress | DownloadEventKind::Updated => None,
+            DownloadEventKind::DiskLow => Some("disk.low".to_string()),
+            kind => Some(format!("download.{}", kind.as_str())),
+        }
+    }
+
+    /// Kind of event for a download row written with `status`
+    pub fn for_status(previous: Option<DownloadStatus>, status: DownloadStatus) -> Self {
+        if previous == Some(status) {
+            return DownloadEventKind::Updated;
+        }
+        match status {
+            DownloadStatus::Completed => DownloadEventKind::Completed,
+            DownloadStatus::Failed => DownloadEventKind::Failed,
+            _ => DownloadEventKind::Status,
+        }
+    }
+}
+
+/// A change to a download, pushed to live subscribers and (for most kinds) webhooks
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadEvent {
+    #[serde(rename = "type")]
+    pub kind: DownloadEventKind,
+    pub download_id: String,
+    pub timestamp: DateTime<Utc>,
+    pub data: serde_json::Value,
+}
+
+/// In-process fan-out of download events
+pub struct EventBus {
+    sender: broadcast::Sender<DownloadEvent>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_BUFFER);
+        Self { sender }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<DownloadEvent> {
+        self.sender.subscribe()
+    }
+
+    pub fn publish(&self, kind: DownloadEventKind, download_id: &str, data: serde_json::Value) -> DownloadEvent {
+        let event = DownloadEvent {
+            kind,
+            download_id: download_id.to_string(),
+            timestamp: Utc::now(),
+            data,
+        };
+        // No subscriber is not an error
+        let _ = self.sender.send(event.clone());
+        event
+    }
+}
+
+/// Payload describing a download, shared by the live stream and webhooks.
+///
+/// Keeps the `download_id` and `error` keys webhook consumers already rely on.
+pub fn download_payload(download: &DownloadResponse) -> serde_json::Value {
+    let mut data = serde_json::to_value(download).unwrap_or_else(|_| serde_json::json!({}));
+    data["download_id"] = serde_json::json!(download.id);
+    if download.status == DownloadStatus::Failed {
+        data["error"] = serde_json::json!(download.message);
+    }
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::DownloadType;
+
+    #[test]
+    fn test_kind_for_status_transitions() {
+        use DownloadStatus::*;
+        assert_eq!(DownloadEventKind::for_status(Some(Pending), Downloading), DownloadEventKind::Status);
+        assert_eq!(DownloadEventKind::for_status(Some(Downloading), Completed), DownloadEventKind::Completed);
+        assert_eq!(DownloadEventKind::for_status(Some(Downloading), Failed), DownloadEventKind::Failed);
+        assert_eq!(DownloadEventKind::for_status(Some(Completed), Completed), DownloadEventKind::Updated);
+        assert_eq!(DownloadEventKind::Progress.webhook_event(), None);
+        assert_eq!(DownloadEventKind::Completed.webhook_event().as_deref(), Some("download.completed"));
+    }
+
+    #[tokio::test]
+    async fn test_subscribers_receive_published_events() {
+        let bus = EventBus::new();
+        let mut receiver = bus.subscribe();
+
+        let mut download = DownloadResponse::new("https://youtu.be/x".to_string(), DownloadType::Audio);
+        download.set_status(DownloadStatus::Failed, "Download failed: boom".to_string());
+        bus.publish(DownloadEventKind::Failed, &download.id, download_payload(&download));
+
+        let event = receiver.recv().await.unwrap();
+        assert_eq!(event.kind, DownloadEventKind::Failed);
+        assert_eq!(event.data["download_id"], serde_json::json!(download.id));
+        assert_eq!(event.data["error"], serde_json::json!("Download failed: boom"));
+    }
+}
@@ -6,6 +6,15 @@ mod db;
 mod cache;
 mod converter;
 mod openapi;
+mod queue;
+mod worker;
+mod events;
+mod retry;
+mod recovery;
+mod scheduler;
+mod subscriptions;
+mod disk;
+mod retention;
 
 use axum::{
     routing::{get, post, delete, patch},
@@ -50,6 +59,19 @@ async fn main() {
 
     // Initialize app state with database
     let state = AppState::new_with_db(db);
+    match state.recover_interrupted_downloads().await {
+        Ok(report) => tracing::info!(
+            "♻️ Startup recovery: {} resumed, {} failed, {} conversions restored, {} temp files removed, {} staging directories removed",
+            report.resumed, report.failed, report.restored, report.removed_temp_files, report.removed_staging_dirs
+        ),
+        Err(e) => tracing::error!("Failed to recover interrupted downloads: {}", e),
+    }
+    state.start_queue();
+    state.start_scheduler();
+    state.start_subscription_poller();
+    state.start_disk_monitor();
+    state.start_bandwidth_monitor();
+    state.start_retention_cleaner();
 
     // Configure rate limiting: 60 requests per minute per IP/key
     // TODO: Fix tower_governor API usage
@@ -61,6 +83,25 @@ async fn main() {
     //         .unwrap()
     // ));
 
+    let app = router(state);
+
+    // Start server
+    let port = std::env::var("PORT")
+        .ok()
+        .and_then(|p| p.parse::<u16>().ok())
+        .unwrap_or(9000);
+    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    tracing::info!("Server listening on {}", addr);
+    tracing::info!("Rate limiting: 60 req/min per IP/key");
+    tracing::info!("Swagger UI available at http://{}/swagger-ui", addr);
+    tracing::info!("URL validation enabled");
+    
+    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
+    axum::serve(listener, app).await.unwrap();
+}
+
+/// Every API route, with CORS, compression and request tracing
+fn router(state: AppState) -> Router {
     // Configure CORS
     let cors = CorsLayer::new()
         .allow_origin(Any)
@@ -68,15 +109,46 @@ async fn main() {
         .allow_headers([header::CONTENT_TYPE]);
 
     // Build main router
-    let app = Router::new()
+    Router::new()
         .route("/api/downloads", post(api::create_download))
         .route("/api/downloads/batch", post(api::create_batch_downloads))
         .route("/api/downloads", get(api::list_downloads))
         .route("/api/downloads/all", get(api::get_all_downloads))
+        .route("/api/queue", get(api::get_queue))
+        .route("/api/schedules", get(api::list_scheduled))
+        .route("/api/schedules/:id", patch(api::reschedule_download))
+        .route("/api/schedules/:id", delete(api::cancel_scheduled))
+        .route("/api/presets", get(api::list_presets))
+        .route("/api/presets", post(api::create_preset))
+        .route("/api/presets/:id", get(api::get_preset))
+        .route("/api/presets/:id", patch(api::update_preset))
+        .route("/api/presets/:id", delete(api::delete_preset))
+        .route("/api/retention/rules", get(api::list_retention_rules))
+        .route("/api/retention/rules", post(api::create_retention_rule))
+        .route("/api/retention/rules/:id", get(api::get_retention_rule))
+        .route("/api/retention/rules/:id", patch(api::update_retention_rule))
+        .route("/api/retention/rules/:id", delete(api::delete_retention_rule))
+        .route("/api/retention/rules/:id/dry-run", post(api::dry_run_retention_rule))
+        .route("/api/retention/rules/:id/run", post(api::run_retention_rule))
+        .route("/api/retention/reports", get(api::list_retention_reports))
+        .route("/api/subscriptions", post(api::create_subscription))
+        .route("/api/subscriptions", get(api::list_subscriptions))
+        .route("/api/subscriptions/:id", get(api::get_subscription))
+        .route("/api/subscriptions/:id", patch(api::update_subscription))
+        .route("/api/subscriptions/:id", delete(api::delete_subscription))
+        .route("/api/subscriptions/:id/check", post(api::check_subscription_now))
+        .route("/api/subscriptions/:id/history", get(api::get_subscription_history))
+        .route("/api/downloads/events", get(api::stream_download_events))
         .route("/api/downloads/:id", get(api::get_download))
         .route("/api/downloads/:id", delete(api::delete_download))
+        .route("/api/downloads/:id/cancel", post(api::cancel_download))
+        .route("/api/downloads/:id/pause", post(api::pause_download))
+        .route("/api/downloads/:id/resume", post(api::resume_download))
+        .route("/api/downloads/:id/retry", post(api::retry_download))
+        .route("/api/downloads/:id/items", get(api::get_playlist_items))
         .route("/api/downloads/:id/metadata", patch(api::update_metadata))
         .route("/api/downloads/:id/convert", post(api::convert_download))
+        .route("/api/downloads/:id/split", post(api::split_download))
         .route("/api/downloads/:id/favorite", patch(api::toggle_favorite))
         .route("/api/downloads/export", get(api::export_downloads))
         .route("/api/downloads/import", post(api::import_downloads))
@@ -85,6 +157,8 @@ async fn main() {
         .route("/api/logs", get(api::get_logs))
         .route("/api/config", get(api::get_config_info))
         .route("/api/disk", get(api::get_disk_info))
+        .route("/api/bandwidth", get(api::get_bandwidth))
+        .route("/api/bandwidth", patch(api::update_bandwidth))
         .route("/api/disclaimer", get(api::get_disclaimer))
         .route("/api/license", get(api::get_license))
         .route("/api/tags", get(api::list_tags))
@@ -113,23 +187,145 @@ async fn main() {
         // .layer(tower_governor::GovernorLayer {
         //     config: governor_conf,
         // })
-        .with_state(state);
-
-    // Start server
-    let port = std::env::var("PORT")
-        .ok()
-        .and_then(|p| p.parse::<u16>().ok())
-        .unwrap_or(9000);
-    let addr = SocketAddr::from(([0, 0, 0, 0], port));
-    tracing::info!("Server listening on {}", addr);
-    tracing::info!("Rate limiting: 60 req/min per IP/key");
-    tracing::info!("Swagger UI available at http://{}/swagger-ui", addr);
-    tracing::info!("URL validation enabled");
-    
-    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+        .with_state(state)
 }
 
 async fn health_check() -> &'static str {
     "OK"
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use crate::models::{DownloadRequest, DownloadResponse, DownloadStatus, DownloadType};
+    use rust_media_downloader_shared::{CollisionPolicy, Config, DuplicatePolicy, FakeBackend, OutputTemplates, VideoInfo};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    async fn get_json(app: Router, uri: &str) -> (StatusCode, serde_json::Value) {
+        let response = app.oneshot(Request::get(uri).body(Body::empty()).unwrap()).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_video_info_endpoint_with_fake_backend() {
+        let db = Database::in_memory().await;
+        let info = VideoInfo {
+            id: "abc".to_string(),
+            extractor_key: Some("Youtube".to_string()),
+            title: "Offline clip".to_string(),
+            thumbnail: None,
+            duration: Some(61),
+            uploader: Some("Someone".to_string()),
+            uploader_id: None,
+            view_count: None,
+            description: None,
+            formats: None,
+            subtitles: None,
+            chapters: None,
+        };
+        let backend = Arc::new(FakeBackend::new().with_video("https://youtu.be/abc", info));
+        let app = router(AppState::new_with_db(db).with_media_backend(backend));
+
+        let (status, body) = get_json(app.clone(), "/api/video/info?url=https://youtu.be/abc").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["title"], "Offline clip");
+        assert_eq!(body["duration"], 61);
+
+        let (status, body) = get_json(app.clone(), "/api/video/info?url=https://example.com/nothing").await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body["error"], "fetch_error");
+
+        let (status, body) = get_json(app, "/api/video/info?url=").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "validation_error");
+    }
+
+    #[tokio::test]
+    async fn test_deleting_paused_download_removes_partial_files() {
+        let db = Database::in_memory().await;
+        let mut download = DownloadResponse::new("https://youtu.be/paused".to_string(), DownloadType::Video);
+        download.status = DownloadStatus::Paused;
+        db.insert_download(&download).await.unwrap();
+        db.enqueue_job(&download.id, &DownloadRequest::for_url(download.url.clone(), DownloadType::Video), 0).await.unwrap();
+        let partial = std::env::temp_dir().join(format!("rmd-paused-{}.mp4.part", uuid::Uuid::new_v4()));
+        std::fs::write(&partial, b"partial").unwrap();
+        db.set_job_partial_files(&download.id, &[partial.to_string_lossy().to_string()]).await.unwrap();
+
+        let app = router(AppState::new_with_db(db));
+        let request = Request::delete(format!("/api/downloads/{}", download.id)).body(Body::empty()).unwrap();
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert!(!partial.exists());
+    }
+
+    #[tokio::test]
+    async fn test_posted_download_completes_into_library() {
+        // Library, staging and policies of a throwaway config: the host's library is never touched
+        let library = tempfile::tempdir().unwrap();
+        let config = Config {
+            download_directory: library.path().to_string_lossy().to_string(),
+            staging_directory: None,
+            duplicate_policy: DuplicatePolicy::Allow,
+            collision_policy: CollisionPolicy::default(),
+            output_templates: OutputTemplates::default(),
+            rate_limit: None,
+            rate_limit_schedule: Vec::new(),
+            library_quota: None,
+            min_free_space: None,
+            ..Config::default()
+        };
+        let video_id = "pipelineAbc".to_string();
+        let url = format!("https://www.youtube.com/watch?v={}", video_id);
+        let info = VideoInfo {
+            id: video_id.clone(),
+            extractor_key: Some("Youtube".to_string()),
+            title: format!("Pipeline clip {}", video_id),
+            thumbnail: None,
+            duration: Some(5),
+            uploader: None,
+            uploader_id: None,
+            view_count: None,
+            description: None,
+            formats: None,
+            subtitles: None,
+            chapters: None,
+        };
+        let backend = Arc::new(FakeBackend::new().with_video(&url, info));
+        let state = AppState::new_with_db(Database::in_memory().await)
+            .with_media_backend(backend)
+            .with_config(config);
+        let app = router(state.clone());
+
+        let body = serde_json::json!({ "url": url, "type": "video" });
+        let request = Request::post("/api/downloads")
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let created: DownloadResponse = serde_json::from_slice(&body).unwrap();
+
+        // The dispatcher picks the job up and the worker runs it against the fake backend
+        state.start_queue();
+        let mut download = created;
+        for _ in 0..100 {
+            download = state.get_download(&download.id).await.unwrap();
+            if matches!(download.status, DownloadStatus::Completed | DownloadStatus::Failed) {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+
+        assert_eq!(download.status, DownloadStatus::Completed, "{}", download.message);
+        let file = std::fs::canonicalize(download.file_path.expect("completed download has a file")).unwrap();
+        let library = std::fs::canonicalize(library.path()).unwrap();
+        assert!(file.starts_with(&library), "{:?} is outside {:?}", file, library);
+    }
+}
@@ -2,6 +2,7 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 use utoipa::ToSchema;
+use rust_media_downloader_shared::{DownloadError, MediaId, Preset, PostProcessing, PresetKind, PresetOptions, RateLimitWindow, VideoInfo};
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct DownloadRequest {
@@ -16,6 +17,84 @@ pub struct DownloadRequest {
     pub download_playlist: Option<bool>,
     pub download_subtitles: Option<bool>,
     pub subtitle_language: Option<String>, // e.g., "fr", "en", "auto"
+    pub priority: Option<i32>, // Higher values are picked first by the queue (default 0)
+    pub scheduled_at: Option<DateTime<Utc>>, // Hold the download until this time
+    pub recurrence: Option<String>, // Cron expression, e.g. "0 2 * * 6" for Saturdays at 02:00 server local time
+    pub rate_limit: Option<String>, // e.g. "500K", "2M": cap for this download on top of the global limit
+    pub preset_id: Option<String>, // Options from this preset fill the fields left unset
+    pub output_template: Option<String>, // e.g. "{uploader}/{playlist}/{index:02} - {title}.{ext}", relative to the download directory
+    #[schema(value_type = Option<Object>)]
+    pub post_processing: Option<PostProcessing>, // Embed metadata/thumbnail/chapters/subs, SponsorBlock remove/mark categories
+    pub split_chapters: Option<bool>, // One child entry per chapter once downloaded (audio and video only)
+}
+
+impl DownloadRequest {
+    /// Request with default options for a URL
+    pub fn for_url(url: String, download_type: DownloadType) -> Self {
+        Self {
+            url,
+            download_type,
+            format: None,
+            resolution: None,
+            audio_quality: None,
+            custom_filename: None,
+            cookies_browser: None,
+            download_playlist: None,
+            download_subtitles: None,
+            subtitle_language: None,
+            priority: None,
+            scheduled_at: None,
+            recurrence: None,
+            rate_limit: None,
+            preset_id: None,
+            output_template: None,
+            post_processing: None,
+            split_chapters: None,
+        }
+    }
+
+    /// Fill the options the request leaves unset from the preset; explicit fields win.
+    ///
+    /// Fails when the preset targets the other media kind (a video preset on an audio request).
+    pub fn apply_preset(&mut self, preset: &Preset) -> Result<(), String> {
+        let is_audio = matches!(self.download_type, DownloadType::Audio | DownloadType::Instrumental);
+        if is_audio != preset.kind.is_audio() {
+            return Err(format!(
+                "Preset '{}' is for {} downloads",
+                preset.id,
+                if preset.kind.is_audio() { "audio" } else { "video" },
+            ));
+        }
+
+        let options = &preset.options;
+        fill(&mut self.format, &options.format);
+        fill(&mut self.resolution, &options.resolution);
+        fill(&mut self.audio_quality, &options.audio_quality);
+        fill(&mut self.download_subtitles, &options.download_subtitles);
+        fill(&mut self.subtitle_language, &options.subtitle_language);
+        fill(&mut self.download_playlist, &options.download_playlist);
+        fill(&mut self.rate_limit, &options.rate_limit);
+        fill(&mut self.output_template, &options.output_template);
+        fill(&mut self.post_processing, &options.post_processing);
+        fill(&mut self.split_chapters, &options.split_chapters);
+        Ok(())
+    }
+}
+
+fn fill<T: Clone>(field: &mut Option<T>, preset_value: &Option<T>) {
+    if field.is_none() {
+        field.clone_from(preset_value);
+    }
+}
+
+/// A download waiting in (or claimed from) the persistent job queue
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct QueuedJob {
+    pub download_id: String,
+    pub request: DownloadRequest,
+    pub priority: i32,
+    pub enqueued_at: DateTime<Utc>,
+    pub available_at: Option<DateTime<Utc>>, // Set while waiting for a retry backoff
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
@@ -57,17 +136,48 @@ pub struct DownloadResponse {
     pub original_file_path: Option<String>,
     // Favorite status
     pub is_favorite: bool,
+    // Live progress reported by yt-dlp
+    pub downloaded_bytes: Option<u64>,
+    pub total_bytes: Option<u64>,
+    pub speed: Option<f64>, // bytes per second
+    pub eta: Option<u64>, // in seconds
+    // Every final file written by the download (file_path is the first one)
+    pub output_files: Option<Vec<String>>,
+    // Playlist entry: the parent playlist download and the 1-based position in it
+    pub parent_id: Option<String>,
+    pub playlist_index: Option<u32>,
+    // Scheduling: when the download is due and the cron expression it repeats on
+    pub scheduled_at: Option<DateTime<Utc>>,
+    pub recurrence: Option<String>,
+    // Media identity (yt-dlp extractor key + video id) used for duplicate detection
+    pub extractor: Option<String>,
+    pub video_id: Option<String>,
+    // Earlier download of the same media, set when a duplicate was allowed through
+    pub duplicate_of: Option<String>,
+    // Target chosen from the output template, without extension (yt-dlp picks it)
+    pub output_path: Option<String>,
+    // Set when a retention rule deleted the files but kept the entry
+    pub files_removed_at: Option<DateTime<Utc>>,
+    // Why the last attempt failed, classified from yt-dlp's output, and its explanation
+    #[schema(value_type = Option<String>, example = "geo_blocked")]
+    pub error_code: Option<DownloadError>,
+    pub error_message: Option<String>,
+    // Post-processing steps yt-dlp applied to the files, e.g. ["metadata", "sponsorblock_remove:sponsor"]
+    pub post_processing: Option<Vec<String>>,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum DownloadStatus {
-    Pending,
+    Pending, // Waiting in the queue for a free worker slot
+    Scheduled, // Held until scheduled_at, then promoted to the queue
     Downloading,
     Processing,
     Completed,
     Failed,
     Converting,
+    Paused, // Stopped by the user, .part files kept for resuming
+    Cancelled,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
@@ -119,6 +229,43 @@ impl DownloadResponse {
             tags: None,
             original_file_path: None,
             is_favorite: false,
+            downloaded_bytes: None,
+            total_bytes: None,
+            speed: None,
+            eta: None,
+            output_files: None,
+            parent_id: None,
+            playlist_index: None,
+            scheduled_at: None,
+            recurrence: None,
+            extractor: None,
+            video_id: None,
+            duplicate_of: None,
+            output_path: None,
+            files_removed_at: None,
+            error_code: None,
+            error_message: None,
+            post_processing: None,
+        }
+    }
+
+    pub fn media_id(&self) -> Option<MediaId> {
+        Some(MediaId::new(self.extractor.clone()?, self.video_id.clone()?))
+    }
+
+    pub fn set_media_id(&mut self, media_id: MediaId) {
+        self.extractor = Some(media_id.extractor);
+        self.video_id = Some(media_id.id);
+    }
+
+    /// Fill the descriptive columns from yt-dlp metadata, keeping what it does not provide
+    pub fn apply_video_info(&mut self, info: &VideoInfo) {
+        self.title = Some(info.title.clone());
+        self.author = info.uploader.clone().or(self.author.take());
+        self.duration = info.duration.or(self.duration);
+        self.thumbnail = info.thumbnail.clone().or(self.thumbnail.take());
+        if let (Some(extractor), false) = (&info.extractor_key, info.id.is_empty()) {
+            self.set_media_id(MediaId::new(extractor.clone(), info.id.clone()));
         }
     }
 
@@ -138,6 +285,16 @@ impl DownloadResponse {
         if status == DownloadStatus::Completed {
             self.completed_at = Some(Utc::now());
         }
+        if status != DownloadStatus::Failed {
+            self.error_code = None;
+            self.error_message = None;
+        }
+    }
+
+    /// Record why the download failed; call after `set_status`, which clears it on other statuses
+    pub fn set_error(&mut self, error: DownloadError) {
+        self.error_code = Some(error);
+        self.error_message = Some(error.message().to_string());
     }
 }
 
@@ -172,6 +329,30 @@ pub struct PaginationParams {
     pub per_page: Option<u32>,
 }
 
+/// Global bandwidth limit and its time-of-day overrides (local time)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BandwidthSettings {
+    pub rate_limit: Option<String>, // e.g. "2M"; absent = unlimited
+    #[serde(default)]
+    pub schedule: Vec<RateLimitWindow>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BandwidthStatus {
+    #[serde(flatten)]
+    pub settings: BandwidthSettings,
+    pub effective_limit: Option<u64>, // bytes per second right now, after the schedule
+    pub active_downloads: usize,
+    pub per_download_limit: Option<u64>, // share of a download started now
+}
+
+/// Move a scheduled download; an empty `recurrence` makes it a one-off
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RescheduleRequest {
+    pub scheduled_at: Option<DateTime<Utc>>,
+    pub recurrence: Option<String>,
+}
+
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct UpdateMetadataRequest {
     pub title: Option<String>,
@@ -185,6 +366,18 @@ pub struct ConvertFileRequest {
     pub keep_original: Option<bool>, // Whether to keep the original file
 }
 
+/// Manual split of a downloaded file: each track runs until the next one starts
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SplitFileRequest {
+    pub tracks: Vec<SplitPoint>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SplitPoint {
+    pub start: String, // e.g. "0:00", "3:25", "1:02:03.5" or seconds
+    pub title: Option<String>, // Defaults to "Track NN"
+}
+
 // Statistics models
 #[derive(Debug, Serialize)]
 pub struct DownloadTrendPoint {
@@ -254,3 +447,140 @@ pub struct WebhookEvent {
 }
 
 
+
+// Presets
+#[derive(Debug, Deserialize)]
+pub struct CreatePresetRequest {
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(rename = "type")]
+    pub kind: PresetKind,
+    #[serde(flatten)]
+    pub options: PresetOptions,
+}
+
+/// Partial update of a user preset; `options` replaces the whole option set
+#[derive(Debug, Deserialize)]
+pub struct UpdatePresetRequest {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub options: Option<PresetOptions>,
+}
+
+// Subscriptions
+/// A channel or playlist polled for new uploads; `request` is the template every new entry is downloaded with
+#[derive(Debug, Serialize, Clone, Deserialize, ToSchema)]
+pub struct Subscription {
+    pub id: String,
+    pub name: Option<String>,
+    #[serde(flatten)]
+    pub request: DownloadRequest,
+    pub check_interval_secs: u64,
+    pub enabled: bool,
+    pub download_existing: bool, // First check downloads the current entries instead of only recording them
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub last_checked_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateSubscriptionRequest {
+    pub name: Option<String>,
+    #[serde(flatten)]
+    pub request: DownloadRequest,
+    pub check_interval_secs: Option<u64>, // Default 3600, at least 300
+    pub enabled: Option<bool>,
+    pub download_existing: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateSubscriptionRequest {
+    pub name: Option<String>,
+    pub check_interval_secs: Option<u64>,
+    pub enabled: Option<bool>,
+    pub options: Option<DownloadRequest>, // Replaces the download template (including the URL)
+}
+
+/// Outcome of one poll of a subscription
+#[derive(Debug, Serialize, Clone, ToSchema)]
+pub struct SubscriptionCheckResult {
+    pub subscription_id: String,
+    pub entries: usize,
+    pub new_entries: usize,
+    pub queued: Vec<String>, // Ids of the downloads created for new entries
+}
+
+// Retention
+/// What a retention rule does with the downloads it selects
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RetentionAction {
+    #[default]
+    DeleteEntry, // Delete the files and the download
+    RemoveFiles, // Delete the files, keep the download marked as file removed
+}
+
+/// Which completed downloads a rule prunes; favorites are never selected.
+///
+/// `tag_id` and `download_type` narrow the rule's scope, `max_age_days` and `max_size`
+/// select within it. A rule with only a scope prunes everything in it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct RetentionCriteria {
+    pub max_age_days: Option<u32>, // Completed more than this many days ago
+    pub max_size: Option<String>, // e.g. "50G": keep at most this much in scope, oldest go first
+    pub tag_id: Option<String>,
+    pub download_type: Option<DownloadType>,
+    #[serde(default)]
+    pub action: RetentionAction,
+}
+
+#[derive(Debug, Serialize, Clone, Deserialize, ToSchema)]
+pub struct RetentionRule {
+    pub id: String,
+    pub name: String,
+    pub enabled: bool,
+    #[serde(flatten)]
+    pub criteria: RetentionCriteria,
+    pub created_at: DateTime<Utc>,
+    pub last_run_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateRetentionRuleRequest {
+    pub name: String,
+    pub enabled: Option<bool>, // Default false: check the dry run first
+    #[serde(flatten)]
+    pub criteria: RetentionCriteria,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateRetentionRuleRequest {
+    pub name: Option<String>,
+    pub enabled: Option<bool>,
+    pub criteria: Option<RetentionCriteria>, // Replaces the whole criteria set
+}
+
+/// A download selected by a retention rule
+#[derive(Debug, Serialize, Clone, Deserialize, ToSchema)]
+pub struct RetentionCandidate {
+    pub download_id: String,
+    pub title: Option<String>,
+    pub file_path: Option<String>,
+    pub file_size: Option<u64>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub reason: String,
+}
+
+/// What one run of a rule removed, or would remove for a dry run
+#[derive(Debug, Serialize, Clone, Deserialize, ToSchema)]
+pub struct RetentionReport {
+    pub id: String,
+    pub rule_id: String,
+    pub rule_name: String,
+    pub action: RetentionAction,
+    pub dry_run: bool,
+    pub ran_at: DateTime<Utc>,
+    pub removed: Vec<RetentionCandidate>,
+    pub freed_bytes: u64,
+    pub errors: Vec<String>,
+}
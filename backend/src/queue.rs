@@ -0,0 +1,266 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Notify, Semaphore};
+use crate::db::Database;
+use crate::models::{DownloadRequest, QueuedJob};
+use crate::state::AppState;
+
+/// How often the dispatcher re-checks the queue even without a wake-up
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// What the dispatcher should do with a job once its worker returns
+pub enum JobOutcome {
+    /// Completed or cancelled: the job leaves the queue
+    Finished,
+    /// Failed for good: the job is kept so it can be retried manually
+    Failed,
+    /// Failed with a transient error: back in line once `delay` has elapsed
+    Retry { delay: Duration },
+    /// Stopped by the user: kept (with its partial files) until resumed
+    Paused { partial_files: Vec<String> },
+}
+
+/// Persistent FIFO/priority queue of downloads, drained by a bounded pool of workers.
+///
+/// Jobs are stored in the `download_jobs` table so queued work survives a restart.
+/// Handlers only enqueue; the dispatcher started by `AppState::start_queue` claims
+/// jobs as worker slots become free.
+pub struct DownloadQueue {
+    db: Arc<Database>,
+    slots: Arc<Semaphore>,
+    wake: Notify,
+    max_workers: usize,
+}
+
+impl DownloadQueue {
+    pub fn new(db: Arc<Database>, max_workers: usize) -> Self {
+        let max_workers = max_workers.max(1);
+        Self {
+            db,
+            slots: Arc::new(Semaphore::new(max_workers)),
+            wake: Notify::new(),
+            max_workers,
+        }
+    }
+
+    pub async fn enqueue(&self, download_id: &str, request: &DownloadRequest) -> anyhow::Result<()> {
+        let priority = request.priority.unwrap_or(0);
+        self.db.enqueue_job(download_id, request, priority).await?;
+        self.wake.notify_one();
+        Ok(())
+    }
+
+    /// Store a job that only enters the line once `promote` is called
+    pub async fn schedule(&self, download_id: &str, request: &DownloadRequest) -> anyhow::Result<()> {
+        let priority = request.priority.unwrap_or(0);
+        self.db.schedule_job(download_id, request, priority).await
+    }
+
+    /// Put a scheduled job in line, returns false if no scheduled job is stored for it
+    pub async fn promote(&self, download_id: &str) -> anyhow::Result<bool> {
+        let promoted = self.db.update_job_state(download_id, "scheduled", "queued").await?;
+        if promoted {
+            self.wake.notify_one();
+        }
+        Ok(promoted)
+    }
+
+    /// Pause a job that has not been picked by a worker yet
+    pub async fn pause_queued(&self, download_id: &str) -> anyhow::Result<bool> {
+        self.db.update_job_state(download_id, "queued", "paused").await
+    }
+
+    /// Put a paused job back in line
+    pub async fn resume(&self, download_id: &str) -> anyhow::Result<bool> {
+        let resumed = self.db.update_job_state(download_id, "paused", "queued").await?;
+        if resumed {
+            self.wake.notify_one();
+        }
+        Ok(resumed)
+    }
+
+    /// Put a failed job back in line, returns false if no failed job is stored for it
+    pub async fn retry(&self, download_id: &str) -> anyhow::Result<bool> {
+        let requeued = self.db.requeue_job(download_id, None).await?;
+        if requeued {
+            self.wake.notify_one();
+        }
+        Ok(requeued)
+    }
+
+    /// Drop a job from the queue, returning the partial files it left behind
+    pub async fn remove(&self, download_id: &str) -> anyhow::Result<Vec<String>> {
+        let partial_files = self.db.get_job_partial_files(download_id).await?;
+        self.db.finish_job(download_id).await?;
+        Ok(partial_files)
+    }
+
+    pub async fn queued_jobs(&self) -> anyhow::Result<Vec<QueuedJob>> {
+        self.db.get_queued_jobs().await
+    }
+
+    pub fn max_workers(&self) -> usize {
+        self.max_workers
+    }
+
+    pub fn active_workers(&self) -> usize {
+        self.max_workers - self.slots.available_permits()
+    }
+
+    /// Dispatcher loop: hands queued jobs to workers while slots are available
+    pub async fn run(self: Arc<Self>, state: AppState) {
+        tracing::info!("📥 Download queue started with {} worker(s)", self.max_workers);
+
+        loop {
+            let permit = match self.slots.clone().acquire_owned().await {
+                Ok(permit) => permit,
+                Err(_) => break, // Semaphore closed
+            };
+
+            let job = match self.db.claim_next_job().await {
+                Ok(job) => job,
+                Err(e) => {
+                    tracing::error!("Failed to claim next download job: {}", e);
+                    None
+                }
+            };
+
+            match job {
+                Some(job) => {
+                    let state = state.clone();
+                    let db = self.db.clone();
+                    let queue = self.clone();
+                    tokio::spawn(async move {
+                        let result = match crate::worker::run_download(&state, &job.download_id, job.request).await {
+                            JobOutcome::Finished => db.finish_job(&job.download_id).await,
+                            JobOutcome::Failed => {
+                                db.update_job_state(&job.download_id, "running", "failed").await.map(|_| ())
+                            }
+                            JobOutcome::Retry { delay } => {
+                                let available_at = chrono::Utc::now()
+                                    + chrono::Duration::from_std(delay).unwrap_or_default();
+                                let result = db.requeue_job(&job.download_id, Some(available_at)).await.map(|_| ());
+                                // Wake the dispatcher when the backoff is over
+                                let queue = queue.clone();
+                                tokio::spawn(async move {
+                                    tokio::time::sleep(delay).await;
+                                    queue.wake.notify_one();
+                                });
+                                result
+                            }
+                            JobOutcome::Paused { partial_files } => {
+                                match db.update_job_state(&job.download_id, "running", "paused").await {
+                                    Ok(_) => db.set_job_partial_files(&job.download_id, &partial_files).await,
+                                    Err(e) => Err(e),
+                                }
+                            }
+                        };
+                        if let Err(e) = result {
+                            tracing::error!("Failed to update job {} after run: {}", job.download_id, e);
+                        }
+                        drop(permit);
+                        queue.wake.notify_one();
+                    });
+                }
+                None => {
+                    // Nothing to do: release the slot and wait for a new job
+                    drop(permit);
+                    let _ = tokio::time::timeout(POLL_INTERVAL, self.wake.notified()).await;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{DownloadResponse, DownloadType};
+
+    fn request(priority: Option<i32>) -> DownloadRequest {
+        DownloadRequest {
+            url: "https://www.youtube.com/watch?v=dQw4w9WgXcQ".to_string(),
+            download_type: DownloadType::Video,
+            format: None,
+            resolution: None,
+            audio_quality: None,
+            custom_filename: None,
+            cookies_browser: None,
+            download_playlist: None,
+            download_subtitles: None,
+            subtitle_language: None,
+            priority,
+            scheduled_at: None,
+            recurrence: None,
+            rate_limit: None,
+            preset_id: None,
+            output_template: None,
+            post_processing: None,
+            split_chapters: None,
+        }
+    }
+
+    async fn add(db: &Database, queue: &DownloadQueue, priority: Option<i32>) -> String {
+        let req = request(priority);
+        let download = DownloadResponse::new(req.url.clone(), req.download_type.clone());
+        db.insert_download(&download).await.unwrap();
+        queue.enqueue(&download.id, &req).await.unwrap();
+        // Keep enqueued_at timestamps strictly ordered
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        download.id
+    }
+
+    #[tokio::test]
+    async fn test_claim_order_is_priority_then_fifo() {
+        let db = Arc::new(Database::in_memory().await);
+        let queue = DownloadQueue::new(db.clone(), 2);
+
+        let first = add(&db, &queue, None).await;
+        let second = add(&db, &queue, None).await;
+        let urgent = add(&db, &queue, Some(10)).await;
+
+        let claimed: Vec<String> = vec![
+            db.claim_next_job().await.unwrap().unwrap().download_id,
+            db.claim_next_job().await.unwrap().unwrap().download_id,
+            db.claim_next_job().await.unwrap().unwrap().download_id,
+        ];
+        assert_eq!(claimed, vec![urgent, first, second]);
+        assert!(db.claim_next_job().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_retried_job_waits_for_backoff() {
+        let db = Arc::new(Database::in_memory().await);
+        let queue = DownloadQueue::new(db.clone(), 1);
+
+        let id = add(&db, &queue, None).await;
+        assert_eq!(db.claim_next_job().await.unwrap().unwrap().download_id, id);
+
+        let later = chrono::Utc::now() + chrono::Duration::seconds(60);
+        assert!(db.requeue_job(&id, Some(later)).await.unwrap());
+        assert!(db.claim_next_job().await.unwrap().is_none());
+
+        // A failed job retried manually is available right away
+        assert!(db.update_job_state(&id, "queued", "failed").await.unwrap());
+        assert!(queue.retry(&id).await.unwrap());
+        assert_eq!(db.claim_next_job().await.unwrap().unwrap().download_id, id);
+    }
+
+    #[tokio::test]
+    async fn test_queued_jobs_survive_reopen() {
+        // Reopening needs a real file: removed with the directory
+        let dir = tempfile::tempdir().unwrap();
+        let url = format!("sqlite:{}?mode=rwc", dir.path().join("queue.db").display());
+
+        let db = Arc::new(Database::new(&url).await.unwrap());
+        let queue = DownloadQueue::new(db.clone(), 1);
+        let id = add(&db, &queue, None).await;
+        drop(queue);
+        drop(db);
+
+        let reopened = Database::new(&url).await.unwrap();
+        let jobs = reopened.get_queued_jobs().await.unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].download_id, id);
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use crate::db::Database;
+use crate::models::DownloadStatus;
+use crate::state::{AppState, remove_partial_files};
+
+/// When interrupted downloads are resumed we cannot tell which temp files they
+/// still need, so only files untouched for this long are considered orphaned
+const ORPHAN_MIN_AGE: Duration = Duration::from_secs(24 * 3600);
+
+/// What startup reconciliation did
+#[derive(Debug, Default)]
+pub struct RecoveryReport {
+    pub resumed: usize,
+    pub failed: usize,
+    pub restored: usize,
+    pub removed_temp_files: usize,
+    pub removed_staging_dirs: usize,
+}
+
+/// Reconcile downloads left active by a previous run of the backend.
+///
+/// Rows still `downloading`/`processing` had their process killed by the restart:
+/// they are put back in the queue with their stored request (yt-dlp continues from its
+/// `.part` files) or, when `resume` is false, marked failed. Rows whose job vanished are
+/// marked failed too, since their options are lost, and an interrupted conversion falls
+/// back to the completed download. Staging
+/// directories of jobs that will not run again are deleted.
+pub async fn recover_interrupted_downloads(state: &AppState, db: &Database, download_dir: &Path, staging_root: &Path, resume: bool) -> anyhow::Result<RecoveryReport> {
+    let mut report = RecoveryReport::default();
+
+    for mut download in db.get_active_downloads().await? {
+        let job_state = db.get_job_state(&download.id).await?;
+
+        match download.status {
+            DownloadStatus::Pending if job_state.as_deref() == Some("queued") => {}
+            // A playlist has no job of its own, its status follows the entries
+            _ if download.is_playlist => state.refresh_playlist(&download.id).await,
+            DownloadStatus::Converting => {
+                download.set_status(DownloadStatus::Completed, "Conversion interrupted by restart".to_string());
+                download.progress = 100.0;
+                state.update_download(&download.id, download.clone()).await;
+                report.restored += 1;
+            }
+            _ if resume && job_state.is_some() => {
+                // The job keeps the original request: only its state changes
+                let job_state = job_state.as_deref().unwrap_or_default();
+                if job_state != "queued" && !db.requeue_job(&download.id, None).await? {
+                    db.update_job_state(&download.id, job_state, "queued").await?;
+                }
+                download.set_status(DownloadStatus::Pending, "Download queued (resumed after restart)".to_string());
+                download.speed = None;
+                download.eta = None;
+                state.update_download(&download.id, download.clone()).await;
+                report.resumed += 1;
+            }
+            // Without its job the requested format, quality and template are unknown:
+            // resuming with defaults could silently produce a different file
+            _ if resume => {
+                download.set_status(DownloadStatus::Failed, "Download failed: interrupted by restart and its options were lost, start it again".to_string());
+                download.speed = None;
+                download.eta = None;
+                state.update_download(&download.id, download.clone()).await;
+                report.failed += 1;
+            }
+            _ => {
+                if job_state.is_some() {
+                    db.update_job_state(&download.id, job_state.as_deref().unwrap_or_default(), "failed").await?;
+                }
+                download.set_status(DownloadStatus::Failed, "Download failed: interrupted by restart".to_string());
+                download.speed = None;
+                download.eta = None;
+                state.update_download(&download.id, download.clone()).await;
+                report.failed += 1;
+            }
+        }
+    }
+
+    // Jobs still marked running belong to downloads that already reached a final state
+    for id in db.get_job_ids_in_state("running").await? {
+        db.finish_job(&id).await?;
+    }
+
+    let keep: HashSet<PathBuf> = db.get_all_job_partial_files().await?
+        .into_iter()
+        .map(PathBuf::from)
+        .collect();
+    let min_age = (report.resumed > 0).then_some(ORPHAN_MIN_AGE);
+    let orphans = orphaned_temp_files(download_dir, &keep, min_age);
+    report.removed_temp_files = orphans.len();
+    remove_partial_files(orphans);
+
+    // A staging directory is named after its job: only queued, scheduled or paused jobs still need it
+    for dir in staging_dirs(staging_root) {
+        let job_id = dir.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let job_state = db.get_job_state(&job_id).await?;
+        if matches!(job_state.as_deref(), Some("queued" | "scheduled" | "paused")) {
+            continue;
+        }
+        match std::fs::remove_dir_all(&dir) {
+            Ok(()) => report.removed_staging_dirs += 1,
+            Err(e) => tracing::warn!("Failed to remove staging directory {}: {}", dir.display(), e),
+        }
+    }
+
+    Ok(report)
+}
+
+fn staging_dirs(staging_root: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(staging_root) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().map(|t| t.is_dir()).unwrap_or(false))
+        .map(|entry| entry.path())
+        .collect()
+}
+
+/// Leftovers of yt-dlp/ffmpeg runs: `.part`, `.ytdl`, fragments and `.temp.` intermediates
+fn is_temp_file(name: &str) -> bool {
+    name.ends_with(".part")
+        || name.ends_with(".ytdl")
+        || name.contains(".part-Frag")
+        || name.contains(".temp.")
+}
+
+/// Temp files in `dir` not referenced by a paused job (and, if given, older than `min_age`)
+fn orphaned_temp_files(dir: &Path, keep: &HashSet<PathBuf>, min_age: Option<Duration>) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let now = SystemTime::now();
+
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().map(|t| t.is_file()).unwrap_or(false))
+        .map(|entry| entry.path())
+        .filter(|path| path.file_name().is_some_and(|n| is_temp_file(&n.to_string_lossy())))
+        .filter(|path| !keep.contains(path))
+        .filter(|path| match min_age {
+            Some(min_age) => std::fs::metadata(path)
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|modified| now.duration_since(modified).ok())
+                .is_some_and(|age| age >= min_age),
+            None => true,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{DownloadRequest, DownloadResponse, DownloadType};
+
+    #[tokio::test]
+    async fn test_download_without_job_fails_instead_of_resuming_with_defaults() {
+        let db = Database::in_memory().await;
+        let state = AppState::new_with_db(db.clone());
+        let empty = tempfile::tempdir().unwrap();
+
+        let mut orphan = DownloadResponse::new("https://youtu.be/orphan".to_string(), DownloadType::Audio);
+        orphan.status = DownloadStatus::Downloading;
+        db.insert_download(&orphan).await.unwrap();
+
+        let mut running = DownloadResponse::new("https://youtu.be/running".to_string(), DownloadType::Audio);
+        running.status = DownloadStatus::Downloading;
+        db.insert_download(&running).await.unwrap();
+        let mut request = DownloadRequest::for_url(running.url.clone(), DownloadType::Audio);
+        request.audio_quality = Some("320k".to_string());
+        db.enqueue_job(&running.id, &request, 0).await.unwrap();
+        db.claim_next_job().await.unwrap().unwrap();
+
+        let report = recover_interrupted_downloads(&state, &db, empty.path(), empty.path(), true).await.unwrap();
+        assert_eq!((report.resumed, report.failed), (1, 1));
+
+        assert_eq!(db.get_download(&orphan.id).await.unwrap().unwrap().status, DownloadStatus::Failed);
+        assert_eq!(db.get_job_state(&orphan.id).await.unwrap(), None);
+        let job = db.claim_next_job().await.unwrap().unwrap();
+        assert_eq!(job.download_id, running.id);
+        assert_eq!(job.request.audio_quality.as_deref(), Some("320k"));
+    }
+
+    #[test]
+    fn test_orphaned_temp_files_skip_paused_jobs() {
+        let dir = std::env::temp_dir().join(format!("rmd-recovery-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in ["a.mp4.part", "a.mp4.ytdl", "b.webm.part-Frag2", "c.temp.mp4", "done.mp4", "paused.mp3.part"] {
+            std::fs::write(dir.join(name), b"").unwrap();
+        }
+        let keep: HashSet<PathBuf> = [dir.join("paused.mp3.part")].into_iter().collect();
+
+        let mut orphans = orphaned_temp_files(&dir, &keep, None);
+        orphans.sort();
+        // Freshly written files are never older than a day
+        let recent = orphaned_temp_files(&dir, &keep, Some(ORPHAN_MIN_AGE));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!(orphans, vec![
+            dir.join("a.mp4.part"),
+            dir.join("a.mp4.ytdl"),
+            dir.join("b.webm.part-Frag2"),
+            dir.join("c.temp.mp4"),
+        ]);
+        assert!(recent.is_empty());
+    }
+}
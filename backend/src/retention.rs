@@ -0,0 +1,219 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use crate::disk::{delete_download_files, format_size, parse_size};
+use crate::models::{DownloadResponse, DownloadStatus, RetentionAction, RetentionCandidate, RetentionCriteria, RetentionReport, RetentionRule};
+use crate::state::AppState;
+
+/// How often the cleaner applies the enabled rules
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Number of reports returned by the reports endpoint
+pub const REPORT_LIMIT: u32 = 50;
+
+/// A rule needs something to select on, a rule without any criteria would empty the library
+pub fn validate_criteria(criteria: &RetentionCriteria) -> Result<(), String> {
+    if criteria.max_age_days.is_none() && criteria.max_size.is_none() && criteria.tag_id.is_none() && criteria.download_type.is_none() {
+        return Err("A retention rule needs at least one of max_age_days, max_size, tag_id or download_type".to_string());
+    }
+    if criteria.max_age_days == Some(0) {
+        return Err("max_age_days must be at least 1".to_string());
+    }
+    if let Some(max_size) = &criteria.max_size {
+        if parse_size(max_size)?.is_none() {
+            return Err("max_size must be greater than 0".to_string());
+        }
+    }
+    Ok(())
+}
+
+/// Downloads the rule removes, oldest first.
+///
+/// Only completed single downloads whose files are still there are in scope, and
+/// favorites are never selected. Favorites still count towards `max_size`.
+pub fn select_candidates(criteria: &RetentionCriteria, downloads: &[DownloadResponse], now: DateTime<Utc>) -> Vec<RetentionCandidate> {
+    let finished_at = |d: &DownloadResponse| d.completed_at.unwrap_or(d.created_at);
+    let mut in_scope: Vec<&DownloadResponse> = downloads.iter()
+        .filter(|d| d.status == DownloadStatus::Completed && !d.is_playlist && d.files_removed_at.is_none())
+        .filter(|d| criteria.download_type.as_ref().is_none_or(|t| *t == d.download_type))
+        .filter(|d| criteria.tag_id.as_ref().is_none_or(|tag_id| {
+            d.tags.as_ref().is_some_and(|tags| tags.iter().any(|tag| &tag.id == tag_id))
+        }))
+        .collect();
+    in_scope.sort_by_key(|d| finished_at(d));
+
+    let mut selected: Vec<(&DownloadResponse, String)> = Vec::new();
+    let mut chosen = HashSet::new();
+
+    if let Some(days) = criteria.max_age_days {
+        let cutoff = now - chrono::Duration::days(days as i64);
+        for download in in_scope.iter().filter(|d| !d.is_favorite && finished_at(d) < cutoff) {
+            chosen.insert(download.id.as_str());
+            selected.push((download, format!("Completed more than {} days ago", days)));
+        }
+    }
+
+    if let Some(max_size) = criteria.max_size.as_deref().and_then(|size| parse_size(size).ok().flatten()) {
+        let mut total: u64 = in_scope.iter()
+            .filter(|d| !chosen.contains(d.id.as_str()))
+            .filter_map(|d| d.file_size)
+            .sum();
+        for download in &in_scope {
+            if total <= max_size {
+                break;
+            }
+            if download.is_favorite || chosen.contains(download.id.as_str()) {
+                continue;
+            }
+            total = total.saturating_sub(download.file_size.unwrap_or(0));
+            chosen.insert(download.id.as_str());
+            selected.push((download, format!("Over the {} kept by the rule", format_size(max_size))));
+        }
+    }
+
+    // A rule with only a tag or a type prunes everything it covers
+    if criteria.max_age_days.is_none() && criteria.max_size.is_none() {
+        for download in in_scope.iter().filter(|d| !d.is_favorite) {
+            selected.push((download, "Matches the rule's tag or type".to_string()));
+        }
+    }
+
+    selected.sort_by_key(|(download, _)| finished_at(download));
+    selected.into_iter()
+        .map(|(download, reason)| RetentionCandidate {
+            download_id: download.id.clone(),
+            title: download.title.clone(),
+            file_path: download.file_path.clone(),
+            file_size: download.file_size,
+            completed_at: download.completed_at,
+            reason,
+        })
+        .collect()
+}
+
+/// Apply a rule, or only list what it would remove for a dry run.
+///
+/// A download whose files could not all be deleted is left in place and reported in `errors`.
+/// Real runs are saved as a report and stamp the rule's `last_run_at`.
+pub async fn run_rule(state: &AppState, rule: &mut RetentionRule, dry_run: bool) -> anyhow::Result<RetentionReport> {
+    let downloads = state.get_all_downloads().await;
+    let candidates = select_candidates(&rule.criteria, &downloads, Utc::now());
+    let mut report = RetentionReport {
+        id: uuid::Uuid::new_v4().to_string(),
+        rule_id: rule.id.clone(),
+        rule_name: rule.name.clone(),
+        action: rule.criteria.action,
+        dry_run,
+        ran_at: Utc::now(),
+        removed: Vec::new(),
+        freed_bytes: 0,
+        errors: Vec::new(),
+    };
+
+    if dry_run {
+        report.freed_bytes = candidates.iter().filter_map(|c| c.file_size).sum();
+        report.removed = candidates;
+        return Ok(report);
+    }
+
+    let root = PathBuf::from(state.config().download_directory);
+    for candidate in candidates {
+        let Some(download) = downloads.iter().find(|d| d.id == candidate.download_id) else {
+            continue;
+        };
+        if let Err(errors) = delete_download_files(download, &root).await {
+            report.errors.extend(errors);
+            continue;
+        }
+
+        match rule.criteria.action {
+            RetentionAction::DeleteEntry => {
+                state.remove_download(&download.id).await;
+            }
+            RetentionAction::RemoveFiles => {
+                state.mark_files_removed(&download.id, &rule.name).await;
+            }
+        }
+        report.freed_bytes += candidate.file_size.unwrap_or(0);
+        report.removed.push(candidate);
+    }
+
+    rule.last_run_at = Some(report.ran_at);
+    state.update_retention_rule(rule).await?;
+    state.save_retention_report(&report).await?;
+    Ok(report)
+}
+
+/// Background task applying every enabled retention rule
+pub async fn run(state: AppState) {
+    tracing::info!("🧹 Library cleaner started");
+
+    loop {
+        tokio::time::sleep(CLEANUP_INTERVAL).await;
+
+        let rules = match state.get_all_retention_rules().await {
+            Ok(rules) => rules,
+            Err(e) => {
+                tracing::error!("Failed to load retention rules: {}", e);
+                continue;
+            }
+        };
+        for mut rule in rules.into_iter().filter(|rule| rule.enabled) {
+            match run_rule(&state, &mut rule, false).await {
+                Ok(report) if !report.removed.is_empty() || !report.errors.is_empty() => {
+                    tracing::info!("🧹 Retention rule '{}': removed {} download(s), freed {}, {} error(s)",
+                        rule.name, report.removed.len(), format_size(report.freed_bytes), report.errors.len());
+                }
+                Ok(_) => {}
+                Err(e) => tracing::error!("Retention rule '{}' failed: {}", rule.name, e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{DownloadType, Tag};
+
+    fn completed(days_ago: i64, size: u64, now: DateTime<Utc>) -> DownloadResponse {
+        let mut download = DownloadResponse::new("https://example.com/v".to_string(), DownloadType::Video);
+        download.status = DownloadStatus::Completed;
+        download.completed_at = Some(now - chrono::Duration::days(days_ago));
+        download.file_size = Some(size);
+        download
+    }
+
+    #[test]
+    fn test_select_candidates() {
+        let now = Utc::now();
+        let old = completed(40, 300, now);
+        let mut old_favorite = completed(50, 300, now);
+        old_favorite.is_favorite = true;
+        let recent = completed(5, 300, now);
+        let mut tagged = completed(1, 100, now);
+        tagged.download_type = DownloadType::Audio;
+        tagged.tags = Some(vec![Tag { id: "podcast".to_string(), name: "Podcast".to_string(), color: None, category: None, created_at: now }]);
+        let downloads = vec![recent.clone(), old.clone(), old_favorite.clone(), tagged.clone()];
+        let ids = |criteria: &RetentionCriteria| -> Vec<String> {
+            select_candidates(criteria, &downloads, now).into_iter().map(|c| c.download_id).collect()
+        };
+
+        // Favorites are exempt from age rules
+        let by_age = RetentionCriteria { max_age_days: Some(30), ..Default::default() };
+        assert_eq!(ids(&by_age), vec![old.id.clone()]);
+
+        // 1000 bytes in total, the favorite is skipped and the next oldest goes
+        let by_size = RetentionCriteria { max_size: Some("500".to_string()), ..Default::default() };
+        assert_eq!(ids(&by_size), vec![old.id.clone(), recent.id.clone()]);
+
+        let by_tag = RetentionCriteria { tag_id: Some("podcast".to_string()), ..Default::default() };
+        assert_eq!(ids(&by_tag), vec![tagged.id.clone()]);
+        let by_type = RetentionCriteria { download_type: Some(DownloadType::Audio), max_age_days: Some(30), ..Default::default() };
+        assert!(ids(&by_type).is_empty());
+
+        assert!(validate_criteria(&RetentionCriteria::default()).is_err());
+        assert!(validate_criteria(&by_size).is_ok());
+    }
+}
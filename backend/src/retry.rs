@@ -0,0 +1,85 @@
+use rand::Rng;
+use rust_media_downloader_shared::{Config, DownloadError};
+use std::time::Duration;
+
+/// Automatic retry settings for failed downloads, read from the shared `Config`
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+}
+
+impl RetryPolicy {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            max_retries: config.max_retries,
+            base_delay: Duration::from_secs(config.retry_base_delay_secs.max(1)),
+            max_delay: Duration::from_secs(config.retry_max_delay_secs.max(1)),
+        }
+    }
+
+    /// Delay before retry number `attempt` (1-based): exponential backoff capped at
+    /// `max_delay`, with half of it randomized so retries do not hit the site in bursts
+    pub fn delay_for(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(16);
+        let backoff = self.base_delay.saturating_mul(1 << exponent).min(self.max_delay);
+        let half = backoff / 2;
+        half + half.mul_f64(rand::thread_rng().gen_range(0.0..=1.0))
+    }
+
+    /// Whether a failure of the given attempt count should be retried automatically
+    pub fn should_retry(&self, error: &anyhow::Error, retries_done: u32) -> bool {
+        retries_done < self.max_retries && is_transient(error)
+    }
+}
+
+/// Only network errors, HTTP 5xx and 429 are worth retrying
+pub fn is_transient(error: &anyhow::Error) -> bool {
+    DownloadError::of(error).is_transient()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_media_downloader_shared::YtDlpFailed;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy {
+            max_retries: 3,
+            base_delay: Duration::from_secs(10),
+            max_delay: Duration::from_secs(60),
+        }
+    }
+
+    #[test]
+    fn test_delay_grows_and_is_capped() {
+        let policy = policy();
+        for _ in 0..20 {
+            let first = policy.delay_for(1);
+            assert!(first >= Duration::from_secs(5) && first <= Duration::from_secs(10));
+            let third = policy.delay_for(3);
+            assert!(third >= Duration::from_secs(20) && third <= Duration::from_secs(40));
+            let capped = policy.delay_for(10);
+            assert!(capped >= Duration::from_secs(30) && capped <= Duration::from_secs(60));
+        }
+    }
+
+    #[test]
+    fn test_only_transient_errors_are_retried() {
+        let policy = policy();
+        let network: anyhow::Error = YtDlpFailed {
+            code: Some(1),
+            stderr: vec!["ERROR: Unable to download webpage: HTTP Error 503: Service Unavailable".to_string()],
+        }.into();
+        let unavailable: anyhow::Error = YtDlpFailed {
+            code: Some(1),
+            stderr: vec!["ERROR: [youtube] abc: Video unavailable".to_string()],
+        }.into();
+
+        assert!(policy.should_retry(&network, 0));
+        assert!(!policy.should_retry(&network, 3));
+        assert!(!policy.should_retry(&unavailable, 0));
+        assert!(!policy.should_retry(&anyhow::anyhow!("spawn failed"), 0));
+    }
+}
@@ -0,0 +1,149 @@
+use chrono::{DateTime, Local, TimeZone, Utc};
+use cron::Schedule;
+use std::str::FromStr;
+use std::time::Duration;
+use crate::state::AppState;
+
+/// How often the scheduler looks for due downloads (schedules have minute granularity)
+const SCHEDULER_INTERVAL: Duration = Duration::from_secs(20);
+
+const WEEKDAYS: [&str; 8] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// Parse a cron expression, evaluated in local time like the bandwidth schedule.
+///
+/// The usual five fields (`minute hour day month weekday`, Sunday = 0 or 7) are
+/// accepted, as well as the six/seven field form with seconds (and years).
+pub fn parse_recurrence(expression: &str) -> Result<Schedule, String> {
+    let fields: Vec<&str> = expression.split_whitespace().collect();
+    let normalized = match fields.as_slice() {
+        [minute, hour, day, month, weekday] => {
+            format!("0 {} {} {} {} {}", minute, hour, day, month, weekday_names(weekday)?)
+        }
+        _ => fields.join(" "),
+    };
+    Schedule::from_str(&normalized).map_err(|e| format!("Invalid recurrence '{}': {}", expression.trim(), e))
+}
+
+/// First run of a recurrence strictly after `after`
+pub fn next_occurrence(expression: &str, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    next_occurrence_in(expression, after, &Local)
+}
+
+fn next_occurrence_in<Tz: TimeZone>(expression: &str, after: DateTime<Utc>, timezone: &Tz) -> Option<DateTime<Utc>> {
+    next_run(&parse_recurrence(expression).ok()?, after, timezone)
+}
+
+/// Fields are read as wall-clock time in `timezone`, the result is stored in UTC
+fn next_run<Tz: TimeZone>(schedule: &Schedule, after: DateTime<Utc>, timezone: &Tz) -> Option<DateTime<Utc>> {
+    schedule.after(&after.with_timezone(timezone)).next().map(|run| run.with_timezone(&Utc))
+}
+
+/// When a new download should start: its `scheduled_at` if still ahead, else the
+/// next run of its recurrence. `None` means right away.
+pub fn resolve_schedule(scheduled_at: Option<DateTime<Utc>>, recurrence: Option<&str>, now: DateTime<Utc>) -> Result<Option<DateTime<Utc>>, String> {
+    resolve_schedule_in(scheduled_at, recurrence, now, &Local)
+}
+
+fn resolve_schedule_in<Tz: TimeZone>(scheduled_at: Option<DateTime<Utc>>, recurrence: Option<&str>, now: DateTime<Utc>, timezone: &Tz) -> Result<Option<DateTime<Utc>>, String> {
+    let scheduled_at = scheduled_at.filter(|at| *at > now);
+    match recurrence.filter(|r| !r.trim().is_empty()) {
+        Some(expression) => {
+            let schedule = parse_recurrence(expression)?;
+            match scheduled_at {
+                Some(at) => Ok(Some(at)),
+                None => next_run(&schedule, now, timezone)
+                    .map(Some)
+                    .ok_or_else(|| format!("Recurrence '{}' never fires", expression.trim())),
+            }
+        }
+        None => Ok(scheduled_at),
+    }
+}
+
+/// Background task promoting due scheduled downloads into the queue.
+///
+/// Everything it needs is in the database, so schedules missed while the backend
+/// was down are picked up on the first pass after a restart.
+pub async fn run(state: AppState) {
+    tracing::info!("⏰ Download scheduler started");
+
+    loop {
+        match state.promote_due_downloads().await {
+            Ok(0) => {}
+            Ok(promoted) => tracing::info!("⏰ {} scheduled download(s) queued", promoted),
+            Err(e) => tracing::error!("Failed to promote scheduled downloads: {}", e),
+        }
+        tokio::time::sleep(SCHEDULER_INTERVAL).await;
+    }
+}
+
+/// Standard cron numbers Sunday as 0, the `cron` crate as 1: use names instead
+fn weekday_names(field: &str) -> Result<String, String> {
+    let name = |value: &str| -> Result<String, String> {
+        match value.parse::<usize>() {
+            Ok(day) if day < WEEKDAYS.len() => Ok(WEEKDAYS[day].to_string()),
+            Ok(day) => Err(format!("Invalid weekday {}", day)),
+            Err(_) => Ok(value.to_string()),
+        }
+    };
+
+    field
+        .split(',')
+        .map(|part| {
+            let (range, step) = match part.split_once('/') {
+                Some((range, step)) => (range, Some(step)),
+                None => (part, None),
+            };
+            let range = match range.split_once('-') {
+                Some((start, end)) => format!("{}-{}", name(start)?, name(end)?),
+                None => name(range)?,
+            };
+            Ok(match step {
+                Some(step) => format!("{}/{}", range, step),
+                None => range,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()
+        .map(|parts| parts.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+
+    #[test]
+    fn test_five_field_recurrence_uses_standard_weekdays() {
+        // Friday 2024-03-01 12:00 UTC
+        let now = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+
+        assert_eq!(next_occurrence_in("0 2 * * 6", now, &Utc), Some(Utc.with_ymd_and_hms(2024, 3, 2, 2, 0, 0).unwrap()));
+        assert_eq!(next_occurrence_in("30 1 * * 0", now, &Utc), Some(Utc.with_ymd_and_hms(2024, 3, 3, 1, 30, 0).unwrap()));
+        assert_eq!(next_occurrence_in("0 3 * * 1-5", now, &Utc), Some(Utc.with_ymd_and_hms(2024, 3, 4, 3, 0, 0).unwrap()));
+        assert!(parse_recurrence("0 2 * * 9").is_err());
+        assert!(parse_recurrence("every night").is_err());
+    }
+
+    #[test]
+    fn test_recurrence_follows_local_time() {
+        // 02:00 in UTC+2 is midnight UTC
+        let paris_summer = FixedOffset::east_opt(2 * 3600).unwrap();
+        let now = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+        assert_eq!(next_occurrence_in("0 2 * * 6", now, &paris_summer), Some(Utc.with_ymd_and_hms(2024, 3, 2, 0, 0, 0).unwrap()));
+    }
+
+    #[test]
+    fn test_resolve_schedule() {
+        let now = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+        let later = Utc.with_ymd_and_hms(2024, 3, 1, 23, 0, 0).unwrap();
+
+        assert_eq!(resolve_schedule_in(None, None, now, &Utc), Ok(None));
+        assert_eq!(resolve_schedule_in(Some(now - chrono::Duration::hours(1)), None, now, &Utc), Ok(None));
+        assert_eq!(resolve_schedule_in(Some(later), Some("0 2 * * *"), now, &Utc), Ok(Some(later)));
+        assert_eq!(
+            resolve_schedule_in(None, Some("0 2 * * *"), now, &Utc),
+            Ok(Some(Utc.with_ymd_and_hms(2024, 3, 2, 2, 0, 0).unwrap())),
+        );
+        assert!(resolve_schedule_in(None, Some("nope"), now, &Utc).is_err());
+    }
+}
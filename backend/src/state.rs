@@ -1,24 +1,149 @@
-use std::sync::Arc;
-use crate::models::{DownloadResponse, Tag, CreateTagRequest};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use crate::models::{DownloadRequest, DownloadResponse, DownloadStatus, DownloadType, QueuedJob, RetentionReport, RetentionRule, Subscription, SubscriptionCheckResult, Tag, CreateTagRequest};
 use crate::db::Database;
 use crate::cache::VideoInfoCache;
+use crate::converter::{split_file, SplitTrack};
+use rust_media_downloader_shared::{Config, MediaBackend, MediaId, Preset, Staging, YtDlp};
+use crate::queue::DownloadQueue;
+use crate::events::{DownloadEvent, DownloadEventKind, EventBus, download_payload};
 use tracing::warn;
+use rust_media_downloader_shared::{bandwidth, AbortHandle, DownloadProgress};
+
+/// Why a running download was asked to stop
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StopReason {
+    Pause,
+    Cancel,
+    /// Restarted right away with a new bandwidth share, keeping its partial files
+    Rebalance,
+}
+
+struct RunningDownload {
+    abort: AbortHandle,
+    stop_reason: Option<StopReason>,
+    reserved_bytes: u64, // Estimated size, counted against the quota and free space while it runs
+    own_limit: Option<u64>, // Limit asked by the request itself
+    rate_limit: Option<u64>, // Share of the global limit passed to yt-dlp
+}
 
 #[derive(Clone)]
 pub struct AppState {
     db: Arc<Database>,
     video_cache: Arc<VideoInfoCache>,
+    queue: Arc<DownloadQueue>,
+    running: Arc<Mutex<HashMap<String, RunningDownload>>>,
+    events: Arc<EventBus>,
+    media: Arc<dyn MediaBackend>,
+    // Serializes playlist counter updates so concurrent entries cannot overwrite each other
+    playlist_lock: Arc<tokio::sync::Mutex<()>>,
+    // Fixed by tests; otherwise the config file is read each time so edits apply live
+    config: Option<Arc<Config>>,
 }
 
 impl AppState {
     pub fn new_with_db(db: Database) -> Self {
         use std::time::Duration;
+        use rust_media_downloader_shared::config;
+
+        let db = Arc::new(db);
+        let max_workers = config::load_config().max_concurrent_downloads;
         Self {
-            db: Arc::new(db),
+            queue: Arc::new(DownloadQueue::new(db.clone(), max_workers)),
+            db,
             video_cache: Arc::new(VideoInfoCache::new(Duration::from_secs(3600))), // 1 hour TTL
+            running: Arc::new(Mutex::new(HashMap::new())),
+            events: Arc::new(EventBus::new()),
+            media: Arc::new(YtDlp::default()),
+            playlist_lock: Arc::new(tokio::sync::Mutex::new(())),
+            config: None,
+        }
+    }
+
+    /// Current configuration
+    pub fn config(&self) -> Config {
+        match &self.config {
+            Some(config) => config.as_ref().clone(),
+            None => rust_media_downloader_shared::config::load_config(),
         }
     }
 
+    /// Use this configuration instead of the config file (library and staging directories…)
+    #[cfg(test)]
+    pub fn with_config(mut self, config: Config) -> Self {
+        self.config = Some(Arc::new(config));
+        self
+    }
+
+    /// Replace yt-dlp with an in-memory backend
+    #[cfg(test)]
+    pub fn with_media_backend(mut self, media: Arc<dyn MediaBackend>) -> Self {
+        self.media = media;
+        self
+    }
+
+    /// Fetches metadata, lists playlists and runs downloads
+    pub fn media_backend(&self) -> Arc<dyn MediaBackend> {
+        self.media.clone()
+    }
+
+    /// Reconcile downloads interrupted by the previous shutdown, before the queue starts
+    pub async fn recover_interrupted_downloads(&self) -> anyhow::Result<crate::recovery::RecoveryReport> {
+        let config = self.config();
+        crate::recovery::recover_interrupted_downloads(
+            self,
+            &self.db,
+            std::path::Path::new(&config.download_directory),
+            &rust_media_downloader_shared::staging::staging_root(&config),
+            config.resume_interrupted_downloads,
+        ).await
+    }
+
+    /// Start the queue dispatcher that feeds queued downloads to the worker pool
+    pub fn start_queue(&self) {
+        let queue = self.queue.clone();
+        let state = self.clone();
+        tokio::spawn(queue.run(state));
+    }
+
+    /// Start the poller that checks subscriptions for new uploads
+    pub fn start_subscription_poller(&self) {
+        tokio::spawn(crate::subscriptions::run(self.clone()));
+    }
+
+    /// Start the monitor that pauses downloads when free space runs low
+    pub fn start_disk_monitor(&self) {
+        tokio::spawn(crate::disk::run(self.clone()));
+    }
+
+    /// Start the monitor that applies the bandwidth schedule to running downloads
+    pub fn start_bandwidth_monitor(&self) {
+        tokio::spawn(crate::worker::run_bandwidth_monitor(self.clone()));
+    }
+
+    /// Start the cleaner that applies the enabled retention rules
+    pub fn start_retention_cleaner(&self) {
+        tokio::spawn(crate::retention::run(self.clone()));
+    }
+
+    /// Publish `disk.low` to live subscribers and webhooks
+    pub fn notify_disk_low(&self, data: serde_json::Value) {
+        self.publish_event(DownloadEventKind::DiskLow, "", data);
+    }
+
+    /// Total size of completed downloads
+    pub async fn library_size(&self) -> u64 {
+        self.db.get_library_size().await.unwrap_or_else(|e| {
+            warn!("Failed to compute library size: {}", e);
+            0
+        })
+    }
+
+    /// Start the scheduler that promotes due scheduled downloads into the queue
+    pub fn start_scheduler(&self) {
+        tokio::spawn(crate::scheduler::run(self.clone()));
+    }
+
     pub fn get_video_cache(&self) -> Arc<VideoInfoCache> {
         self.video_cache.clone()
     }
@@ -26,17 +151,424 @@ impl AppState {
     pub async fn add_download(&self, download: DownloadResponse) {
         if let Err(e) = self.db.insert_download(&download).await {
             tracing::error!("Failed to insert download: {}", e);
+            return;
         }
+        self.publish_event(DownloadEventKind::Created, &download.id, download_payload(&download));
+    }
+
+    /// Live download events, as streamed by `/api/downloads/events`
+    pub fn subscribe_events(&self) -> tokio::sync::broadcast::Receiver<DownloadEvent> {
+        self.events.subscribe()
+    }
+
+    /// Push an event to live subscribers and fire the matching webhooks with the same payload
+    fn publish_event(&self, kind: DownloadEventKind, id: &str, data: serde_json::Value) {
+        let event = self.events.publish(kind, id, data);
+        if let Some(webhook_event) = kind.webhook_event() {
+            let state = self.clone();
+            tokio::spawn(async move {
+                crate::api::webhooks::trigger_webhooks(&state, &webhook_event, event.data).await;
+            });
+        }
+    }
+
+    pub async fn enqueue_download(&self, id: &str, request: &DownloadRequest) -> anyhow::Result<()> {
+        self.queue.enqueue(id, request).await
+    }
+
+    /// Hold a download until the scheduler promotes it
+    pub async fn schedule_download(&self, id: &str, request: &DownloadRequest) -> anyhow::Result<()> {
+        self.queue.schedule(id, request).await
+    }
+
+    pub async fn get_scheduled_downloads(&self) -> Vec<DownloadResponse> {
+        self.db.get_scheduled_downloads().await.unwrap_or_default()
+    }
+
+    /// Queue every scheduled download that is due; a recurring one gets its next run scheduled
+    pub async fn promote_due_downloads(&self) -> anyhow::Result<usize> {
+        let now = chrono::Utc::now();
+        let due = self.db.get_due_scheduled_downloads(now).await?;
+
+        for mut download in due.iter().cloned() {
+            let request = self.db.get_job_request(&download.id).await?
+                .unwrap_or_else(|| DownloadRequest::for_url(download.url.clone(), download.download_type.clone()));
+
+            // Pending first: the worker skips jobs whose download is in any other state
+            download.set_status(DownloadStatus::Pending, "Download queued (scheduled)".to_string());
+            self.update_download(&download.id, download.clone()).await;
+            if !self.queue.promote(&download.id).await? {
+                self.queue.enqueue(&download.id, &request).await?;
+            }
+
+            let Some(recurrence) = download.recurrence.clone() else {
+                continue;
+            };
+            let Some(next_run) = crate::scheduler::next_occurrence(&recurrence, now) else {
+                tracing::warn!("Recurrence '{}' of download {} has no next run", recurrence, download.id);
+                continue;
+            };
+            let mut next = DownloadResponse::new(download.url.clone(), download.download_type.clone());
+            next.title = download.title.clone();
+            next.author = download.author.clone();
+            next.thumbnail = download.thumbnail.clone();
+            next.duration = download.duration;
+            next.scheduled_at = Some(next_run);
+            next.recurrence = Some(recurrence);
+            next.set_status(DownloadStatus::Scheduled, format!("Scheduled for {}", next_run.to_rfc3339()));
+            self.add_download(next.clone()).await;
+            self.queue.schedule(&next.id, &DownloadRequest { scheduled_at: Some(next_run), ..request }).await?;
+        }
+
+        Ok(due.len())
+    }
+
+    pub async fn create_subscription(&self, subscription: &Subscription) -> anyhow::Result<()> {
+        self.db.create_subscription(subscription).await
+    }
+
+    pub async fn get_subscription(&self, id: &str) -> Option<Subscription> {
+        self.db.get_subscription(id).await.ok().flatten()
+    }
+
+    pub async fn get_all_subscriptions(&self) -> anyhow::Result<Vec<Subscription>> {
+        self.db.get_all_subscriptions().await
+    }
+
+    pub async fn update_subscription(&self, subscription: &Subscription) -> anyhow::Result<()> {
+        self.db.update_subscription(subscription).await
+    }
+
+    pub async fn delete_subscription(&self, id: &str) -> anyhow::Result<bool> {
+        self.db.delete_subscription(id).await
+    }
+
+    pub async fn get_subscription_history(&self, id: &str) -> anyhow::Result<Vec<DownloadResponse>> {
+        self.db.get_subscription_downloads(id).await
+    }
+
+    /// Poll one subscription right away
+    pub async fn check_subscription(&self, subscription: &mut Subscription) -> anyhow::Result<SubscriptionCheckResult> {
+        crate::subscriptions::check_subscription(self, &self.db, subscription).await
+    }
+
+    /// Poll every subscription whose check interval has elapsed
+    pub async fn check_due_subscriptions(&self) -> anyhow::Result<Vec<SubscriptionCheckResult>> {
+        let now = chrono::Utc::now();
+        let mut results = Vec::new();
+        for mut subscription in self.db.get_all_subscriptions().await? {
+            if !crate::subscriptions::is_due(&subscription, now) {
+                continue;
+            }
+            match self.check_subscription(&mut subscription).await {
+                Ok(result) => results.push(result),
+                Err(e) => tracing::warn!("Subscription {} check failed: {}", subscription.id, e),
+            }
+        }
+        Ok(results)
+    }
+
+    // Presets
+    pub async fn get_all_presets(&self) -> anyhow::Result<Vec<Preset>> {
+        self.db.get_all_presets().await
+    }
+
+    pub async fn get_preset(&self, id: &str) -> anyhow::Result<Option<Preset>> {
+        self.db.get_preset(id).await
+    }
+
+    pub async fn create_preset(&self, preset: &Preset) -> anyhow::Result<()> {
+        self.db.create_preset(preset).await
+    }
+
+    pub async fn update_preset(&self, preset: &Preset) -> anyhow::Result<()> {
+        self.db.update_preset(preset).await
+    }
+
+    pub async fn delete_preset(&self, id: &str) -> anyhow::Result<bool> {
+        self.db.delete_preset(id).await
+    }
+
+    // Retention
+    pub async fn get_all_retention_rules(&self) -> anyhow::Result<Vec<RetentionRule>> {
+        self.db.get_all_retention_rules().await
+    }
+
+    pub async fn get_retention_rule(&self, id: &str) -> anyhow::Result<Option<RetentionRule>> {
+        self.db.get_retention_rule(id).await
+    }
+
+    pub async fn create_retention_rule(&self, rule: &RetentionRule) -> anyhow::Result<()> {
+        self.db.create_retention_rule(rule).await
+    }
+
+    pub async fn update_retention_rule(&self, rule: &RetentionRule) -> anyhow::Result<()> {
+        self.db.update_retention_rule(rule).await
+    }
+
+    pub async fn delete_retention_rule(&self, id: &str) -> anyhow::Result<bool> {
+        self.db.delete_retention_rule(id).await
+    }
+
+    /// Apply a rule now, or list what it would remove for a dry run
+    pub async fn run_retention_rule(&self, rule: &mut RetentionRule, dry_run: bool) -> anyhow::Result<RetentionReport> {
+        crate::retention::run_rule(self, rule, dry_run).await
+    }
+
+    pub async fn save_retention_report(&self, report: &RetentionReport) -> anyhow::Result<()> {
+        self.db.insert_retention_report(report).await
+    }
+
+    pub async fn get_retention_reports(&self) -> anyhow::Result<Vec<RetentionReport>> {
+        self.db.get_retention_reports(crate::retention::REPORT_LIMIT).await
+    }
+
+    /// Keep a download whose files a retention rule deleted, marked as such
+    pub async fn mark_files_removed(&self, id: &str, rule_name: &str) {
+        if let Some(mut download) = self.get_download(id).await {
+            download.files_removed_at = Some(chrono::Utc::now());
+            download.message = format!("Files removed by retention rule '{}'", rule_name);
+            self.update_download(id, download).await;
+        }
+    }
+
+    pub async fn get_queued_jobs(&self) -> Vec<QueuedJob> {
+        self.queue.queued_jobs().await.unwrap_or_default()
+    }
+
+    pub fn queue_workers(&self) -> (usize, usize) {
+        (self.queue.active_workers(), self.queue.max_workers())
+    }
+
+    // Live yt-dlp processes, so they can be paused or cancelled
+    pub fn register_running(&self, id: &str, own_limit: Option<u64>) -> (AbortHandle, Option<u64>) {
+        let global_limit = crate::worker::current_rate_limit(&self.config());
+        self.register_running_with(id, own_limit, global_limit)
+    }
+
+    /// Register a process and return its bandwidth share; the others are rebalanced to the new count
+    fn register_running_with(&self, id: &str, own_limit: Option<u64>, global_limit: Option<u64>) -> (AbortHandle, Option<u64>) {
+        let abort = AbortHandle::new();
+        let mut running = self.running.lock().unwrap();
+        running.insert(id.to_string(), RunningDownload {
+            abort: abort.clone(),
+            stop_reason: None,
+            reserved_bytes: 0,
+            own_limit,
+            rate_limit: None,
+        });
+        rebalance(&mut running, global_limit, Some(id));
+        (abort, running[id].rate_limit)
+    }
+
+    /// Restart the running downloads whose bandwidth share no longer matches the
+    /// global limit in force and the number of downloads sharing it
+    pub fn rebalance_bandwidth(&self) {
+        let global_limit = crate::worker::current_rate_limit(&self.config());
+        rebalance(&mut self.running.lock().unwrap(), global_limit, None);
+    }
+
+    /// Record the estimated size of a running download
+    pub fn reserve_space(&self, id: &str, bytes: u64) {
+        if let Some(running) = self.running.lock().unwrap().get_mut(id) {
+            running.reserved_bytes = bytes;
+        }
+    }
+
+    /// Estimated size of every running download
+    pub fn reserved_space(&self) -> u64 {
+        self.running.lock().unwrap().values().map(|r| r.reserved_bytes).sum()
+    }
+
+    /// Pause every running download that is not already stopping, returns their ids
+    pub fn pause_running_downloads(&self) -> Vec<String> {
+        let ids: Vec<String> = self.running.lock().unwrap().iter()
+            .filter(|(_, running)| running.stop_reason.is_none())
+            .map(|(id, _)| id.clone())
+            .collect();
+        ids.into_iter().filter(|id| self.request_stop(id, StopReason::Pause)).collect()
+    }
+
+    /// Put back in the queue the given downloads that are still paused
+    pub async fn resume_paused_downloads(&self, ids: &[String]) {
+        for id in ids {
+            let Some(mut download) = self.get_download(id).await else { continue };
+            if download.status != DownloadStatus::Paused {
+                continue;
+            }
+            download.set_status(DownloadStatus::Pending, "Download queued".to_string());
+            self.update_download(id, download.clone()).await;
+            match self.resume_download(id).await {
+                Ok(true) => {}
+                result => {
+                    if let Err(e) = result {
+                        warn!("Failed to resume download {}: {}", id, e);
+                    }
+                    download.set_status(DownloadStatus::Paused, "Download paused".to_string());
+                    self.update_download(id, download).await;
+                }
+            }
+        }
+    }
+
+    /// Forget a finished process and return why it was stopped, if it was;
+    /// the downloads still running get the bandwidth it leaves
+    pub fn unregister_running(&self, id: &str) -> Option<StopReason> {
+        let stop_reason = self.running.lock().unwrap().remove(id).and_then(|r| r.stop_reason);
+        // A rebalanced download registers again right away
+        if stop_reason != Some(StopReason::Rebalance) {
+            self.rebalance_bandwidth();
+        }
+        stop_reason
+    }
+
+    /// Ask a running download to stop, returns false if no process is running for it
+    pub fn request_stop(&self, id: &str, reason: StopReason) -> bool {
+        match self.running.lock().unwrap().get_mut(id) {
+            Some(running) => {
+                // A cancel always wins over a pending pause
+                if running.stop_reason != Some(StopReason::Cancel) {
+                    running.stop_reason = Some(reason);
+                }
+                running.abort.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub async fn pause_queued_download(&self, id: &str) -> anyhow::Result<bool> {
+        self.queue.pause_queued(id).await
+    }
+
+    pub async fn resume_download(&self, id: &str) -> anyhow::Result<bool> {
+        self.queue.resume(id).await
+    }
+
+    /// Requeue a failed download with its original request; downloads that failed
+    /// before requests were kept are retried with default options
+    pub async fn retry_download(&self, download: &DownloadResponse) -> anyhow::Result<()> {
+        if !self.queue.retry(&download.id).await? {
+            let request = DownloadRequest::for_url(download.url.clone(), download.download_type.clone());
+            self.queue.enqueue(&download.id, &request).await?;
+        }
+        Ok(())
+    }
+
+    /// Remove a job that is not running from the queue and delete its partial files
+    pub async fn discard_queued_download(&self, id: &str) -> anyhow::Result<()> {
+        let partial_files = self.queue.remove(id).await?;
+        remove_partial_files(partial_files.iter().map(std::path::PathBuf::from));
+        Staging::for_job(&self.config(), id).discard();
+        Ok(())
     }
 
     pub async fn get_download(&self, id: &str) -> Option<DownloadResponse> {
         self.db.get_download(id).await.ok().flatten()
     }
 
+    /// Persist a download row; every status change goes through here and is published
     pub async fn update_download(&self, id: &str, download: DownloadResponse) {
-        if let Err(e) = self.db.update_download(&download).await {
+        let parent_id = download.parent_id.clone();
+        let status_changed = self.write_download(id, &download).await;
+
+        // Playlist counters follow their entries' status
+        if let (true, Some(parent_id)) = (status_changed, parent_id) {
+            self.refresh_playlist(&parent_id).await;
+        }
+    }
+
+    /// Write and publish a row, returns whether its status changed
+    async fn write_download(&self, id: &str, download: &DownloadResponse) -> bool {
+        let previous = self.db.get_download_status(id).await.ok().flatten();
+        if let Err(e) = self.db.update_download(download).await {
             tracing::error!("Failed to update download {}: {}", id, e);
+            return false;
+        }
+        let kind = DownloadEventKind::for_status(previous, download.status);
+        self.publish_event(kind, id, download_payload(download));
+        previous != Some(download.status)
+    }
+
+    /// Earlier download of the same media as the same type, for duplicate detection
+    pub async fn find_duplicate(&self, media_id: &MediaId, download_type: &DownloadType) -> Option<DownloadResponse> {
+        self.db.find_download_by_media(media_id, download_type).await.ok().flatten()
+    }
+
+    pub async fn get_playlist_items(&self, parent_id: &str) -> Vec<DownloadResponse> {
+        self.db.get_child_downloads(parent_id).await.unwrap_or_default()
+    }
+
+    /// Recompute a playlist's counters and status from its entries
+    pub async fn refresh_playlist(&self, parent_id: &str) {
+        let _guard = self.playlist_lock.lock().await;
+
+        // Tracks of a split download do not drive their parent's status
+        let Some(mut parent) = self.get_download(parent_id).await.filter(|parent| parent.is_playlist) else {
+            return;
+        };
+        let items = self.get_playlist_items(parent_id).await;
+        let count = |status: DownloadStatus| items.iter().filter(|i| i.status == status).count();
+        let (total, completed) = (items.len(), count(DownloadStatus::Completed));
+        let (failed, cancelled) = (count(DownloadStatus::Failed), count(DownloadStatus::Cancelled));
+
+        parent.total_items = Some(total as i32);
+        parent.completed_items = Some(completed as i32);
+        parent.progress = if total == 0 { 100.0 } else { completed as f32 * 100.0 / total as f32 };
+        parent.output_files = Some(items.iter().filter_map(|i| i.file_path.clone()).collect());
+
+        if completed + failed + cancelled < total {
+            parent.set_status(DownloadStatus::Processing, format!("{}/{} entries downloaded", completed, total));
+        } else if completed == 0 && total > 0 {
+            parent.set_status(DownloadStatus::Failed, format!("Download failed: none of the {} playlist entries could be downloaded", total));
+        } else {
+            parent.set_status(DownloadStatus::Completed, format!(
+                "Playlist finished: {}/{} entries downloaded, {} failed, {} cancelled", completed, total, failed, cancelled
+            ));
+        }
+
+        self.write_download(parent_id, &parent).await;
+    }
+
+    pub async fn update_progress(&self, id: &str, progress: &DownloadProgress) {
+        if let Err(e) = self.db.update_progress(
+            id,
+            progress.percent,
+            Some(progress.downloaded_bytes),
+            (progress.total_bytes > 0).then_some(progress.total_bytes),
+            progress.speed,
+            progress.eta,
+        ).await {
+            tracing::error!("Failed to update progress for download {}: {}", id, e);
+            return;
+        }
+        self.publish_event(DownloadEventKind::Progress, id, serde_json::json!({
+            "download_id": id,
+            "progress": progress.percent,
+            "downloaded_bytes": progress.downloaded_bytes,
+            "total_bytes": (progress.total_bytes > 0).then_some(progress.total_bytes),
+            "speed": progress.speed,
+            "eta": progress.eta,
+        }));
+    }
+
+    /// Follow yt-dlp between downloading and a post-processing step (merge, audio extraction...)
+    pub async fn set_download_phase(&self, id: &str, post_processing: Option<&str>) {
+        let Some(mut download) = self.get_download(id).await else {
+            return;
+        };
+        if !matches!(download.status, DownloadStatus::Downloading | DownloadStatus::Processing) {
+            return;
+        }
+        match post_processing {
+            Some(step) => {
+                download.set_status(DownloadStatus::Processing, format!("Post-processing: {}", step));
+                download.speed = None;
+                download.eta = None;
+            }
+            None => download.set_status(DownloadStatus::Downloading, "Downloading...".to_string()),
         }
+        self.update_download(id, download).await;
     }
 
     pub async fn get_all_downloads(&self) -> Vec<DownloadResponse> {
@@ -47,16 +579,50 @@ impl AppState {
         self.db.get_downloads_paginated(page, per_page).await.unwrap_or((Vec::new(), 0))
     }
 
+    /// Delete every file tracked for a download and its children (playlist entries, split tracks),
+    /// refusing paths outside the download directory
+    pub async fn delete_download_files(&self, download: &DownloadResponse) -> Result<(), Vec<String>> {
+        let root = std::path::PathBuf::from(self.config().download_directory);
+        let mut downloads = vec![download.clone()];
+        downloads.extend(self.get_playlist_items(&download.id).await);
+
+        let mut errors = Vec::new();
+        for download in &downloads {
+            if let Err(e) = crate::disk::delete_download_files(download, &root).await {
+                errors.extend(e);
+            }
+        }
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
     pub async fn remove_download(&self, id: &str) -> Option<DownloadResponse> {
         // Get the download first
         let download = self.get_download(id).await;
-        
+
+        // A playlist takes its entries with it, a split download its tracks
+        if download.is_some() {
+            for item in self.get_playlist_items(id).await {
+                self.request_stop(&item.id, StopReason::Cancel);
+                if let Err(e) = self.db.delete_download(&item.id).await {
+                    tracing::error!("Failed to delete playlist entry {}: {}", item.id, e);
+                    continue;
+                }
+                self.publish_event(DownloadEventKind::Deleted, &item.id, download_payload(&item));
+            }
+        }
+
         // Delete from database
         if let Err(e) = self.db.delete_download(id).await {
             tracing::error!("Failed to delete download {}: {}", id, e);
             return None;
         }
-        
+        if let Some(download) = &download {
+            self.publish_event(DownloadEventKind::Deleted, id, download_payload(download));
+            if let Some(parent_id) = &download.parent_id {
+                self.refresh_playlist(parent_id).await;
+            }
+        }
+
         download
     }
 
@@ -119,7 +685,6 @@ impl AppState {
 
     pub async fn convert_download(&self, id: &str, format: &str, keep_original: bool) -> anyhow::Result<()> {
         use crate::converter::{convert_file, ConversionFormat};
-        use rust_media_downloader_shared::config;
         use std::path::PathBuf;
         use std::fs;
 
@@ -141,7 +706,7 @@ impl AppState {
         };
 
         // Load config to get download directory
-        let config = config::load_config();
+        let config = self.config();
         let download_dir = PathBuf::from(&config.download_directory);
         
         // Construct full path (same logic as serve_file)
@@ -225,6 +790,70 @@ impl AppState {
         }
     }
 
+    /// Cut a completed download into one file per track, each recorded as a child entry.
+    ///
+    /// The original file is kept; tracks of an earlier split are replaced.
+    pub async fn split_download(&self, id: &str, tracks: &[SplitTrack]) -> anyhow::Result<Vec<DownloadResponse>> {
+        let Some(mut download) = self.get_download(id).await else {
+            anyhow::bail!("Download not found");
+        };
+        if download.status != DownloadStatus::Completed {
+            anyhow::bail!("Download must be completed before splitting");
+        }
+        if download.is_playlist {
+            anyhow::bail!("A playlist cannot be split, split its entries instead");
+        }
+        if tracks.is_empty() {
+            anyhow::bail!("At least one track is required");
+        }
+        let Some(file_path) = download.file_path.clone() else {
+            anyhow::bail!("File path not found");
+        };
+
+        let config = self.config();
+        let download_dir = std::path::PathBuf::from(&config.download_directory);
+        let input_path = if std::path::Path::new(&file_path).is_absolute() {
+            std::path::PathBuf::from(&file_path)
+        } else {
+            download_dir.join(&file_path)
+        };
+
+        for previous in self.get_playlist_items(id).await {
+            if let Err(errors) = crate::disk::delete_download_files(&previous, &download_dir).await {
+                warn!("Failed to delete previous track files of {}: {}", id, errors.join(", "));
+            }
+            self.remove_download(&previous.id).await;
+        }
+
+        let files = split_file(&input_path, tracks, download.title.as_deref(), config.max_filename_length).await?;
+
+        let mut children = Vec::with_capacity(files.len());
+        for (index, (track, file)) in tracks.iter().zip(files).enumerate() {
+            let file = file.to_string_lossy().to_string();
+            let mut child = DownloadResponse::new(download.url.clone(), download.download_type.clone());
+            child.parent_id = Some(id.to_string());
+            child.playlist_index = Some(index as u32 + 1);
+            child.title = Some(track.title.clone());
+            child.author = download.author.clone();
+            child.thumbnail = download.thumbnail.clone();
+            child.duration = match track.end {
+                Some(end) => Some((end - track.start).round() as u64),
+                None => download.duration.map(|duration| duration.saturating_sub(track.start as u64)),
+            };
+            child.file_size = std::fs::metadata(&file).ok().map(|metadata| metadata.len());
+            child.file_path = Some(file.clone());
+            child.output_files = Some(vec![file]);
+            child.progress = 100.0;
+            child.set_status(DownloadStatus::Completed, format!("Track {}/{}", index + 1, tracks.len()));
+            self.add_download(child.clone()).await;
+            children.push(child);
+        }
+
+        download.message = format!("Split into {} tracks", children.len());
+        self.update_download(id, download).await;
+        Ok(children)
+    }
+
     pub async fn toggle_favorite(&self, id: &str) -> anyhow::Result<()> {
         let mut download = match self.get_download(id).await {
             Some(d) => d,
@@ -261,3 +890,267 @@ impl AppState {
         self.db.delete_webhook(id).await
     }
 }
+
+/// Delete leftovers of an interrupted yt-dlp run (`.part`, `.ytdl`, fragments)
+pub fn remove_partial_files(paths: impl IntoIterator<Item = std::path::PathBuf>) {
+    for path in paths {
+        if let Err(e) = std::fs::remove_file(&path) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                warn!("Failed to remove partial file {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+/// Growth of a bandwidth share below which running downloads are left alone
+const REBALANCE_TOLERANCE: f64 = 0.25;
+
+/// Give every running download its share of `global_limit`; `newcomer` just registered and
+/// takes its share directly, the others are stopped for a restart when their share has to change
+fn rebalance(running: &mut HashMap<String, RunningDownload>, global_limit: Option<u64>, newcomer: Option<&str>) {
+    // Downloads being rebalanced come back at once: they still count
+    let sharing = |r: &RunningDownload| matches!(r.stop_reason, None | Some(StopReason::Rebalance));
+    let active_downloads = running.values().filter(|r| sharing(r)).count();
+
+    for (id, download) in running.iter_mut() {
+        let share = bandwidth::share_for(global_limit, active_downloads, download.own_limit);
+        if Some(id.as_str()) == newcomer {
+            download.rate_limit = share;
+        } else if download.stop_reason.is_none() && needs_restart(download.rate_limit, share) {
+            tracing::info!("Restarting download {} with a bandwidth share of {:?} B/s", id, share);
+            download.stop_reason = Some(StopReason::Rebalance);
+            download.abort.abort();
+        }
+    }
+}
+
+/// A share that drops must apply now to keep the global budget; a small gain waits for
+/// the next natural restart rather than making yt-dlp reconnect and probe formats again
+fn needs_restart(applied: Option<u64>, share: Option<u64>) -> bool {
+    match (applied, share) {
+        (None, None) => false,
+        (None, Some(_)) | (Some(_), None) => true,
+        (Some(applied), Some(share)) => {
+            share < applied || share as f64 > applied as f64 * (1.0 + REBALANCE_TOLERANCE)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_media_downloader_shared::DownloadError;
+
+    async fn test_state() -> AppState {
+        AppState::new_with_db(Database::in_memory().await)
+    }
+
+    #[tokio::test]
+    async fn test_playlist_counters_follow_entries() {
+        let state = test_state().await;
+        let mut parent = DownloadResponse::new("https://www.youtube.com/playlist?list=PL1".to_string(), DownloadType::Audio);
+        parent.is_playlist = true;
+        parent.status = DownloadStatus::Processing;
+        state.add_download(parent.clone()).await;
+
+        let mut entries = Vec::new();
+        for index in 1..=3 {
+            let mut entry = DownloadResponse::new(format!("https://www.youtube.com/watch?v={}", index), DownloadType::Audio);
+            entry.parent_id = Some(parent.id.clone());
+            entry.playlist_index = Some(index);
+            state.add_download(entry.clone()).await;
+            entries.push(entry);
+        }
+
+        let mut first = entries[0].clone();
+        first.set_status(DownloadStatus::Completed, "done".to_string());
+        first.file_path = Some("/tmp/one.mp3".to_string());
+        state.update_download(&first.id.clone(), first).await;
+
+        let playlist = state.get_download(&parent.id).await.unwrap();
+        assert_eq!(playlist.status, DownloadStatus::Processing);
+        assert_eq!((playlist.total_items, playlist.completed_items), (Some(3), Some(1)));
+
+        let mut second = entries[1].clone();
+        second.set_status(DownloadStatus::Failed, "failed".to_string());
+        state.update_download(&second.id.clone(), second).await;
+        let mut third = entries[2].clone();
+        third.set_status(DownloadStatus::Completed, "done".to_string());
+        state.update_download(&third.id.clone(), third).await;
+
+        let playlist = state.get_download(&parent.id).await.unwrap();
+        assert_eq!(playlist.status, DownloadStatus::Completed);
+        assert_eq!(playlist.completed_items, Some(2));
+        assert_eq!(playlist.output_files, Some(vec!["/tmp/one.mp3".to_string()]));
+
+        let items = state.get_playlist_items(&parent.id).await;
+        assert_eq!(items.iter().map(|i| i.playlist_index).collect::<Vec<_>>(), vec![Some(1), Some(2), Some(3)]);
+    }
+
+    #[tokio::test]
+    async fn test_split_tracks_follow_but_do_not_drive_their_parent() {
+        let state = test_state().await;
+        let mut album = DownloadResponse::new("https://www.youtube.com/watch?v=album".to_string(), DownloadType::Audio);
+        album.set_status(DownloadStatus::Completed, "Split into 2 tracks".to_string());
+        state.add_download(album.clone()).await;
+
+        let mut tracks = Vec::new();
+        for index in 1..=2 {
+            let mut track = DownloadResponse::new(album.url.clone(), DownloadType::Audio);
+            track.parent_id = Some(album.id.clone());
+            track.playlist_index = Some(index);
+            track.set_status(DownloadStatus::Completed, format!("Track {}/2", index));
+            state.add_download(track.clone()).await;
+            tracks.push(track);
+        }
+
+        let mut converting = tracks[0].clone();
+        converting.set_status(DownloadStatus::Converting, "Conversion".to_string());
+        state.update_download(&converting.id.clone(), converting).await;
+        let parent = state.get_download(&album.id).await.unwrap();
+        assert!(!parent.is_playlist);
+        assert_eq!((parent.status, parent.message.as_str()), (DownloadStatus::Completed, "Split into 2 tracks"));
+
+        state.remove_download(&album.id).await;
+        for track in tracks {
+            assert!(state.get_download(&track.id).await.is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_due_recurring_download_is_queued_and_rescheduled() {
+        let state = test_state().await;
+        let mut download = DownloadResponse::new("https://www.youtube.com/watch?v=weekly".to_string(), DownloadType::Video);
+        download.status = DownloadStatus::Scheduled;
+        download.scheduled_at = Some(chrono::Utc::now() - chrono::Duration::minutes(1));
+        download.recurrence = Some("0 2 * * 6".to_string());
+        let request = DownloadRequest::for_url(download.url.clone(), DownloadType::Video);
+        state.add_download(download.clone()).await;
+        state.schedule_download(&download.id, &request).await.unwrap();
+        assert!(state.get_queued_jobs().await.is_empty());
+
+        assert_eq!(state.promote_due_downloads().await.unwrap(), 1);
+
+        let promoted = state.get_download(&download.id).await.unwrap();
+        assert_eq!(promoted.status, DownloadStatus::Pending);
+        assert_eq!(state.get_queued_jobs().await.len(), 1);
+
+        let next = state.get_scheduled_downloads().await;
+        assert_eq!(next.len(), 1);
+        assert_eq!(next[0].recurrence.as_deref(), Some("0 2 * * 6"));
+        assert!(next[0].scheduled_at.unwrap() > chrono::Utc::now());
+        // Not due yet: nothing more to promote
+        assert_eq!(state.promote_due_downloads().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_lookup_ignores_failed_downloads() {
+        let state = test_state().await;
+        let media_id = MediaId::from_url("https://youtu.be/dQw4w9WgXcQ").unwrap();
+
+        let mut failed = DownloadResponse::new("https://youtu.be/dQw4w9WgXcQ".to_string(), DownloadType::Video);
+        failed.set_media_id(media_id.clone());
+        failed.status = DownloadStatus::Failed;
+        state.add_download(failed).await;
+        assert!(state.find_duplicate(&media_id, &DownloadType::Video).await.is_none());
+
+        let mut done = DownloadResponse::new("https://www.youtube.com/watch?v=dQw4w9WgXcQ".to_string(), DownloadType::Video);
+        done.extractor = Some("youtube".to_string());
+        done.video_id = Some("dQw4w9WgXcQ".to_string());
+        done.status = DownloadStatus::Completed;
+        state.add_download(done.clone()).await;
+
+        assert_eq!(state.find_duplicate(&media_id, &DownloadType::Video).await.map(|d| d.id), Some(done.id));
+        // The audio of a downloaded video is a different file
+        assert!(state.find_duplicate(&media_id, &DownloadType::Audio).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_failure_reason_is_stored_and_cleared_on_retry() {
+        let state = test_state().await;
+        let mut download = DownloadResponse::new("https://www.youtube.com/watch?v=blocked".to_string(), DownloadType::Video);
+        state.add_download(download.clone()).await;
+
+        download.set_status(DownloadStatus::Failed, "Download failed".to_string());
+        download.set_error(DownloadError::GeoBlocked);
+        state.update_download(&download.id.clone(), download.clone()).await;
+
+        let failed = state.get_download(&download.id).await.unwrap();
+        assert_eq!(failed.error_code, Some(DownloadError::GeoBlocked));
+        assert_eq!(failed.error_message.as_deref(), Some(DownloadError::GeoBlocked.message()));
+
+        download.set_status(DownloadStatus::Pending, "Download queued".to_string());
+        state.update_download(&download.id.clone(), download.clone()).await;
+        let retried = state.get_download(&download.id).await.unwrap();
+        assert_eq!((retried.error_code, retried.error_message), (None, None));
+    }
+
+    #[tokio::test]
+    async fn test_presets_fill_unset_fields_and_builtins_are_read_only() {
+        let state = test_state().await;
+        let presets = state.get_all_presets().await.unwrap();
+        assert!(presets.iter().any(|p| p.id == "video-1080p-mp4-fr-subs" && p.builtin));
+        assert!(!state.delete_preset("video-1080p-mp4-fr-subs").await.unwrap());
+
+        let preset = state.get_preset("video-1080p-mp4-fr-subs").await.unwrap().unwrap();
+        let mut request = DownloadRequest::for_url("https://www.youtube.com/watch?v=preset".to_string(), DownloadType::Video);
+        request.resolution = Some("720p".to_string());
+        request.apply_preset(&preset).unwrap();
+        assert_eq!(request.resolution.as_deref(), Some("720p"));
+        assert_eq!(request.format.as_deref(), Some("mp4"));
+        assert_eq!((request.download_subtitles, request.subtitle_language.as_deref()), (Some(true), Some("fr")));
+
+        let audio = state.get_preset("audio-mp3-320k").await.unwrap().unwrap();
+        assert!(request.apply_preset(&audio).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_bandwidth_is_rebalanced_between_running_downloads() {
+        let state = test_state().await;
+        let budget = Some(1_000_000);
+
+        let (first, rate) = state.register_running_with("first", None, budget);
+        assert_eq!(rate, budget);
+
+        // A second download halves the first one's share: it is stopped to restart with it
+        let (second, rate) = state.register_running_with("second", None, budget);
+        assert_eq!(rate, Some(500_000));
+        assert!(first.is_aborted());
+        assert!(!second.is_aborted());
+
+        assert_eq!(state.running.lock().unwrap().remove("first").unwrap().stop_reason, Some(StopReason::Rebalance));
+        let (first, rate) = state.register_running_with("first", None, budget);
+        assert_eq!(rate, Some(500_000));
+        assert!(!first.is_aborted());
+        assert!(!second.is_aborted(), "a restart must not disturb downloads already at their share");
+
+        // A new schedule window changes everyone's share
+        rebalance(&mut state.running.lock().unwrap(), Some(200_000), None);
+        assert!(first.is_aborted() && second.is_aborted());
+    }
+
+    #[tokio::test]
+    async fn test_slightly_larger_share_does_not_restart_downloads() {
+        let state = test_state().await;
+        let budget = Some(1_000_000);
+        let own_limit = Some(400_000);
+
+        // Three downloads capped at 400 kB/s, restarted like the worker does until all run at a third of the budget
+        let mut handles = HashMap::new();
+        for id in ["a", "b", "c"] {
+            handles.insert(id, state.register_running_with(id, own_limit, budget).0);
+        }
+        for id in ["a", "b"] {
+            assert!(handles[id].is_aborted());
+            state.running.lock().unwrap().remove(id);
+            handles.insert(id, state.register_running_with(id, own_limit, budget).0);
+        }
+        assert!(handles.values().all(|abort| !abort.is_aborted()));
+        assert_eq!(state.running.lock().unwrap()["a"].rate_limit, Some(333_333));
+
+        // One finishes: the others could go from 333 to 400 kB/s, not worth a restart
+        state.running.lock().unwrap().remove("c");
+        rebalance(&mut state.running.lock().unwrap(), budget, None);
+        assert!(!handles["a"].is_aborted() && !handles["b"].is_aborted());
+    }
+}
@@ -0,0 +1,134 @@
+use chrono::{DateTime, Utc};
+use std::time::Duration;
+use crate::db::Database;
+use crate::models::{DownloadRequest, DownloadResponse, Subscription, SubscriptionCheckResult};
+use crate::state::AppState;
+
+pub const DEFAULT_CHECK_INTERVAL_SECS: u64 = 3600;
+/// Listing a channel is a full yt-dlp run, do not hammer the site
+pub const MIN_CHECK_INTERVAL_SECS: u64 = 300;
+
+/// How often the poller looks for subscriptions due for a check
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Whether an enabled subscription's check interval has elapsed
+pub fn is_due(subscription: &Subscription, now: DateTime<Utc>) -> bool {
+    subscription.enabled && subscription.last_checked_at.is_none_or(|checked_at| {
+        now >= checked_at + chrono::Duration::seconds(subscription.check_interval_secs as i64)
+    })
+}
+
+/// List the subscription's source and queue a download for every entry not in its archive.
+///
+/// The first check of a subscription only records the current entries, unless it was
+/// created with `download_existing`. The check time and error are saved either way.
+pub async fn check_subscription(state: &AppState, db: &Database, subscription: &mut Subscription) -> anyhow::Result<SubscriptionCheckResult> {
+    let first_check = subscription.last_checked_at.is_none();
+    let listing = state.media_backend().list_playlist(&subscription.request.url, subscription.request.cookies_browser.as_deref()).await
+        .and_then(|info| info.ok_or_else(|| anyhow::anyhow!("URL is not a channel or playlist")));
+    subscription.last_checked_at = Some(Utc::now());
+
+    let info = match listing {
+        Ok(info) => info,
+        Err(e) => {
+            subscription.last_error = Some(e.to_string());
+            db.update_subscription(subscription).await?;
+            return Err(e);
+        }
+    };
+
+    let mut result = SubscriptionCheckResult {
+        subscription_id: subscription.id.clone(),
+        entries: info.entries.len(),
+        new_entries: 0,
+        queued: Vec::new(),
+    };
+
+    // Listings are newest first: queue the oldest new upload first
+    for entry in info.entries.into_iter().rev() {
+        if first_check && !subscription.download_existing {
+            if db.record_subscription_entry(&subscription.id, &entry.id, None).await? {
+                result.new_entries += 1;
+            }
+            continue;
+        }
+
+        let mut download = DownloadResponse::new(entry.url.clone(), subscription.request.download_type.clone());
+        download.title = entry.title;
+        download.duration = entry.duration;
+
+        // The archive row is the claim: a concurrent check of the same entry stops here
+        if !db.record_subscription_entry(&subscription.id, &entry.id, Some(&download.id)).await? {
+            continue;
+        }
+        result.new_entries += 1;
+
+        let request = DownloadRequest {
+            url: entry.url,
+            download_playlist: Some(false),
+            scheduled_at: None,
+            recurrence: None,
+            ..subscription.request.clone()
+        };
+        state.add_download(download.clone()).await;
+        if let Err(e) = state.enqueue_download(&download.id, &request).await {
+            tracing::error!("Failed to queue {} for subscription {}: {}", download.id, subscription.id, e);
+            download.set_status(crate::models::DownloadStatus::Failed, format!("Download failed: could not queue entry: {}", e));
+            state.update_download(&download.id.clone(), download).await;
+            continue;
+        }
+        result.queued.push(download.id);
+    }
+
+    subscription.last_error = None;
+    db.update_subscription(subscription).await?;
+    Ok(result)
+}
+
+/// Background task checking every subscription whose interval has elapsed
+pub async fn run(state: AppState) {
+    tracing::info!("📡 Subscription poller started");
+
+    loop {
+        match state.check_due_subscriptions().await {
+            Ok(results) => {
+                for result in results.iter().filter(|r| !r.queued.is_empty()) {
+                    tracing::info!("📡 Subscription {}: {} new upload(s) queued", result.subscription_id, result.queued.len());
+                }
+            }
+            Err(e) => tracing::error!("Failed to check subscriptions: {}", e),
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::DownloadType;
+
+    #[test]
+    fn test_is_due() {
+        let now = Utc::now();
+        let mut subscription = Subscription {
+            id: "sub".to_string(),
+            name: None,
+            request: DownloadRequest::for_url("https://www.youtube.com/@channel/videos".to_string(), DownloadType::Video),
+            check_interval_secs: 3600,
+            enabled: true,
+            download_existing: false,
+            created_at: now,
+            last_checked_at: None,
+            last_error: None,
+        };
+        assert!(is_due(&subscription, now));
+
+        subscription.last_checked_at = Some(now - chrono::Duration::minutes(30));
+        assert!(!is_due(&subscription, now));
+        subscription.last_checked_at = Some(now - chrono::Duration::hours(2));
+        assert!(is_due(&subscription, now));
+
+        subscription.enabled = false;
+        assert!(!is_due(&subscription, now));
+    }
+}
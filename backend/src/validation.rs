@@ -1,6 +1,7 @@
 use regex::Regex;
 use url::Url;
 use once_cell::sync::Lazy;
+use rust_media_downloader_shared::{bandwidth, RateLimitWindow};
 
 /// Whitelist of allowed domains for downloads
 static ALLOWED_DOMAINS: &[&str] = &[
@@ -95,6 +96,23 @@ pub fn validate_url(url_str: &str) -> Result<String, ValidationError> {
     Ok(url_str.to_string())
 }
 
+/// Validates a bandwidth limit such as `500K`, `2M` or `unlimited`
+pub fn validate_rate_limit(rate: &str) -> Result<(), ValidationError> {
+    bandwidth::parse_rate(rate).map(|_| ()).map_err(|_| ValidationError {
+        message: format!("Invalid rate limit '{}' (expected e.g. 500K, 2M or unlimited)", rate.trim()),
+    })
+}
+
+/// Validates a time-of-day bandwidth window
+pub fn validate_rate_window(window: &RateLimitWindow) -> Result<(), ValidationError> {
+    if let Some(limit) = &window.limit {
+        validate_rate_limit(limit)?;
+    }
+    window.validate().map_err(|_| ValidationError {
+        message: format!("Invalid bandwidth window {}-{} (expected HH:MM times)", window.start, window.end),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -126,4 +144,17 @@ mod tests {
         let long_url = format!("https://youtube.com/{}", "a".repeat(2100));
         assert!(validate_url(&long_url).is_err());
     }
+
+    #[test]
+    fn test_rate_limit_errors_are_in_english() {
+        assert!(validate_rate_limit("2M").is_ok());
+        assert!(validate_rate_limit("unlimited").is_ok());
+        assert_eq!(
+            validate_rate_limit("fast").unwrap_err().message,
+            "Invalid rate limit 'fast' (expected e.g. 500K, 2M or unlimited)",
+        );
+
+        let window = RateLimitWindow { start: "25:00".to_string(), end: "06:00".to_string(), limit: None };
+        assert_eq!(validate_rate_window(&window).unwrap_err().message, "Invalid bandwidth window 25:00-06:00 (expected HH:MM times)");
+    }
 }
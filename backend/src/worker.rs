@@ -0,0 +1,560 @@
+use crate::models::{DownloadRequest, DownloadResponse, DownloadStatus, DownloadType};
+use crate::queue::JobOutcome;
+use crate::disk::{self, DiskLimits, DiskUsage, Preflight};
+use crate::retry::RetryPolicy;
+use crate::converter::tracks_from_chapters;
+use crate::state::{AppState, StopReason, remove_partial_files};
+use rust_media_downloader_shared::{MediaId, DownloadAborted, DownloadError, DownloadSpec, DownloadHooks, DownloadOutcome, FormatError, FormatSelector, ProgressCallback, ProgressEvent, Staging, VideoInfo};
+use rust_media_downloader_shared::{bandwidth, output_template, Config, CollisionPolicy};
+use rust_media_downloader_shared::output_template::{Collision, TemplateValues};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use chrono::Timelike;
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// Minimum delay between two progress writes for the same download
+const PROGRESS_WRITE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long a download deferred for disk space waits before it is checked again
+const DISK_RECHECK_DELAY: Duration = Duration::from_secs(300);
+
+/// How often running downloads are checked against the bandwidth schedule
+const BANDWIDTH_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Run a single queued download to completion, updating its row and firing webhooks.
+///
+/// Called by the queue dispatcher once a worker slot is available.
+pub async fn run_download(state: &AppState, download_id: &str, request: DownloadRequest) -> JobOutcome {
+    let download_id = download_id.to_string();
+    let config = state.config();
+    let retry_policy = RetryPolicy::from_config(&config);
+
+    // Update status to Downloading
+    match state.get_download(&download_id).await {
+        Some(mut dl) if dl.status == DownloadStatus::Pending => {
+            dl.set_status(DownloadStatus::Downloading, "Downloading...".to_string());
+            dl.progress = 0.0;
+            dl.max_retries = Some(retry_policy.max_retries);
+            state.update_download(&download_id, dl).await;
+        }
+        Some(dl) => {
+            tracing::warn!("Download {} is {:?}, skipping queued job", download_id, dl.status);
+            return JobOutcome::Finished;
+        }
+        None => {
+            // Row was deleted while the job was waiting in the queue
+            tracing::warn!("Download {} no longer exists, skipping queued job", download_id);
+            return JobOutcome::Finished;
+        }
+    }
+
+    let mut request = request;
+    if request.download_playlist == Some(true) {
+        if let Some(outcome) = expand_playlist(state, &download_id, &request, &retry_policy).await {
+            return outcome;
+        }
+        // The URL is a single video: download it like any other
+        request.download_playlist = Some(false);
+    }
+
+    // Where the file goes: the template resolved now, checked against the files already there
+    let planned = match plan_output(state, &config, &download_id, &request).await {
+        Ok(Some(Collision::Skip(existing))) => {
+            skip_existing(state, &download_id, &existing).await;
+            return JobOutcome::Finished;
+        }
+        Ok(Some(Collision::Download(relative))) => {
+            request.custom_filename = Some(relative.to_string_lossy().to_string());
+            Ok(())
+        }
+        Ok(None) => Ok(()),
+        Err(e) => Err(e),
+    };
+
+    // Pre-flight: the estimated size must fit in the library quota and leave the minimum free space
+    let limits = DiskLimits::from_config(&config);
+    let mut estimate = 0;
+    if planned.is_ok() && limits.is_enabled() {
+        estimate = estimate_download_size(state, &request).await;
+        let usage = DiskUsage {
+            library_size: state.library_size().await,
+            reserved: state.reserved_space(),
+            free: disk::get_disk_space(Path::new(&config.download_directory)).0,
+        };
+        match disk::preflight(&limits, &usage, estimate) {
+            Preflight::Proceed => {}
+            Preflight::Defer(reason) => {
+                tracing::info!("Deferring download {}: {}", download_id, reason);
+                if let Some(mut dl) = state.get_download(&download_id).await {
+                    dl.set_status(DownloadStatus::Pending, reason);
+                    state.update_download(&download_id, dl).await;
+                }
+                return JobOutcome::Retry { delay: DISK_RECHECK_DELAY };
+            }
+            Preflight::Reject(reason) => {
+                tracing::warn!("Rejecting download {}: {}", download_id, reason);
+                if let Some(mut dl) = state.get_download(&download_id).await {
+                    dl.set_status(DownloadStatus::Failed, format!("Download rejected: {}", reason));
+                    state.update_download(&download_id, dl).await;
+                }
+                return JobOutcome::Failed;
+            }
+        }
+    }
+
+    // Real progress from yt-dlp: the callback only publishes the latest event,
+    // a single writer task persists it at most once per PROGRESS_WRITE_INTERVAL
+    let (progress_tx, mut progress_rx) = watch::channel(None::<ProgressEvent>);
+    let progress_tx = Arc::new(progress_tx);
+
+    let state_progress = state.clone();
+    let download_id_progress = download_id.clone();
+    let progress_writer = tokio::spawn(async move {
+        let mut post_processing = false;
+        while progress_rx.changed().await.is_ok() {
+            let latest = progress_rx.borrow_and_update().clone();
+            match latest {
+                Some(ProgressEvent::PostProcessing { step }) => {
+                    post_processing = true;
+                    state_progress.set_download_phase(&download_id_progress, Some(&step)).await;
+                }
+                Some(event) => {
+                    // The next entry of a playlist run starts downloading again
+                    if let Some(progress) = event.download_progress() {
+                        if post_processing {
+                            post_processing = false;
+                            state_progress.set_download_phase(&download_id_progress, None).await;
+                        }
+                        state_progress.update_progress(&download_id_progress, &progress).await;
+                    }
+                }
+                None => {}
+            }
+            tokio::time::sleep(PROGRESS_WRITE_INTERVAL).await;
+        }
+    });
+
+    let own_limit = request.rate_limit.as_deref().and_then(|rate| bandwidth::parse_rate(rate).ok().flatten());
+    let split_chapters = request.split_chapters == Some(true);
+    // Read from the arguments yt-dlp runs with, not from the options asked for
+    let post_processing = format_selector(&request).and_then(|selector| selector.post_processing_steps())
+        .ok()
+        .filter(|steps| !steps.is_empty());
+
+    // Perform the actual download; yt-dlp is restarted on its partial files
+    // whenever this download's share of the bandwidth changes
+    let (result, stop_reason) = match planned {
+        Ok(()) => loop {
+            let (abort, rate_limit) = state.register_running(&download_id, own_limit);
+            let progress_tx = progress_tx.clone();
+            let progress_callback: ProgressCallback = Box::new(move |event| {
+                progress_tx.send_replace(Some(event));
+            });
+            let hooks = DownloadHooks {
+                progress: Some(progress_callback),
+                abort: Some(abort),
+                rate_limit,
+                // Kept across pauses and retries so yt-dlp finds its partial files again
+                staging: Some(Staging::for_job(&config, &download_id)),
+            };
+            state.reserve_space(&download_id, estimate);
+
+            let result = perform_download(state, request.clone(), hooks).await;
+            let stop_reason = state.unregister_running(&download_id);
+            let rebalanced = stop_reason == Some(StopReason::Rebalance)
+                && result.as_ref().is_err_and(|e| e.downcast_ref::<DownloadAborted>().is_some());
+            if !rebalanced {
+                break (result, stop_reason);
+            }
+        },
+        Err(e) => (Err(e), None),
+    };
+    drop(progress_tx);
+
+    // The callback (and its sender) is dropped with the download future: let the
+    // writer flush its last value so it cannot overwrite the final status below
+    let _ = progress_writer.await;
+
+    // Update download status based on result
+    if let Some(mut dl) = state.get_download(&download_id).await {
+        match result {
+            Ok(outcome) => {
+                let files: Vec<String> = outcome.files.iter()
+                    .map(|p| p.to_string_lossy().to_string())
+                    .collect();
+                if files.is_empty() {
+                    tracing::warn!("Download {} finished but yt-dlp reported no output file", download_id);
+                }
+                // Metadata printed by the same yt-dlp run, size from the file on disk
+                if let Some(info) = &outcome.info {
+                    dl.apply_video_info(info);
+                }
+                let extractor = outcome.entries.first().and_then(|e| e.extractor.clone());
+                let media_id = match (&outcome.video_id, extractor) {
+                    (Some(id), Some(extractor)) => Some(MediaId::new(extractor, id.clone())),
+                    _ => MediaId::from_url(&dl.url),
+                };
+                if let Some(media_id) = media_id {
+                    dl.set_media_id(media_id);
+                }
+                // Size of every output file, not just the first one
+                let sizes: Vec<u64> = outcome.files.iter()
+                    .filter_map(|file| std::fs::metadata(file).ok())
+                    .map(|metadata| metadata.len())
+                    .collect();
+                dl.file_size = (!sizes.is_empty()).then(|| sizes.iter().sum());
+                dl.set_status(DownloadStatus::Completed, "Download completed successfully".to_string());
+                dl.file_path = files.first().cloned();
+                dl.output_files = Some(files);
+                dl.post_processing = post_processing;
+                dl.progress = 100.0;
+                dl.speed = None;
+                dl.eta = None;
+                // Publishes download.completed to the event stream and webhooks
+                state.update_download(&download_id, dl).await;
+                if split_chapters {
+                    split_by_chapters(state, &download_id, outcome.info.as_ref()).await;
+                }
+            }
+            Err(e) if e.downcast_ref::<DownloadAborted>().is_some() => {
+                let partial_files = e.downcast::<DownloadAborted>()
+                    .map(|aborted| aborted.partial_files)
+                    .unwrap_or_default();
+                dl.speed = None;
+                dl.eta = None;
+
+                if stop_reason == Some(StopReason::Pause) {
+                    dl.set_status(DownloadStatus::Paused, "Download paused".to_string());
+                    state.update_download(&download_id, dl).await;
+                    return JobOutcome::Paused {
+                        partial_files: partial_files.iter().map(|p| p.to_string_lossy().to_string()).collect(),
+                    };
+                }
+
+                remove_partial_files(partial_files);
+                Staging::for_job(&config, &download_id).discard();
+                dl.set_status(DownloadStatus::Cancelled, "Download cancelled".to_string());
+                state.update_download(&download_id, dl).await;
+            }
+            Err(e) => {
+                dl.speed = None;
+                dl.eta = None;
+                let retries_done = dl.retry_count.unwrap_or(0);
+
+                if retry_policy.should_retry(&e, retries_done) {
+                    let attempt = retries_done + 1;
+                    let delay = retry_policy.delay_for(attempt);
+                    tracing::warn!("Download {} failed with a transient error, retry {}/{} in {:?}: {}",
+                        download_id, attempt, retry_policy.max_retries, delay, e);
+                    dl.retry_count = Some(attempt);
+                    dl.set_status(DownloadStatus::Pending, format!(
+                        "Retrying in {}s (attempt {}/{}): {}", delay.as_secs(), attempt, retry_policy.max_retries, e
+                    ));
+                    dl.set_error(DownloadError::of(&e));
+                    state.update_download(&download_id, dl).await;
+                    return JobOutcome::Retry { delay };
+                }
+
+                tracing::error!("Download failed for {}: {}", download_id, e);
+                Staging::for_job(&config, &download_id).discard();
+                dl.set_status(DownloadStatus::Failed, format!("Download failed: {}", e));
+                dl.set_error(DownloadError::of(&e));
+                // Publishes download.failed to the event stream and webhooks
+                state.update_download(&download_id, dl).await;
+                return JobOutcome::Failed;
+            }
+        }
+    } else if let Err(e) = result {
+        // The row was deleted while yt-dlp was running: nothing to record, only files to clean up
+        if let Ok(aborted) = e.downcast::<DownloadAborted>() {
+            remove_partial_files(aborted.partial_files);
+        }
+        Staging::for_job(&config, &download_id).discard();
+    }
+
+    JobOutcome::Finished
+}
+
+/// Cut a finished download into one child entry per chapter; the download stays completed
+/// whatever happens, only its message tells whether the split worked.
+async fn split_by_chapters(state: &AppState, download_id: &str, info: Option<&VideoInfo>) {
+    let chapters = info.and_then(|info| info.chapters.as_deref()).unwrap_or_default();
+    let message = if chapters.len() < 2 {
+        Some("Download completed successfully (no chapters to split)".to_string())
+    } else {
+        match state.split_download(download_id, &tracks_from_chapters(chapters)).await {
+            Ok(_) => None,
+            Err(e) => {
+                tracing::error!("Failed to split download {} by chapters: {}", download_id, e);
+                Some(format!("Download completed, but splitting by chapters failed: {}", e))
+            }
+        }
+    };
+
+    if let (Some(message), Some(mut dl)) = (message, state.get_download(download_id).await) {
+        dl.message = message;
+        state.update_download(download_id, dl).await;
+    }
+}
+
+/// Turn a playlist download into one queued child download per entry.
+///
+/// The parent row only tracks the entries from then on. Returns `None` when the
+/// URL is a single video so the caller downloads it directly.
+async fn expand_playlist(state: &AppState, download_id: &str, request: &DownloadRequest, retry_policy: &RetryPolicy) -> Option<JobOutcome> {
+    let info = match state.media_backend().list_playlist(&request.url, request.cookies_browser.as_deref()).await {
+        Ok(Some(info)) => info,
+        Ok(None) => return None,
+        Err(e) => {
+            let Some(mut dl) = state.get_download(download_id).await else {
+                return Some(JobOutcome::Finished);
+            };
+            let retries_done = dl.retry_count.unwrap_or(0);
+            if retry_policy.should_retry(&e, retries_done) {
+                let attempt = retries_done + 1;
+                let delay = retry_policy.delay_for(attempt);
+                tracing::warn!("Listing playlist {} failed with a transient error, retry {}/{} in {:?}: {}",
+                    download_id, attempt, retry_policy.max_retries, delay, e);
+                dl.retry_count = Some(attempt);
+                dl.set_status(DownloadStatus::Pending, format!(
+                    "Retrying in {}s (attempt {}/{}): {}", delay.as_secs(), attempt, retry_policy.max_retries, e
+                ));
+                dl.set_error(DownloadError::of(&e));
+                state.update_download(download_id, dl).await;
+                return Some(JobOutcome::Retry { delay });
+            }
+
+            tracing::error!("Failed to list playlist entries for {}: {}", download_id, e);
+            dl.set_status(DownloadStatus::Failed, format!("Download failed: could not list playlist entries: {}", e));
+            dl.set_error(DownloadError::of(&e));
+            state.update_download(download_id, dl).await;
+            return Some(JobOutcome::Failed);
+        }
+    };
+
+    let Some(mut parent) = state.get_download(download_id).await else {
+        return Some(JobOutcome::Finished);
+    };
+    let total = info.entries.len();
+    parent.is_playlist = true;
+    parent.title = parent.title.or(info.title);
+    parent.total_items = Some(total as i32);
+    parent.completed_items = Some(0);
+    parent.progress = 0.0;
+    parent.set_status(DownloadStatus::Processing, format!("0/{} entries downloaded", total));
+    state.update_download(download_id, parent).await;
+
+    // Every row exists before the first job is queued, so an entry finishing
+    // early cannot make the playlist look complete
+    let mut children = Vec::with_capacity(total);
+    for (index, entry) in info.entries.into_iter().enumerate() {
+        let position = index as u32 + 1;
+        let mut child = DownloadResponse::new(entry.url.clone(), request.download_type.clone());
+        child.parent_id = Some(download_id.to_string());
+        child.playlist_index = Some(position);
+        child.title = entry.title;
+        child.duration = entry.duration;
+        let child_request = DownloadRequest {
+            url: entry.url,
+            download_playlist: Some(false),
+            scheduled_at: None,
+            recurrence: None,
+            custom_filename: request.custom_filename.as_ref().map(|name| format!("{} - {:02}", name, position)),
+            ..request.clone()
+        };
+        state.add_download(child.clone()).await;
+        children.push((child, child_request));
+    }
+
+    for (mut child, child_request) in children {
+        if let Err(e) = state.enqueue_download(&child.id, &child_request).await {
+            tracing::error!("Failed to queue playlist entry {}: {}", child.id, e);
+            child.set_status(DownloadStatus::Failed, format!("Download failed: could not queue entry: {}", e));
+            state.update_download(&child.id.clone(), child).await;
+        }
+    }
+
+    // Settles an empty playlist right away
+    state.refresh_playlist(download_id).await;
+    Some(JobOutcome::Finished)
+}
+
+/// Resolve the download's output path from its template and apply the collision policy.
+///
+/// Returns `None` when neither the request nor the config sets a template (yt-dlp's
+/// default naming). The chosen path is stored on the row, so a resumed or retried
+/// download keeps its target instead of colliding with itself.
+async fn plan_output(state: &AppState, config: &Config, download_id: &str, request: &DownloadRequest) -> anyhow::Result<Option<Collision>> {
+    let Some(mut dl) = state.get_download(download_id).await else {
+        return Ok(None);
+    };
+    let root = Path::new(&config.download_directory);
+    if let Some(planned) = dl.output_path.as_deref().and_then(|path| Path::new(path).strip_prefix(root).ok()) {
+        return Ok(Some(Collision::Download(planned.to_path_buf())));
+    }
+
+    let template = request.output_template.clone().or_else(|| match request.download_type {
+        DownloadType::Video => config.output_templates.video.clone(),
+        DownloadType::Audio => config.output_templates.audio.clone(),
+        DownloadType::Instrumental => config.output_templates.instrumental.clone(),
+    });
+    let relative = match (&request.custom_filename, template) {
+        // An explicit name wins over any template
+        (Some(name), _) => match output_template::sanitize_component(name, config.max_filename_length) {
+            name if name.is_empty() => return Ok(None),
+            name => PathBuf::from(name),
+        },
+        (None, Some(template)) => {
+            let values = template_values(state, &mut dl, request, &template).await?;
+            output_template::render_template(&template, &values, config.max_filename_length)
+                .map_err(|e| anyhow::anyhow!("Invalid output template: {}", e))?
+        }
+        (None, None) => return Ok(None),
+    };
+
+    let collision = output_template::resolve_collision(root, &relative, config.collision_policy);
+    if let Collision::Download(relative) = &collision {
+        if config.collision_policy == CollisionPolicy::Overwrite {
+            for existing in output_template::existing_files(&root.join(relative)) {
+                tracing::info!("Overwriting {} for download {}", existing.display(), download_id);
+                if let Err(e) = std::fs::remove_file(&existing) {
+                    tracing::warn!("Failed to remove {}: {}", existing.display(), e);
+                }
+            }
+        }
+        dl.output_path = Some(root.join(relative).to_string_lossy().to_string());
+        state.update_download(download_id, dl).await;
+    }
+    Ok(Some(collision))
+}
+
+/// Values for the template fields; media metadata is only fetched when the template uses it
+async fn template_values(state: &AppState, dl: &mut DownloadResponse, request: &DownloadRequest, template: &str) -> anyhow::Result<TemplateValues> {
+    let needs_metadata = ["{title}", "{uploader}", "{id}", "{extractor}"].iter().any(|field| template.contains(field));
+    let mut info = state.get_video_cache().get(&request.url).await;
+    if info.is_none() && needs_metadata {
+        let fetched = state.media_backend().fetch_info(&request.url, request.cookies_browser.as_deref()).await?;
+        state.get_video_cache().set(request.url.clone(), fetched.clone()).await;
+        info = Some(fetched);
+    }
+    if let Some(info) = &info {
+        dl.apply_video_info(info);
+    }
+
+    let playlist = match &dl.parent_id {
+        Some(parent_id) => state.get_download(parent_id).await.and_then(|parent| parent.title),
+        None => None,
+    };
+    let media_id = dl.media_id().or_else(|| MediaId::from_url(&request.url));
+    Ok(TemplateValues {
+        title: dl.title.clone(),
+        uploader: dl.author.clone(),
+        id: media_id.as_ref().map(|m| m.id.clone()),
+        extractor: media_id.map(|m| m.extractor),
+        playlist,
+        index: dl.playlist_index,
+        media_type: Some(match request.download_type {
+            DownloadType::Video => "video",
+            DownloadType::Audio => "audio",
+            DownloadType::Instrumental => "instrumental",
+        }.to_string()),
+        date: Some(chrono::Local::now().format("%Y-%m-%d").to_string()),
+    })
+}
+
+/// Expected size of the download, 0 when yt-dlp reports no format size
+async fn estimate_download_size(state: &AppState, request: &DownloadRequest) -> u64 {
+    let info = match state.get_video_cache().get(&request.url).await {
+        Some(info) => info,
+        None => match state.media_backend().fetch_info(&request.url, request.cookies_browser.as_deref()).await {
+            Ok(info) => {
+                state.get_video_cache().set(request.url.clone(), info.clone()).await;
+                info
+            }
+            Err(e) => {
+                tracing::warn!("Could not estimate the size of {}: {}", request.url, e);
+                return 0;
+            }
+        },
+    };
+    disk::estimate_size(&info, request).unwrap_or(0)
+}
+
+/// Skip policy: the target already exists, point the download at it
+async fn skip_existing(state: &AppState, download_id: &str, existing: &Path) {
+    let Some(mut dl) = state.get_download(download_id).await else {
+        return;
+    };
+    let file = existing.to_string_lossy().to_string();
+    tracing::info!("Skipping download {}: {} already exists", download_id, file);
+    dl.file_size = std::fs::metadata(existing).ok().map(|metadata| metadata.len());
+    dl.file_path = Some(file.clone());
+    dl.output_files = Some(vec![file.clone()]);
+    dl.progress = 100.0;
+    dl.set_status(DownloadStatus::Completed, format!("Skipped: {} already exists", file));
+    state.update_download(download_id, dl).await;
+}
+
+/// Global bandwidth limit in force now, following the time-of-day schedule
+pub fn current_rate_limit(config: &Config) -> Option<u64> {
+    let now = chrono::Local::now();
+    let minute_of_day = now.hour() * 60 + now.minute();
+    let default = config.rate_limit.as_deref().and_then(|rate| bandwidth::parse_rate(rate).ok().flatten());
+    bandwidth::limit_at(default, &config.rate_limit_schedule, minute_of_day)
+}
+
+/// Background task restarting running downloads when a scheduled bandwidth window
+/// starts or ends; worker changes and limit updates rebalance on their own
+pub async fn run_bandwidth_monitor(state: AppState) {
+    loop {
+        tokio::time::sleep(BANDWIDTH_CHECK_INTERVAL).await;
+        state.rebalance_bandwidth();
+    }
+}
+
+/// yt-dlp format selection for a request; handlers call it up front to reject
+/// impossible option combinations before anything is queued
+pub fn format_selector(request: &DownloadRequest) -> Result<FormatSelector, FormatError> {
+    let mut selector = match request.download_type {
+        DownloadType::Video => FormatSelector::video(request.format.as_deref().unwrap_or("mp4")),
+        DownloadType::Audio | DownloadType::Instrumental => {
+            FormatSelector::audio(request.format.as_deref().unwrap_or("mp3"))
+        }
+    };
+
+    if let Some(resolution) = &request.resolution {
+        selector = selector.resolution(resolution);
+    }
+    if let Some(audio_quality) = &request.audio_quality {
+        selector = selector.audio_quality(audio_quality);
+    }
+    selector = selector.subtitles(
+        request.download_subtitles.unwrap_or(false),
+        request.subtitle_language.as_deref(),
+    );
+    if let Some(post_processing) = &request.post_processing {
+        // Spleeter writes a new wav in place of yt-dlp's file: nothing embedded there would survive
+        if request.download_type == DownloadType::Instrumental && !post_processing.is_empty() {
+            return Err(FormatError::Conflict("post-processing is not available for instrumental downloads"));
+        }
+        selector = selector.post_processing(post_processing.clone());
+    }
+    if request.split_chapters == Some(true) && request.download_type == DownloadType::Instrumental {
+        return Err(FormatError::Conflict("split_chapters is only available for audio and video downloads"));
+    }
+
+    selector.validate()?;
+    Ok(selector)
+}
+
+async fn perform_download(state: &AppState, request: DownloadRequest, hooks: DownloadHooks) -> anyhow::Result<DownloadOutcome> {
+    let spec = DownloadSpec {
+        extract_instrumental: request.download_type == DownloadType::Instrumental,
+        custom_filename: request.custom_filename.clone(),
+        cookies_browser: request.cookies_browser.clone(),
+        download_playlist: request.download_playlist.unwrap_or(false),
+        ..DownloadSpec::new(&request.url, format_selector(&request)?)
+    };
+    state.media_backend().download(&spec, hooks).await
+}
@@ -6,9 +6,9 @@ use log::{info, warn, error};
 
 // Use shared library
 use rust_media_downloader_shared::{
-    download_video, download_audio,
+    download_video, download_audio, DownloadHooks, FormatSelector,
     check_command, ensure_dependencies,
-    config, cookies,
+    config, cookies, bandwidth, preset, Preset, PresetKind,
 };
 
 // Keep local modules for CLI-specific functionality
@@ -34,6 +34,15 @@ struct Cli {
     /// Extract instrumental (requires Spleeter)
     #[arg(short, long)]
     instrumental: bool,
+
+    /// Download with a built-in preset (see --list-presets); overrides --format and --audio.
+    /// Presets created through the web API live in the backend database and are not available here
+    #[arg(short, long)]
+    preset: Option<String>,
+
+    /// List the built-in presets and exit
+    #[arg(long)]
+    list_presets: bool,
 }
 
 #[tokio::main]
@@ -42,6 +51,11 @@ async fn main() -> Result<()> {
     
     let cli = Cli::parse();
 
+    if cli.list_presets {
+        user_input::afficher_presets();
+        return Ok(());
+    }
+
     // 🛠️ Vérification de la présence de yt-dlp et ffmpeg
     ensure_dependencies();
 
@@ -59,10 +73,17 @@ async fn main() -> Result<()> {
 
     // CLI Mode
     if let Some(url) = cli.url {
+        if let Some(preset_id) = &cli.preset {
+            let Some(preset) = preset::find_builtin_preset(preset_id) else {
+                user_input::afficher_presets();
+                anyhow::bail!("Préréglage inconnu : {}", preset_id);
+            };
+            return telecharger_avec_preset(&url, &preset, None, None, spleeter_available).await;
+        }
         if cli.audio {
-             download_audio(&url, &cli.format, cli.instrumental, None, None, false).await?;
+             download_audio(&url, &FormatSelector::audio(&cli.format), cli.instrumental, None, None, false, DownloadHooks::default()).await?;
         } else {
-             download_video(&url, &cli.format, false, None, None, false).await?;
+             download_video(&url, &FormatSelector::video(&cli.format), false, None, None, false, DownloadHooks::default()).await?;
         }
         return Ok(());
     }
@@ -102,19 +123,20 @@ async fn main() -> Result<()> {
 
                 println!("{}", "\n═══════════════════════════════════════════════════════════".bright_blue());
                 info!("{}", "\nTéléchargement de la vidéo en cours...\n".cyan().bold());
-                if let Err(e) = download_video(&url, &format, keep_files, custom_filename, cookies, false).await {
+                if let Err(e) = download_video(&url, &FormatSelector::video(&format), keep_files, custom_filename, cookies, false, DownloadHooks::default()).await {
                     error!("Erreur lors du téléchargement: {}", e);
                 }
             }
             "2" => {
                 let url = demander_url();
-                let (format, keep_files) = user_input::choisir_video_options_avances();
+                let (resolution, keep_files) = user_input::choisir_video_options_avances();
+                let selector = FormatSelector::video(&config::load_config().default_video_format).resolution(&resolution);
                 let custom_filename = user_input::demander_nom_fichier_personnalise();
                 let cookies = user_input::demander_cookies();
 
                 println!("{}", "\n═══════════════════════════════════════════════════════════".bright_blue());
                 info!("{}", "\n📥 Téléchargement de la vidéo en cours...\n".cyan().bold());
-                if let Err(e) = download_video(&url, &format, keep_files, custom_filename, cookies, false).await {
+                if let Err(e) = download_video(&url, &selector, keep_files, custom_filename, cookies, false, DownloadHooks::default()).await {
                     error!("Erreur lors du téléchargement: {}", e);
                 }
             }
@@ -127,7 +149,7 @@ async fn main() -> Result<()> {
 
                 println!("{}", "\n═══════════════════════════════════════════════════════════".bright_blue());
                 info!("{}", "\n🎵 Téléchargement de l'audio en cours...\n".cyan().bold());
-                if let Err(e) = download_audio(&url, &audio_format, _extract_instrumental, custom_filename, cookies, false).await {
+                if let Err(e) = download_audio(&url, &FormatSelector::audio(&audio_format), _extract_instrumental, custom_filename, cookies, false, DownloadHooks::default()).await {
                     error!("Erreur lors du téléchargement: {}", e);
                 }
             }
@@ -138,8 +160,22 @@ async fn main() -> Result<()> {
             "5" => {
                 settings::show_settings_menu();
             }
+            "6" => {
+                let Some(preset) = user_input::choisir_preset(spleeter_available) else {
+                    continue;
+                };
+                let url = demander_url();
+                let custom_filename = user_input::demander_nom_fichier_personnalise();
+                let cookies = user_input::demander_cookies();
+
+                println!("{}", "\n═══════════════════════════════════════════════════════════".bright_blue());
+                info!("{}", format!("\n🎛️  Téléchargement avec le préréglage « {} »...\n", preset.name).cyan().bold());
+                if let Err(e) = telecharger_avec_preset(&url, &preset, custom_filename, cookies, spleeter_available).await {
+                    error!("Erreur lors du téléchargement: {}", e);
+                }
+            }
             _ => {
-                warn!("{}", "❌ Choix invalide. Veuillez entrer 1, 2, 3, 4, 5 ou q.".red());
+                warn!("{}", "❌ Choix invalide. Veuillez entrer 1, 2, 3, 4, 5, 6 ou q.".red());
                 continue;
             }
         }
@@ -159,6 +195,38 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+/// Télécharge l'URL avec les options du préréglage (format, qualité, sous-titres, débit).
+///
+/// Seuls les préréglages intégrés sont proposés en ligne de commande : ceux créés via
+/// l'API sont enregistrés dans la base du backend.
+async fn telecharger_avec_preset(url: &str, preset: &Preset, custom_filename: Option<String>, cookies: Option<String>, spleeter_available: bool) -> Result<()> {
+    let selector = preset.format_selector().map_err(|e| anyhow::anyhow!("Préréglage invalide : {}", e))?;
+    let download_playlist = preset.options.download_playlist.unwrap_or(false);
+    let rate_limit = preset.options.rate_limit.as_deref()
+        .map(bandwidth::parse_rate)
+        .transpose()
+        .map_err(|e| anyhow::anyhow!("Préréglage invalide : {}", e))?
+        .flatten();
+    let hooks = DownloadHooks {
+        rate_limit,
+        ..DownloadHooks::default()
+    };
+
+    match preset.kind {
+        PresetKind::Video => {
+            download_video(url, &selector, false, custom_filename, cookies, download_playlist, hooks).await?;
+        }
+        PresetKind::Audio | PresetKind::Instrumental => {
+            let instrumental = preset.kind == PresetKind::Instrumental;
+            if instrumental && !spleeter_available {
+                anyhow::bail!("Le préréglage « {} » nécessite Spleeter", preset.name);
+            }
+            download_audio(url, &selector, instrumental, custom_filename, cookies, download_playlist, hooks).await?;
+        }
+    }
+    Ok(())
+}
+
 fn afficher_interface(spleeter_available: bool) {
     // Clear screen for better visual experience
     print!("\x1B[2J\x1B[1;1H");
@@ -216,6 +284,11 @@ fn afficher_interface(spleeter_available: bool) {
         "│".cyan(),
         "  [4] 🍪  Download with Cookies                           │".bright_white()
     );
+    println!(
+        "{} {}",
+        "│".cyan(),
+        "  [6] 🎛️   Download with a Preset                          │".bright_white()
+    );
     println!("{}", "└───────────────────────────────────────────────────────────┘".cyan());
     println!("");
     
@@ -1,4 +1,5 @@
 use crate::config;
+use rust_media_downloader_shared::{builtin_presets, Preset, PresetKind};
 use dialoguer::{Confirm, Input, Select, theme::ColorfulTheme};
 use std::io::{self, Write};
 use log::{info, warn};
@@ -64,7 +65,7 @@ pub fn choisir_video_options_avances() -> (String, bool) {
         .interact()
         .unwrap();
 
-    let format = resolutions[selection].to_string();
+    let resolution = resolutions[selection].to_string();
 
     let keep_files = Confirm::with_theme(&ColorfulTheme::default())
         .with_prompt("Keep original files after merge?")
@@ -72,7 +73,7 @@ pub fn choisir_video_options_avances() -> (String, bool) {
         .interact()
         .unwrap_or(false);
 
-    (format, keep_files)
+    (resolution, keep_files)
 }
 
 /// Fonction pour demander à l'utilisateur le format audio.
@@ -93,6 +94,42 @@ pub fn choisir_audio_format() -> String {
     formats[selection].clone()
 }
 
+/// Affiche les préréglages disponibles (identifiant, type et description).
+pub fn afficher_presets() {
+    println!("{}", "\n🎛️  Préréglages intégrés :".bright_yellow().bold());
+    for preset in builtin_presets() {
+        println!(
+            "  {:<26} {:<13} {}",
+            preset.id.bright_cyan(),
+            format!("[{:?}]", preset.kind).dimmed(),
+            preset.description.as_deref().unwrap_or(&preset.name)
+        );
+    }
+    println!();
+}
+
+/// Fonction pour demander à l'utilisateur de choisir un préréglage.
+/// Les préréglages instrumentaux ne sont proposés que si Spleeter est disponible.
+pub fn choisir_preset(spleeter_available: bool) -> Option<Preset> {
+    let presets: Vec<Preset> = builtin_presets()
+        .into_iter()
+        .filter(|preset| spleeter_available || preset.kind != PresetKind::Instrumental)
+        .collect();
+    let items: Vec<String> = presets
+        .iter()
+        .map(|preset| format!("{} — {}", preset.name, preset.description.as_deref().unwrap_or("")))
+        .collect();
+
+    let selection = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt("Choisissez un préréglage")
+        .items(&items)
+        .default(0)
+        .interact_opt()
+        .unwrap_or(None)?;
+
+    Some(presets[selection].clone())
+}
+
 /// Fonction pour demander à l'utilisateur s'il souhaite extraire uniquement la piste instrumentale.
 /// Nécessite que Spleeter soit installé et accessible.
 pub fn demander_extraction_instrumental(spleeter_available: bool) -> bool {
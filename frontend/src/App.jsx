@@ -6,7 +6,7 @@ import { CloudDownload, Sun, Moon, AlertCircle, Github } from 'lucide-react';
 import './App.css';
 
 function App() {
-  const { downloads, createDownload, deleteDownload, error } = useDownloads();
+  const { downloads, createDownload, deleteDownload, deleteDownloads, error } = useDownloads();
   const [theme, setTheme] = useState('dark');
 
   const toggleTheme = () => {
@@ -64,7 +64,7 @@ function App() {
           )}
 
           <DownloadForm onSubmit={handleDownload} />
-          <DownloadList downloads={downloads} onDelete={handleDelete} />
+          <DownloadList downloads={downloads} onDelete={handleDelete} onBulkDelete={deleteDownloads} />
         </div>
       </main>
 
@@ -30,9 +30,17 @@ export const downloadAPI = {
         return response.data;
     },
 
-    // Delete a download
-    deleteDownload: async (id) => {
-        await apiClient.delete(`/api/downloads/${id}`);
+    // Delete a download, and its files on disk with { deleteFiles: true }
+    deleteDownload: async (id, { deleteFiles = false } = {}) => {
+        await apiClient.delete(`/api/downloads/${id}`, {
+            params: deleteFiles ? { delete_files: true } : undefined,
+        });
+    },
+
+    // Server-Sent Events stream of download changes (optionally for one download)
+    eventsUrl: (id) => {
+        const query = id ? `?download_id=${encodeURIComponent(id)}` : '';
+        return `${API_BASE_URL}/api/downloads/events${query}`;
     },
 };
 
@@ -1,8 +1,11 @@
+import { useState } from 'react';
 import { motion } from 'framer-motion';
 import { Trash2, Tag, Star, X, Download } from 'lucide-react';
 import './BulkActionBar.css';
 
 const BulkActionBar = ({ selectedCount, onDelete, onTag, onFavorite, onClearSelection }) => {
+  const [deleteFiles, setDeleteFiles] = useState(false);
+
   if (selectedCount === 0) return null;
 
   return (
@@ -45,10 +48,21 @@ const BulkActionBar = ({ selectedCount, onDelete, onTag, onFavorite, onClearSele
             </button>
           )}
           
+          {onDelete && (
+            <label className="bulk-action-option" title="Supprimer aussi les fichiers du disque">
+              <input
+                type="checkbox"
+                checked={deleteFiles}
+                onChange={(e) => setDeleteFiles(e.target.checked)}
+              />
+              <span>Fichiers inclus</span>
+            </label>
+          )}
+
           {onDelete && (
             <button
               className="bulk-action-btn bulk-action-btn-danger"
-              onClick={onDelete}
+              onClick={() => onDelete({ deleteFiles })}
               title="Supprimer les éléments sélectionnés"
               aria-label="Supprimer"
             >
@@ -1,7 +1,23 @@
 import { motion } from 'framer-motion';
 import './DownloadCard.css';
 
-const DownloadCard = ({ download, onDelete }) => {
+const POST_PROCESSING_LABELS = {
+  metadata: 'Métadonnées',
+  thumbnail: 'Miniature',
+  chapters: 'Chapitres',
+  subtitles: 'Sous-titres',
+  sponsorblock_remove: 'SponsorBlock retiré',
+  sponsorblock_mark: 'SponsorBlock marqué',
+};
+
+// "sponsorblock_remove:sponsor,intro" -> "SponsorBlock retiré (sponsor, intro)"
+const formatPostProcessingStep = (step) => {
+  const [name, categories] = step.split(':');
+  const label = POST_PROCESSING_LABELS[name] || name;
+  return categories ? `${label} (${categories.split(',').join(', ')})` : label;
+};
+
+const DownloadCard = ({ download, onDelete, selected, onSelect }) => {
   const getStatusIcon = (status) => {
     switch (status) {
       case 'pending':
@@ -90,6 +106,15 @@ const DownloadCard = ({ download, onDelete }) => {
     >
       <div className="card-header">
         <div className="card-type">
+          {onSelect && (
+            <input
+              type="checkbox"
+              className="card-select"
+              checked={selected}
+              onChange={() => onSelect(download.id)}
+              aria-label="Sélectionner"
+            />
+          )}
           <span className="type-icon">{getTypeIcon(download.download_type)}</span>
           <span className="type-label">{download.download_type}</span>
         </div>
@@ -118,10 +143,18 @@ const DownloadCard = ({ download, onDelete }) => {
           <span>{truncateUrl(download.url)}</span>
         </div>
 
-        <div className="download-message">
-          {download.message}
+        <div className="download-message" title={download.error_message ? download.message : undefined}>
+          {download.error_message || download.message}
         </div>
 
+        {download.post_processing?.length > 0 && (
+          <div className="download-post-processing">
+            {download.post_processing.map((step) => (
+              <span key={step} className="post-processing-step">{formatPostProcessingStep(step)}</span>
+            ))}
+          </div>
+        )}
+
         {(download.status === 'downloading' || download.status === 'processing') && (
           <div className="progress-container">
             <div className="progress">
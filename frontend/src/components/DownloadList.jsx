@@ -1,10 +1,35 @@
 import { useState } from 'react';
 import { motion, AnimatePresence } from 'framer-motion';
 import DownloadCard from './DownloadCard';
+import BulkActionBar from './BulkActionBar';
 import './DownloadList.css';
 
-const DownloadList = ({ downloads, onDelete }) => {
+const DownloadList = ({ downloads, onDelete, onBulkDelete }) => {
   const [filter, setFilter] = useState('all');
+  const [selectedIds, setSelectedIds] = useState(new Set());
+
+  const toggleSelected = (id) => {
+    setSelectedIds(prev => {
+      const next = new Set(prev);
+      if (next.has(id)) {
+        next.delete(id);
+      } else {
+        next.add(id);
+      }
+      return next;
+    });
+  };
+
+  const handleBulkDelete = async (options) => {
+    const ids = downloads.filter(d => selectedIds.has(d.id)).map(d => d.id);
+    const message = options.deleteFiles
+      ? `Supprimer ${ids.length} téléchargement(s) et leurs fichiers du disque ?`
+      : `Supprimer ${ids.length} téléchargement(s) de l'historique ?`;
+    if (!window.confirm(message)) return;
+
+    const deleted = await onBulkDelete(ids, options);
+    setSelectedIds(prev => new Set([...prev].filter(id => !deleted.includes(id))));
+  };
 
   const filteredDownloads = downloads.filter(download => {
     if (filter === 'all') return true;
@@ -84,11 +109,23 @@ const DownloadList = ({ downloads, onDelete }) => {
                 key={download.id}
                 download={download}
                 onDelete={onDelete}
+                selected={selectedIds.has(download.id)}
+                onSelect={onBulkDelete ? toggleSelected : undefined}
               />
             ))
           )}
         </AnimatePresence>
       </div>
+
+      <AnimatePresence>
+        {onBulkDelete && (
+          <BulkActionBar
+            selectedCount={selectedIds.size}
+            onDelete={handleBulkDelete}
+            onClearSelection={() => setSelectedIds(new Set())}
+          />
+        )}
+      </AnimatePresence>
     </div>
   );
 };
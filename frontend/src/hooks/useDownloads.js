@@ -5,6 +5,7 @@ export const useDownloads = () => {
     const [downloads, setDownloads] = useState([]);
     const [loading, setLoading] = useState(false);
     const [error, setError] = useState(null);
+    const [liveUpdates, setLiveUpdates] = useState(false);
 
     const fetchDownloads = useCallback(async () => {
         try {
@@ -23,7 +24,8 @@ export const useDownloads = () => {
         try {
             setLoading(true);
             const newDownload = await downloadAPI.createDownload(downloadData);
-            setDownloads(prev => [newDownload, ...prev]);
+            // The event stream may already have delivered it
+            setDownloads(prev => prev.some(d => d.id === newDownload.id) ? prev : [newDownload, ...prev]);
             setError(null);
 
             // Immediately refresh to get the updated status (Downloading instead of Pending)
@@ -45,17 +47,33 @@ export const useDownloads = () => {
         }
     };
 
-    const deleteDownload = async (id) => {
+    const deleteDownload = async (id, options) => {
         try {
-            await downloadAPI.deleteDownload(id);
+            await downloadAPI.deleteDownload(id, options);
             setDownloads(prev => prev.filter(d => d.id !== id));
             setError(null);
         } catch (err) {
-            setError(err.message);
+            setError(err.response?.data?.message || err.message);
             throw err;
         }
     };
 
+    // Bulk delete: each download is deleted on its own, failures keep their entry
+    const deleteDownloads = async (ids, options) => {
+        const results = await Promise.allSettled(ids.map(id => downloadAPI.deleteDownload(id, options)));
+        const deleted = ids.filter((_, index) => results[index].status === 'fulfilled');
+        setDownloads(prev => prev.filter(d => !deleted.includes(d.id)));
+
+        const failed = results.filter(result => result.status === 'rejected');
+        if (failed.length > 0) {
+            const reason = failed[0].reason;
+            setError(`${failed.length} suppression(s) échouée(s) : ${reason.response?.data?.message || reason.message}`);
+        } else {
+            setError(null);
+        }
+        return deleted;
+    };
+
     const refreshDownload = async (id) => {
         try {
             const updated = await downloadAPI.getDownload(id);
@@ -70,7 +88,41 @@ export const useDownloads = () => {
     }, [fetchDownloads]);
 
     useEffect(() => {
-        // Poll for updates every 2 seconds
+        // Live updates pushed by the backend; polling is only a fallback
+        if (typeof EventSource === 'undefined') {
+            return undefined;
+        }
+        const source = new EventSource(downloadAPI.eventsUrl());
+
+        const upsert = (event) => {
+            const { download_id, data } = JSON.parse(event.data);
+            setDownloads(prev => prev.some(d => d.id === download_id)
+                ? prev.map(d => d.id === download_id ? { ...d, ...data } : d)
+                : [data, ...prev]);
+        };
+        const merge = (event) => {
+            const { download_id, data } = JSON.parse(event.data);
+            setDownloads(prev => prev.map(d => d.id === download_id ? { ...d, ...data } : d));
+        };
+
+        ['created', 'status', 'completed', 'failed', 'updated'].forEach(type => source.addEventListener(type, upsert));
+        source.addEventListener('progress', merge);
+        source.addEventListener('deleted', (event) => {
+            const { download_id } = JSON.parse(event.data);
+            setDownloads(prev => prev.filter(d => d.id !== download_id));
+        });
+        source.addEventListener('resync', () => fetchDownloads());
+        source.onopen = () => setLiveUpdates(true);
+        source.onerror = () => setLiveUpdates(false);
+
+        return () => source.close();
+    }, [fetchDownloads]);
+
+    useEffect(() => {
+        if (liveUpdates) {
+            return undefined;
+        }
+        // Poll for updates every 2 seconds while the event stream is unavailable
         const interval = setInterval(() => {
             downloads.forEach(download => {
                 // Poll for pending, downloading, and processing statuses
@@ -81,7 +133,7 @@ export const useDownloads = () => {
         }, 2000);
 
         return () => clearInterval(interval);
-    }, [downloads]);
+    }, [downloads, liveUpdates]);
 
     return {
         downloads,
@@ -89,6 +141,7 @@ export const useDownloads = () => {
         error,
         createDownload,
         deleteDownload,
+        deleteDownloads,
         refreshDownloads: fetchDownloads,
     };
 };
@@ -0,0 +1,137 @@
+use serde::{Deserialize, Serialize};
+
+/// Plus petite part accordée à un téléchargement : en dessous, yt-dlp expire.
+const MIN_RATE: u64 = 10 * 1024;
+
+/// Plage horaire (heure locale) pendant laquelle une autre limite s'applique.
+///
+/// `start`/`end` au format `HH:MM` ; une plage peut passer minuit (`22:00` → `06:00`).
+/// `limit` absent = illimité pendant la plage.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RateLimitWindow {
+    pub start: String,
+    pub end: String,
+    pub limit: Option<String>,
+}
+
+impl RateLimitWindow {
+    /// Vérifie les heures et la limite de la plage.
+    pub fn validate(&self) -> Result<(), String> {
+        parse_time_of_day(&self.start)?;
+        parse_time_of_day(&self.end)?;
+        if let Some(limit) = &self.limit {
+            parse_rate(limit)?;
+        }
+        Ok(())
+    }
+
+    fn contains(&self, minute_of_day: u32) -> bool {
+        let (Ok(start), Ok(end)) = (parse_time_of_day(&self.start), parse_time_of_day(&self.end)) else {
+            return false;
+        };
+        if start <= end {
+            (start..end).contains(&minute_of_day)
+        } else {
+            minute_of_day >= start || minute_of_day < end
+        }
+    }
+}
+
+/// Convertit un débit (`500K`, `2M`, `1.5MiB/s`, `250000`) en octets par seconde.
+///
+/// `None` signifie illimité (`0`, `unlimited` ou chaîne vide). Les unités sont en
+/// puissances de 1024, comme `--limit-rate` de yt-dlp.
+pub fn parse_rate(rate: &str) -> Result<Option<u64>, String> {
+    let normalized = rate.trim().to_lowercase();
+    if normalized.is_empty() || normalized == "unlimited" || normalized == "0" {
+        return Ok(None);
+    }
+
+    let value = normalized.trim_end_matches("/s").trim_end_matches("ib").trim_end_matches('b');
+    let split = value.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let multiplier: u64 = match unit.trim() {
+        "" => 1,
+        "k" => 1024,
+        "m" => 1024 * 1024,
+        "g" => 1024 * 1024 * 1024,
+        _ => return Err(format!("Débit invalide : {}", rate)),
+    };
+    let number: f64 = number.parse().map_err(|_| format!("Débit invalide : {}", rate))?;
+    if !number.is_finite() || number < 0.0 {
+        return Err(format!("Débit invalide : {}", rate));
+    }
+
+    let bytes = (number * multiplier as f64) as u64;
+    Ok((bytes > 0).then_some(bytes))
+}
+
+/// Limite globale en vigueur à `minute_of_day` (0..1440, heure locale) :
+/// la première plage qui couvre ce moment, sinon la limite par défaut.
+pub fn limit_at(default: Option<u64>, schedule: &[RateLimitWindow], minute_of_day: u32) -> Option<u64> {
+    match schedule.iter().find(|window| window.contains(minute_of_day)) {
+        Some(window) => window.limit.as_deref().and_then(|limit| parse_rate(limit).ok().flatten()),
+        None => default,
+    }
+}
+
+/// Part d'un téléchargement : le budget global divisé entre les téléchargements actifs,
+/// plafonnée par la limite propre au téléchargement.
+pub fn share_for(global: Option<u64>, active_downloads: usize, own_limit: Option<u64>) -> Option<u64> {
+    let share = global.map(|budget| (budget / active_downloads.max(1) as u64).max(MIN_RATE));
+    match (share, own_limit) {
+        (Some(share), Some(own)) => Some(share.min(own)),
+        (share, own) => share.or(own),
+    }
+}
+
+fn parse_time_of_day(time: &str) -> Result<u32, String> {
+    let invalid = || format!("Heure invalide (HH:MM attendu) : {}", time);
+    let (hours, minutes) = time.trim().split_once(':').ok_or_else(invalid)?;
+    let hours: u32 = hours.parse().map_err(|_| invalid())?;
+    let minutes: u32 = minutes.parse().map_err(|_| invalid())?;
+    if hours > 24 || minutes > 59 || (hours == 24 && minutes > 0) {
+        return Err(invalid());
+    }
+    Ok(hours * 60 + minutes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(start: &str, end: &str, limit: Option<&str>) -> RateLimitWindow {
+        RateLimitWindow { start: start.to_string(), end: end.to_string(), limit: limit.map(str::to_string) }
+    }
+
+    #[test]
+    fn test_parse_rate() {
+        assert_eq!(parse_rate("500K"), Ok(Some(500 * 1024)));
+        assert_eq!(parse_rate("2M"), Ok(Some(2 * 1024 * 1024)));
+        assert_eq!(parse_rate("1.5MiB/s"), Ok(Some(1536 * 1024)));
+        assert_eq!(parse_rate("2mb/s"), Ok(Some(2 * 1024 * 1024)));
+        assert_eq!(parse_rate("250000"), Ok(Some(250_000)));
+        assert_eq!(parse_rate("unlimited"), Ok(None));
+        assert_eq!(parse_rate(""), Ok(None));
+        assert!(parse_rate("fast").is_err());
+        assert!(parse_rate("2T").is_err());
+    }
+
+    #[test]
+    fn test_schedule_and_share() {
+        let schedule = vec![window("09:00", "18:00", Some("2M")), window("22:00", "06:00", None)];
+        let default = Some(1024 * 1024);
+
+        assert_eq!(limit_at(default, &schedule, 10 * 60), Some(2 * 1024 * 1024));
+        assert_eq!(limit_at(default, &schedule, 23 * 60), None);
+        assert_eq!(limit_at(default, &schedule, 3 * 60), None);
+        assert_eq!(limit_at(default, &schedule, 19 * 60), default);
+
+        assert_eq!(share_for(Some(3000 * 1024), 3, None), Some(1000 * 1024));
+        assert_eq!(share_for(Some(3000 * 1024), 3, Some(100 * 1024)), Some(100 * 1024));
+        assert_eq!(share_for(None, 3, Some(100 * 1024)), Some(100 * 1024));
+        assert_eq!(share_for(Some(20 * 1024), 4, None), Some(MIN_RATE));
+        assert_eq!(share_for(None, 1, None), None);
+        assert!(window("25:00", "06:00", None).validate().is_err());
+    }
+}
@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
+use crate::bandwidth::RateLimitWindow;
+use crate::output_template::{CollisionPolicy, OutputTemplates};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Config {
@@ -9,6 +11,86 @@ pub struct Config {
     pub default_video_format: String,
     pub download_directory: String,
     pub keep_temporary_files: bool,
+    #[serde(default = "default_max_concurrent_downloads")]
+    pub max_concurrent_downloads: usize,
+    /// Nombre de nouvelles tentatives automatiques après une erreur temporaire
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    /// Délai avant la première nouvelle tentative, doublé à chaque essai
+    #[serde(default = "default_retry_base_delay_secs")]
+    pub retry_base_delay_secs: u64,
+    /// Plafond du délai entre deux tentatives
+    #[serde(default = "default_retry_max_delay_secs")]
+    pub retry_max_delay_secs: u64,
+    /// Au démarrage du serveur, reprendre les téléchargements interrompus (sinon ils passent en échec)
+    #[serde(default = "default_resume_interrupted_downloads")]
+    pub resume_interrupted_downloads: bool,
+    /// Que faire d'une vidéo déjà téléchargée (même extracteur et même identifiant)
+    #[serde(default)]
+    pub duplicate_policy: DuplicatePolicy,
+    /// Débit maximal global (ex. "2M"), partagé entre les téléchargements actifs ; absent = illimité
+    #[serde(default)]
+    pub rate_limit: Option<String>,
+    /// Plages horaires où une autre limite remplace `rate_limit`
+    #[serde(default)]
+    pub rate_limit_schedule: Vec<RateLimitWindow>,
+    /// Taille maximale de la bibliothèque (ex. "500G") ; absent = pas de quota
+    #[serde(default)]
+    pub library_quota: Option<String>,
+    /// Espace libre à préserver sur le disque (ex. "5G") ; en dessous, les téléchargements sont mis en pause
+    #[serde(default)]
+    pub min_free_space: Option<String>,
+    /// Que faire quand le chemin de sortie existe déjà
+    #[serde(default)]
+    pub collision_policy: CollisionPolicy,
+    /// Longueur maximale d'un nom de fichier ou de dossier, en octets
+    #[serde(default = "default_max_filename_length")]
+    pub max_filename_length: usize,
+    /// Gabarits de chemin par type, ex. `{uploader}/{playlist}/{index:02} - {title}.{ext}`
+    #[serde(default)]
+    pub output_templates: OutputTemplates,
+    /// Dossier de travail des téléchargements en cours ; par défaut `<download_directory>/.staging`.
+    /// Sur le même disque que la bibliothèque, les fichiers terminés y sont déplacés atomiquement
+    #[serde(default)]
+    pub staging_directory: Option<String>,
+}
+
+/// Politique appliquée aux doublons détectés dans l'historique
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicatePolicy {
+    /// Ne rien retélécharger : renvoyer le téléchargement existant
+    Skip,
+    /// Télécharger quand même en signalant le doublon
+    #[default]
+    Warn,
+    /// Ne pas vérifier
+    Allow,
+}
+
+fn default_max_concurrent_downloads() -> usize {
+    3
+}
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+fn default_retry_base_delay_secs() -> u64 {
+    10
+}
+
+fn default_retry_max_delay_secs() -> u64 {
+    600
+}
+
+fn default_resume_interrupted_downloads() -> bool {
+    true
+}
+
+fn default_max_filename_length() -> usize {
+    // Marge sous les 255 octets des systèmes de fichiers pour les suffixes de yt-dlp (.f137.webm.part)
+    200
 }
 
 impl Default for Config {
@@ -25,6 +107,20 @@ impl Default for Config {
             default_video_format: "mp4".to_string(),
             download_directory,
             keep_temporary_files: false,
+            max_concurrent_downloads: default_max_concurrent_downloads(),
+            max_retries: default_max_retries(),
+            retry_base_delay_secs: default_retry_base_delay_secs(),
+            retry_max_delay_secs: default_retry_max_delay_secs(),
+            resume_interrupted_downloads: default_resume_interrupted_downloads(),
+            duplicate_policy: DuplicatePolicy::default(),
+            rate_limit: None,
+            rate_limit_schedule: Vec::new(),
+            library_quota: None,
+            min_free_space: None,
+            collision_policy: CollisionPolicy::default(),
+            max_filename_length: default_max_filename_length(),
+            output_templates: OutputTemplates::default(),
+            staging_directory: None,
         }
     }
 }
@@ -0,0 +1,177 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::process::Child;
+use tokio::sync::watch;
+use log::warn;
+
+use crate::progress::ProgressCallback;
+use crate::staging::Staging;
+
+/// Points d'extension fournis par l'appelant d'un téléchargement :
+/// suivi de la progression, interruption à distance, débit alloué et dossier de travail.
+#[derive(Default)]
+pub struct DownloadHooks {
+    pub progress: Option<ProgressCallback>,
+    pub abort: Option<AbortHandle>,
+    /// Débit maximal en octets par seconde (`--limit-rate`), fixé au lancement
+    pub rate_limit: Option<u64>,
+    /// Dossier de travail fourni par l'appelant, qui le supprime lui-même en cas d'échec ;
+    /// sinon un dossier unique est créé puis supprimé par le téléchargement
+    pub staging: Option<Staging>,
+}
+
+impl DownloadHooks {
+    /// Se termine lorsque l'arrêt est demandé ; ne se termine jamais sans [`AbortHandle`].
+    pub async fn aborted(&self) {
+        match &self.abort {
+            Some(handle) => handle.aborted().await,
+            None => std::future::pending().await,
+        }
+    }
+}
+
+/// Handle partagé permettant d'interrompre un téléchargement depuis une autre tâche.
+#[derive(Clone)]
+pub struct AbortHandle {
+    sender: Arc<watch::Sender<bool>>,
+}
+
+impl AbortHandle {
+    pub fn new() -> Self {
+        let (sender, _) = watch::channel(false);
+        Self { sender: Arc::new(sender) }
+    }
+
+    /// Demande l'arrêt du téléchargement associé.
+    pub fn abort(&self) {
+        self.sender.send_replace(true);
+    }
+
+    pub fn is_aborted(&self) -> bool {
+        *self.sender.borrow()
+    }
+
+    /// Se termine dès que [`AbortHandle::abort`] a été appelé.
+    pub async fn aborted(&self) {
+        let mut receiver = self.sender.subscribe();
+        // L'émetteur vit aussi longtemps que `self` : l'erreur est impossible ici
+        let _ = receiver.wait_for(|aborted| *aborted).await;
+    }
+}
+
+impl Default for AbortHandle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Erreur renvoyée lorsqu'un téléchargement a été interrompu via un [`AbortHandle`].
+///
+/// Liste les fichiers partiels laissés par yt-dlp (`.part`, `.ytdl`, fragments…) :
+/// l'appelant décide de les conserver (reprise) ou de les supprimer (annulation).
+#[derive(Debug, Clone)]
+pub struct DownloadAborted {
+    pub partial_files: Vec<PathBuf>,
+}
+
+impl fmt::Display for DownloadAborted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Téléchargement interrompu")
+    }
+}
+
+impl std::error::Error for DownloadAborted {}
+
+/// Fichiers existants appartenant à une destination yt-dlp : le fichier lui-même et
+/// ses dérivés temporaires (`<dest>.part`, `<dest>.ytdl`, `<dest>.part-Frag12`…).
+pub fn partial_files_for(destination: &Path) -> Vec<PathBuf> {
+    let (Some(parent), Some(name)) = (destination.parent(), destination.file_name()) else {
+        return Vec::new();
+    };
+    let name = name.to_string_lossy().to_string();
+
+    let Ok(entries) = std::fs::read_dir(parent) else {
+        return Vec::new();
+    };
+
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .map(|n| n.to_string_lossy().starts_with(&name))
+                .unwrap_or(false)
+        })
+        .collect()
+}
+
+/// Tue yt-dlp ainsi que les processus qu'il a lancés (ffmpeg…).
+///
+/// Sous Unix, yt-dlp est lancé dans son propre groupe de processus : tout le groupe
+/// reçoit SIGTERM. Sous Windows, `taskkill /T` termine l'arbre complet.
+pub async fn kill_process_tree(child: &mut Child) {
+    if let Some(pid) = child.id() {
+        #[cfg(unix)]
+        {
+            // SAFETY: simple appel système, un pid négatif cible le groupe de processus
+            unsafe {
+                libc::kill(-(pid as i32), libc::SIGTERM);
+            }
+        }
+
+        #[cfg(windows)]
+        {
+            let _ = tokio::process::Command::new("taskkill")
+                .args(["/T", "/F", "/PID", &pid.to_string()])
+                .status()
+                .await;
+        }
+    }
+
+    if let Err(e) = child.kill().await {
+        warn!("Impossible de tuer le processus yt-dlp : {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_abort_handle_wakes_waiters() {
+        let handle = AbortHandle::new();
+        let waiter = {
+            let handle = handle.clone();
+            tokio::spawn(async move { handle.aborted().await })
+        };
+
+        assert!(!handle.is_aborted());
+        handle.abort();
+        tokio::time::timeout(Duration::from_secs(1), waiter)
+            .await
+            .expect("aborted() should resolve after abort()")
+            .unwrap();
+        assert!(handle.is_aborted());
+    }
+
+    #[test]
+    fn test_partial_files_for_destination() {
+        let dir = std::env::temp_dir().join(format!("rmd-partial-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in ["clip.mp4.part", "clip.mp4.ytdl", "clip.mp4.part-Frag3", "other.mp4"] {
+            std::fs::write(dir.join(name), b"").unwrap();
+        }
+
+        let mut found = partial_files_for(&dir.join("clip.mp4"));
+        found.sort();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!(found, vec![
+            dir.join("clip.mp4.part"),
+            dir.join("clip.mp4.part-Frag3"),
+            dir.join("clip.mp4.ytdl"),
+        ]);
+    }
+}
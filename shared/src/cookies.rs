@@ -3,6 +3,7 @@ use dialoguer::{Select, theme::ColorfulTheme};
 use std::process::{Command, Stdio};
 use which::which;
 use log::{info, warn, error};
+use crate::media_backend::YtDlp;
 
 // Liste des navigateurs supportés et leurs noms pour yt-dlp
 const BROWSERS: &[(&str, &str)] = &[
@@ -71,11 +72,9 @@ pub fn extract_cookies_and_download(url: &str) {
 fn download_with_cookies(url: &str, browser: &str) {
     info!("{}", "\n📥 Téléchargement en cours...".cyan().bold());
 
-    let mut command = Command::new("yt-dlp");
+    let mut command = Command::new(YtDlp::default().program());
     command
-        .arg("--cookies-from-browser")
-        .arg(browser)
-        .arg(url)
+        .args(YtDlp::cookies_download_args(url, browser))
         .stdout(Stdio::inherit()) // Affiche la sortie de yt-dlp en temps réel
         .stderr(Stdio::inherit()); // Affiche les erreurs de yt-dlp en temps réel
 
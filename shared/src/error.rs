@@ -0,0 +1,260 @@
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use crate::format::FormatError;
+
+/// Nombre de lignes de stderr conservées pour le diagnostic.
+pub(crate) const STDERR_TAIL: usize = 20;
+
+/// Motifs de stderr (en minuscules) de chaque catégorie, dans l'ordre où ils sont testés :
+/// « HTTP Error 404 » ou « 429 » l'emportent ainsi sur « Unable to download webpage ».
+const PATTERNS: &[(DownloadError, &[&str])] = &[
+    (DownloadError::DiskFull, &["no space left on device", "errno 28", "disk quota exceeded"]),
+    (DownloadError::FfmpegMissing, &["ffmpeg not found", "ffprobe not found", "ffprobe and ffmpeg not found", "ffmpeg is not installed"]),
+    (DownloadError::RateLimited, &["http error 429", "too many requests", "rate-limited", "rate limit"]),
+    (DownloadError::Private, &["private video", "this video is private", "video is private"]),
+    (DownloadError::SignInRequired, &[
+        "sign in to confirm",
+        "age-restricted",
+        "age restricted",
+        "inappropriate for some users",
+        "login required",
+        "requires authentication",
+        "members-only",
+        "join this channel",
+    ]),
+    (DownloadError::GeoBlocked, &["available in your country", "geo restriction", "geo-restricted", "geo restricted", "blocked it in your country"]),
+    (DownloadError::UnsupportedUrl, &["unsupported url"]),
+    (DownloadError::NoMatchingFormat, &["requested format is not available", "requested format not available", "no video formats found"]),
+    (DownloadError::Unavailable, &[
+        "video unavailable",
+        "this video is unavailable",
+        "has been removed",
+        "has been terminated",
+        "http error 404",
+        "does not exist",
+    ]),
+    (DownloadError::Network, &[
+        "http error 500",
+        "http error 502",
+        "http error 503",
+        "http error 504",
+        "timed out",
+        "connection reset",
+        "connection refused",
+        "connection aborted",
+        "remote end closed connection",
+        "temporary failure in name resolution",
+        "name or service not known",
+        "network is unreachable",
+        "incompleteread",
+        "unable to download webpage",
+        "unable to download json metadata",
+    ]),
+];
+
+/// Cause d'un échec de téléchargement, déduite de la sortie d'erreur de yt-dlp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DownloadError {
+    Unavailable,
+    Private,
+    /// Vidéo soumise à une limite d'âge, réservée aux membres ou vérification anti-robot
+    SignInRequired,
+    GeoBlocked,
+    UnsupportedUrl,
+    RateLimited,
+    NoMatchingFormat,
+    FfmpegMissing,
+    DiskFull,
+    Network,
+    Unknown,
+}
+
+impl DownloadError {
+    pub const ALL: [DownloadError; 11] = [
+        DownloadError::Unavailable,
+        DownloadError::Private,
+        DownloadError::SignInRequired,
+        DownloadError::GeoBlocked,
+        DownloadError::UnsupportedUrl,
+        DownloadError::RateLimited,
+        DownloadError::NoMatchingFormat,
+        DownloadError::FfmpegMissing,
+        DownloadError::DiskFull,
+        DownloadError::Network,
+        DownloadError::Unknown,
+    ];
+
+    /// Catégorie des lignes de stderr ; les lignes `ERROR:` font foi quand il y en a.
+    pub fn classify(stderr: &[String]) -> Self {
+        let errors: Vec<String> = stderr.iter().filter(|line| line.starts_with("ERROR:")).map(|line| line.to_lowercase()).collect();
+        let lines = if errors.is_empty() { stderr.iter().map(|line| line.to_lowercase()).collect() } else { errors };
+
+        PATTERNS.iter()
+            .find(|(_, patterns)| lines.iter().any(|line| patterns.iter().any(|pattern| line.contains(pattern))))
+            .map(|(kind, _)| *kind)
+            .unwrap_or(DownloadError::Unknown)
+    }
+
+    /// Catégorie d'une erreur quelconque du téléchargement (yt-dlp, disque, format…).
+    pub fn of(error: &anyhow::Error) -> Self {
+        for cause in error.chain() {
+            if let Some(failed) = cause.downcast_ref::<YtDlpFailed>() {
+                return failed.error();
+            }
+            if cause.downcast_ref::<FormatError>().is_some() {
+                return DownloadError::NoMatchingFormat;
+            }
+            if cause.downcast_ref::<std::io::Error>().is_some_and(|e| e.kind() == std::io::ErrorKind::StorageFull) {
+                return DownloadError::DiskFull;
+            }
+        }
+        DownloadError::Unknown
+    }
+
+    /// Code stable exposé par l'API (`rate_limited`, `geo_blocked`…).
+    pub fn code(&self) -> &'static str {
+        match self {
+            DownloadError::Unavailable => "unavailable",
+            DownloadError::Private => "private",
+            DownloadError::SignInRequired => "sign_in_required",
+            DownloadError::GeoBlocked => "geo_blocked",
+            DownloadError::UnsupportedUrl => "unsupported_url",
+            DownloadError::RateLimited => "rate_limited",
+            DownloadError::NoMatchingFormat => "no_matching_format",
+            DownloadError::FfmpegMissing => "ffmpeg_missing",
+            DownloadError::DiskFull => "disk_full",
+            DownloadError::Network => "network",
+            DownloadError::Unknown => "unknown",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.code() == code)
+    }
+
+    /// Explication destinée à l'utilisateur.
+    pub fn message(&self) -> &'static str {
+        match self {
+            DownloadError::Unavailable => "The video is unavailable or has been removed",
+            DownloadError::Private => "The video is private",
+            DownloadError::SignInRequired => "The video requires signing in (age-restricted or members only), try again with browser cookies",
+            DownloadError::GeoBlocked => "The video is not available in this country",
+            DownloadError::UnsupportedUrl => "This URL is not supported",
+            DownloadError::RateLimited => "The site is rate limiting requests, try again later",
+            DownloadError::NoMatchingFormat => "No format matches the requested format or quality",
+            DownloadError::FfmpegMissing => "ffmpeg is required for this download but is not installed",
+            DownloadError::DiskFull => "There is not enough disk space left",
+            DownloadError::Network => "A network error interrupted the download",
+            DownloadError::Unknown => "yt-dlp failed",
+        }
+    }
+
+    /// Vrai si une nouvelle tentative a des chances d'aboutir (réseau, 5xx, 429).
+    pub fn is_transient(&self) -> bool {
+        matches!(self, DownloadError::RateLimited | DownloadError::Network)
+    }
+}
+
+impl fmt::Display for DownloadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message())
+    }
+}
+
+/// yt-dlp s'est terminé en erreur ; conserve la fin de sa sortie d'erreur.
+#[derive(Debug, Clone)]
+pub struct YtDlpFailed {
+    pub code: Option<i32>,
+    pub stderr: Vec<String>,
+}
+
+impl YtDlpFailed {
+    /// Dernière ligne `ERROR:` de yt-dlp, ou la dernière ligne de stderr.
+    pub fn last_error(&self) -> Option<&str> {
+        self.stderr
+            .iter()
+            .rev()
+            .find(|line| line.starts_with("ERROR:"))
+            .or_else(|| self.stderr.last())
+            .map(|line| line.as_str())
+    }
+
+    /// Cause de l'échec d'après la sortie d'erreur.
+    pub fn error(&self) -> DownloadError {
+        DownloadError::classify(&self.stderr)
+    }
+
+    /// Vrai si une nouvelle tentative a des chances d'aboutir.
+    pub fn is_transient(&self) -> bool {
+        self.error().is_transient()
+    }
+}
+
+impl fmt::Display for YtDlpFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.error(), self.code) {
+            (DownloadError::Unknown, Some(code)) => write!(f, "yt-dlp failed with exit code {}", code)?,
+            (kind, _) => write!(f, "{}", kind)?,
+        }
+        if let Some(error) = self.last_error() {
+            write!(f, " ({})", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for YtDlpFailed {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failed(lines: &[&str]) -> YtDlpFailed {
+        YtDlpFailed { code: Some(1), stderr: lines.iter().map(|l| l.to_string()).collect() }
+    }
+
+    #[test]
+    fn test_transient_errors() {
+        assert!(failed(&["ERROR: [youtube] abc: Unable to download webpage: HTTP Error 503: Service Unavailable"]).is_transient());
+        assert!(failed(&["ERROR: unable to download video data: HTTP Error 429: Too Many Requests"]).is_transient());
+        assert!(failed(&["ERROR: [Errno 104] Connection reset by peer"]).is_transient());
+        assert!(!failed(&["ERROR: [youtube] abc: Video unavailable"]).is_transient());
+        assert!(!failed(&["ERROR: Unsupported URL: https://example.com"]).is_transient());
+    }
+
+    #[test]
+    fn test_classify_stderr() {
+        let cases = [
+            ("ERROR: [youtube] abc: Video unavailable. This video has been removed by the uploader", DownloadError::Unavailable),
+            ("ERROR: [youtube] abc: Private video. Sign in if you've been granted access to this video", DownloadError::Private),
+            ("ERROR: [youtube] abc: Sign in to confirm your age. This video may be inappropriate for some users.", DownloadError::SignInRequired),
+            ("ERROR: [youtube] abc: The uploader has not made this video available in your country", DownloadError::GeoBlocked),
+            ("ERROR: Unsupported URL: https://example.com", DownloadError::UnsupportedUrl),
+            ("ERROR: [youtube] abc: Unable to download webpage: HTTP Error 429: Too Many Requests", DownloadError::RateLimited),
+            ("ERROR: [youtube] abc: Requested format is not available. Use --list-formats for a list of available formats", DownloadError::NoMatchingFormat),
+            ("ERROR: Postprocessing: ffprobe and ffmpeg not found. Please install or provide the path using --ffmpeg-location", DownloadError::FfmpegMissing),
+            ("ERROR: unable to write data: [Errno 28] No space left on device", DownloadError::DiskFull),
+            ("ERROR: [Errno 104] Connection reset by peer", DownloadError::Network),
+            ("ERROR: [generic] abc: Unable to download webpage: HTTP Error 404: Not Found", DownloadError::Unavailable),
+            ("ERROR: something nobody expected", DownloadError::Unknown),
+        ];
+        for (line, expected) in cases {
+            assert_eq!(failed(&[line]).error(), expected, "{}", line);
+            assert_eq!(DownloadError::from_code(expected.code()), Some(expected));
+        }
+
+        // A warning mentioning a known pattern does not hide the actual error
+        assert_eq!(failed(&["WARNING: [youtube] Sign in to confirm you're not a bot", "ERROR: Unsupported URL: x"]).error(), DownloadError::UnsupportedUrl);
+        assert_eq!(DownloadError::of(&failed(&["ERROR: Private video"]).into()), DownloadError::Private);
+        assert_eq!(DownloadError::of(&anyhow::anyhow!("spawn failed")), DownloadError::Unknown);
+    }
+
+    #[test]
+    fn test_display_uses_last_error_line() {
+        let error = failed(&["WARNING: something", "ERROR: Video unavailable", "debug noise"]);
+        assert_eq!(error.to_string(), "The video is unavailable or has been removed (ERROR: Video unavailable)");
+        let error = failed(&["ERROR: something nobody expected"]);
+        assert_eq!(error.to_string(), "yt-dlp failed with exit code 1 (ERROR: something nobody expected)");
+    }
+}
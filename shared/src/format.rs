@@ -0,0 +1,371 @@
+use std::fmt;
+
+use crate::postprocess::{applied_steps, PostProcessing};
+
+/// Conteneurs vidéo pour lesquels yt-dlp sait fusionner les flux (`--merge-output-format`).
+const VIDEO_CONTAINERS: &[&str] = &["mp4", "mkv", "webm", "mov", "flv", "avi"];
+
+/// Valeurs acceptées par `--audio-format`.
+const AUDIO_FORMATS: &[&str] = &["best", "aac", "alac", "flac", "m4a", "mp3", "opus", "vorbis", "wav"];
+
+/// Conteneurs capables d'embarquer des sous-titres (`--embed-subs`).
+pub(crate) const SUBTITLE_CONTAINERS: &[&str] = &["mp4", "mkv", "webm", "mov"];
+
+/// Combinaison d'options refusée par [`FormatSelector::build`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum FormatError {
+    InvalidResolution(String),
+    InvalidAudioQuality(String),
+    InvalidAudioFormat(String),
+    InvalidSubtitleLanguage(String),
+    InvalidSponsorBlockCategory(String),
+    /// Options valides séparément mais incompatibles entre elles
+    Conflict(&'static str),
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::InvalidResolution(r) => write!(f, "Invalid resolution '{}' (expected e.g. 1080p, 720p or best)", r),
+            FormatError::InvalidAudioQuality(q) => write!(f, "Invalid audio quality '{}' (expected e.g. 192k, 320k, 0-10 or best)", q),
+            FormatError::InvalidAudioFormat(a) => write!(f, "Unsupported audio format '{}'", a),
+            FormatError::InvalidSubtitleLanguage(l) => write!(f, "Invalid subtitle language '{}'", l),
+            FormatError::InvalidSponsorBlockCategory(c) => write!(f, "Invalid SponsorBlock category '{}'", c),
+            FormatError::Conflict(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MediaKind {
+    Video,
+    Audio,
+}
+
+/// Construit les arguments de sélection de format de yt-dlp
+/// (`-f`/`-S`, `--audio-quality`, `--write-subs`/`--sub-langs`/`--embed-subs`)
+/// et des post-traitements qui dépendent du fichier produit.
+///
+/// ```
+/// use rust_media_downloader_shared::format::FormatSelector;
+///
+/// let args = FormatSelector::video("mp4").resolution("720p").build().unwrap();
+/// assert!(args.contains(&"res:720".to_string()));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormatSelector {
+    kind: MediaKind,
+    format: String,
+    resolution: Option<String>,
+    audio_quality: Option<String>,
+    subtitles: bool,
+    subtitle_language: Option<String>,
+    post_processing: PostProcessing,
+}
+
+impl FormatSelector {
+    /// Vidéo dans le conteneur donné (`mp4`, `mkv`…), `best`, ou sélecteur `-f` brut.
+    pub fn video(format: &str) -> Self {
+        Self::new(MediaKind::Video, format)
+    }
+
+    /// Extraction audio dans le format donné (`mp3`, `flac`…).
+    pub fn audio(audio_format: &str) -> Self {
+        Self::new(MediaKind::Audio, audio_format)
+    }
+
+    fn new(kind: MediaKind, format: &str) -> Self {
+        Self {
+            kind,
+            format: format.trim().to_string(),
+            resolution: None,
+            audio_quality: None,
+            subtitles: false,
+            subtitle_language: None,
+            post_processing: PostProcessing::default(),
+        }
+    }
+
+    /// Hauteur maximale (`1080p`, `720`, `best`).
+    pub fn resolution(mut self, resolution: &str) -> Self {
+        self.resolution = Some(resolution.trim().to_string());
+        self
+    }
+
+    /// Qualité audio (`192k`, `0`-`10` en VBR pour l'audio seul, `best`).
+    pub fn audio_quality(mut self, audio_quality: &str) -> Self {
+        self.audio_quality = Some(audio_quality.trim().to_string());
+        self
+    }
+
+    /// Active les sous-titres ; `language` accepte une liste séparée par des virgules ou `auto`.
+    pub fn subtitles(mut self, enabled: bool, language: Option<&str>) -> Self {
+        self.subtitles = enabled;
+        self.subtitle_language = language.map(|l| l.trim().to_string());
+        self
+    }
+
+    /// Métadonnées, miniature, chapitres, sous-titres intégrés et SponsorBlock.
+    pub fn post_processing(mut self, post_processing: PostProcessing) -> Self {
+        self.post_processing = post_processing;
+        self
+    }
+
+    /// Vrai pour une extraction audio.
+    pub fn is_audio(&self) -> bool {
+        self.kind == MediaKind::Audio
+    }
+
+    /// Post-traitements réellement passés à yt-dlp, y compris les sous-titres intégrés.
+    pub fn post_processing_steps(&self) -> Result<Vec<String>, FormatError> {
+        self.build().map(|args| applied_steps(&args))
+    }
+
+    /// Vérifie les options sans produire d'arguments.
+    pub fn validate(&self) -> Result<(), FormatError> {
+        self.build().map(|_| ())
+    }
+
+    /// Arguments yt-dlp correspondant aux options, ou l'erreur de la première incohérence.
+    pub fn build(&self) -> Result<Vec<String>, FormatError> {
+        let mut args = Vec::new();
+        let max_height = self.resolution.as_deref().map(parse_resolution).transpose()?.flatten();
+        let audio_quality = self.audio_quality.as_deref().map(parse_audio_quality).transpose()?.flatten();
+
+        match self.kind {
+            MediaKind::Video => {
+                let format = self.format.to_lowercase();
+                let container = VIDEO_CONTAINERS.contains(&format.as_str()).then_some(format.as_str());
+                let raw_selector = !format.is_empty() && format != "best" && container.is_none();
+
+                if raw_selector && (max_height.is_some() || audio_quality.is_some()) {
+                    return Err(FormatError::Conflict("resolution and audio_quality cannot be combined with a raw yt-dlp format selector"));
+                }
+                // Le flux audio d'une vidéo n'est pas réencodé : seul un débit sert à le choisir
+                if matches!(audio_quality, Some(AudioQuality::Vbr(_))) {
+                    return Err(FormatError::Conflict("a VBR audio quality (0-10) only applies to audio downloads, use a bitrate such as 192k"));
+                }
+
+                if raw_selector {
+                    args.extend(["-f".to_string(), self.format.clone()]);
+                } else if container.is_some() || max_height.is_some() || audio_quality.is_some() {
+                    // Meilleure vidéo + meilleur audio fusionnés, le tri -S applique les limites
+                    args.extend(["-f".to_string(), "bv*+ba/b".to_string()]);
+                }
+
+                let mut sort = Vec::new();
+                if let Some(height) = max_height {
+                    sort.push(format!("res:{}", height));
+                }
+                if let Some(AudioQuality::Bitrate(kbps)) = audio_quality {
+                    sort.push(format!("abr:{}", kbps));
+                }
+                if !sort.is_empty() {
+                    args.extend(["-S".to_string(), sort.join(",")]);
+                }
+
+                if let Some(container) = container {
+                    args.extend(["--merge-output-format".to_string(), container.to_string()]);
+                }
+
+                if self.subtitles {
+                    if container.is_some_and(|c| !SUBTITLE_CONTAINERS.contains(&c)) {
+                        return Err(FormatError::Conflict("subtitles cannot be embedded in this container"));
+                    }
+                    args.extend(self.subtitle_args()?);
+                    args.push("--embed-subs".to_string());
+                } else if self.subtitle_language.is_some() {
+                    return Err(FormatError::Conflict("subtitle_language requires download_subtitles"));
+                }
+            }
+            MediaKind::Audio => {
+                if max_height.is_some() {
+                    return Err(FormatError::Conflict("resolution cannot be used for audio downloads"));
+                }
+                if self.subtitles || self.subtitle_language.is_some() {
+                    return Err(FormatError::Conflict("subtitles are not available for audio downloads"));
+                }
+
+                let audio_format = if self.format.is_empty() { "best".to_string() } else { self.format.to_lowercase() };
+                if !AUDIO_FORMATS.contains(&audio_format.as_str()) {
+                    return Err(FormatError::InvalidAudioFormat(self.format.clone()));
+                }
+                // Formats sans perte : une qualité explicite n'aurait aucun effet
+                if matches!(audio_format.as_str(), "flac" | "wav" | "alac") && matches!(audio_quality, Some(AudioQuality::Bitrate(_))) {
+                    return Err(FormatError::Conflict("a bitrate cannot be applied to a lossless audio format"));
+                }
+
+                args.extend([
+                    "-f".to_string(),
+                    "bestaudio/best".to_string(), // Fallback if bestaudio is not available
+                    "--extract-audio".to_string(),
+                    "--audio-format".to_string(),
+                    audio_format,
+                ]);
+
+                match audio_quality {
+                    Some(AudioQuality::Bitrate(kbps)) => args.extend(["--audio-quality".to_string(), format!("{}K", kbps)]),
+                    Some(AudioQuality::Vbr(level)) => args.extend(["--audio-quality".to_string(), level.to_string()]),
+                    None => {}
+                }
+            }
+        }
+
+        args.extend(self.post_processing.build(self.is_audio(), self.container().as_deref(), self.subtitles)?);
+        Ok(args)
+    }
+
+    /// Extension du fichier produit quand elle est connue d'avance.
+    fn container(&self) -> Option<String> {
+        let format = self.format.to_lowercase();
+        match self.kind {
+            MediaKind::Video => VIDEO_CONTAINERS.contains(&format.as_str()).then_some(format),
+            MediaKind::Audio => (!format.is_empty() && format != "best").then_some(format),
+        }
+    }
+
+    fn subtitle_args(&self) -> Result<Vec<String>, FormatError> {
+        let language = self.subtitle_language.as_deref().filter(|l| !l.is_empty());
+        match language {
+            // Sous-titres générés automatiquement, dans toutes les langues disponibles
+            Some("auto") => Ok(vec!["--write-auto-subs".to_string(), "--sub-langs".to_string(), "all,-live_chat".to_string()]),
+            Some(languages) => {
+                let valid = languages.split(',').all(|lang| {
+                    !lang.is_empty() && lang.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '*'))
+                });
+                if !valid {
+                    return Err(FormatError::InvalidSubtitleLanguage(languages.to_string()));
+                }
+                Ok(vec!["--write-subs".to_string(), "--sub-langs".to_string(), languages.to_string()])
+            }
+            None => Ok(vec!["--write-subs".to_string(), "--sub-langs".to_string(), "all,-live_chat".to_string()]),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum AudioQuality {
+    Bitrate(u32),
+    Vbr(u8),
+}
+
+/// `None` pour `best`, sinon la hauteur maximale en pixels.
+fn parse_resolution(resolution: &str) -> Result<Option<u32>, FormatError> {
+    let lower = resolution.to_lowercase();
+    if lower.is_empty() || lower == "best" {
+        return Ok(None);
+    }
+    let height = match lower.as_str() {
+        "4k" => 2160,
+        "8k" => 4320,
+        other => other
+            .trim_end_matches('p')
+            .parse::<u32>()
+            .map_err(|_| FormatError::InvalidResolution(resolution.to_string()))?,
+    };
+    if !(144..=4320).contains(&height) {
+        return Err(FormatError::InvalidResolution(resolution.to_string()));
+    }
+    Ok(Some(height))
+}
+
+/// `None` pour `best`, sinon un débit (`192k`) ou un niveau VBR (`0` meilleur, `10` pire).
+fn parse_audio_quality(quality: &str) -> Result<Option<AudioQuality>, FormatError> {
+    let lower = quality.to_lowercase();
+    if lower.is_empty() || lower == "best" {
+        return Ok(None);
+    }
+    let invalid = || FormatError::InvalidAudioQuality(quality.to_string());
+
+    if let Some(kbps) = lower.strip_suffix('k') {
+        let kbps = kbps.parse::<u32>().map_err(|_| invalid())?;
+        if !(8..=512).contains(&kbps) {
+            return Err(invalid());
+        }
+        return Ok(Some(AudioQuality::Bitrate(kbps)));
+    }
+
+    match lower.parse::<u8>() {
+        Ok(level) if level <= 10 => Ok(Some(AudioQuality::Vbr(level))),
+        _ => Err(invalid()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_video_resolution_and_subtitles() {
+        let args = FormatSelector::video("mp4")
+            .resolution("1080p")
+            .subtitles(true, Some("fr,en"))
+            .build()
+            .unwrap();
+
+        assert_eq!(args, vec![
+            "-f", "bv*+ba/b", "-S", "res:1080", "--merge-output-format", "mp4",
+            "--write-subs", "--sub-langs", "fr,en", "--embed-subs",
+        ]);
+    }
+
+    #[test]
+    fn test_audio_quality() {
+        let args = FormatSelector::audio("mp3").audio_quality("192k").build().unwrap();
+        assert_eq!(args, vec![
+            "-f", "bestaudio/best", "--extract-audio", "--audio-format", "mp3", "--audio-quality", "192K",
+        ]);
+        assert!(FormatSelector::video("best").build().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_rejects_impossible_combinations() {
+        assert!(matches!(FormatSelector::audio("mp3").resolution("720p").build(), Err(FormatError::Conflict(_))));
+        assert!(matches!(FormatSelector::audio("mp3").subtitles(true, None).build(), Err(FormatError::Conflict(_))));
+        assert!(matches!(FormatSelector::audio("flac").audio_quality("320k").build(), Err(FormatError::Conflict(_))));
+        assert!(matches!(FormatSelector::video("mp4").subtitles(false, Some("fr")).build(), Err(FormatError::Conflict(_))));
+        assert!(matches!(FormatSelector::video("137+140").resolution("720p").build(), Err(FormatError::Conflict(_))));
+        assert!(matches!(FormatSelector::video("mp4").audio_quality("5").build(), Err(FormatError::Conflict(_))));
+        assert!(matches!(FormatSelector::video("mp4").resolution("huge").build(), Err(FormatError::InvalidResolution(_))));
+        assert!(matches!(FormatSelector::audio("mp3").audio_quality("11").build(), Err(FormatError::InvalidAudioQuality(_))));
+        assert!(matches!(FormatSelector::audio("xyz").build(), Err(FormatError::InvalidAudioFormat(_))));
+    }
+
+    #[test]
+    fn test_post_processing() {
+        let post_processing = PostProcessing {
+            embed_metadata: true,
+            embed_thumbnail: true,
+            embed_subs: true,
+            sponsorblock_remove: vec!["sponsor".to_string(), "selfpromo".to_string()],
+            sponsorblock_mark: vec!["intro".to_string()],
+            ..Default::default()
+        };
+        let args = FormatSelector::video("mkv").subtitles(true, Some("fr")).post_processing(post_processing.clone()).build().unwrap();
+        // --embed-subs is not repeated when the subtitle options already ask for it
+        assert_eq!(args, vec![
+            "-f", "bv*+ba/b", "--merge-output-format", "mkv",
+            "--write-subs", "--sub-langs", "fr", "--embed-subs",
+            "--embed-metadata", "--embed-thumbnail",
+            "--sponsorblock-remove", "sponsor,selfpromo", "--sponsorblock-mark", "intro",
+        ]);
+        let selector = FormatSelector::video("mkv").subtitles(true, Some("fr")).post_processing(post_processing);
+        assert_eq!(selector.post_processing_steps().unwrap(), ["subtitles", "metadata", "thumbnail", "sponsorblock_remove:sponsor,selfpromo", "sponsorblock_mark:intro"]);
+        // Subtitles asked through download_subtitles alone are embedded too
+        assert_eq!(FormatSelector::video("mp4").subtitles(true, None).post_processing_steps().unwrap(), ["subtitles"]);
+
+        let audio = PostProcessing { embed_thumbnail: true, embed_chapters: true, ..Default::default() };
+        let args = FormatSelector::audio("mp3").post_processing(audio.clone()).build().unwrap();
+        assert!(args.ends_with(&["--embed-thumbnail".to_string(), "--embed-chapters".to_string()]));
+
+        assert!(matches!(FormatSelector::audio("wav").post_processing(audio).build(), Err(FormatError::Conflict(_))));
+        let subs = PostProcessing { embed_subs: true, ..Default::default() };
+        assert!(matches!(FormatSelector::audio("mp3").post_processing(subs).build(), Err(FormatError::Conflict(_))));
+        let unknown = PostProcessing { sponsorblock_mark: vec!["ads".to_string()], ..Default::default() };
+        assert_eq!(FormatSelector::video("mp4").post_processing(unknown).build(), Err(FormatError::InvalidSponsorBlockCategory("ads".to_string())));
+        let highlight = PostProcessing { sponsorblock_remove: vec!["poi_highlight".to_string()], ..Default::default() };
+        assert!(FormatSelector::video("mp4").post_processing(highlight).build().is_err());
+    }
+}
@@ -7,12 +7,35 @@ pub mod installers;
 pub mod utils;
 pub mod progress;
 pub mod video_info;
+pub mod control;
+pub mod outcome;
+pub mod format;
+pub mod error;
+pub mod media_id;
+pub mod bandwidth;
+pub mod preset;
+pub mod output_template;
+pub mod staging;
+pub mod media_backend;
+pub mod postprocess;
 
 // Re-export commonly used items
 pub use downloader::{download_video, download_audio};
 pub use spleeter::extract_instrumental;
-pub use config::{Config, load_config, save_config, get_config_path};
+pub use config::{Config, DuplicatePolicy, load_config, save_config, get_config_path};
 pub use cookies::extract_cookies_and_download;
 pub use commands::check_command;
 pub use installers::ensure_dependencies;
-pub use video_info::{VideoInfo, get_video_info};
+pub use video_info::{VideoInfo, Chapter, get_video_info, PlaylistInfo, PlaylistEntry, get_playlist_info};
+pub use progress::{DownloadProgress, ProgressCallback, ProgressEvent};
+pub use control::{DownloadHooks, AbortHandle, DownloadAborted};
+pub use outcome::{DownloadOutcome, DownloadedEntry};
+pub use format::{FormatSelector, FormatError};
+pub use postprocess::PostProcessing;
+pub use error::{DownloadError, YtDlpFailed};
+pub use media_id::MediaId;
+pub use bandwidth::RateLimitWindow;
+pub use preset::{Preset, PresetKind, PresetOptions, builtin_presets};
+pub use output_template::{CollisionPolicy, OutputTemplates};
+pub use staging::Staging;
+pub use media_backend::{MediaBackend, YtDlp, FakeBackend, DownloadSpec};
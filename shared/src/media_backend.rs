@@ -0,0 +1,403 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::process::Stdio;
+use std::sync::Mutex;
+use anyhow::{Context, Result};
+use log::{info, warn};
+use tokio::process::Command;
+
+use crate::control::{DownloadAborted, DownloadHooks};
+use crate::error::{YtDlpFailed, STDERR_TAIL};
+use crate::format::{FormatError, FormatSelector};
+use crate::outcome::{DownloadOutcome, DownloadedEntry, FILE_PRINT_TEMPLATE, INFO_PRINT_TEMPLATE};
+use crate::progress::{ProgressEvent, DOWNLOAD_PROGRESS_TEMPLATE, POSTPROCESS_PROGRESS_TEMPLATE};
+use crate::staging::Staging;
+use crate::video_info::{parse_playlist_info, parse_video_info, PlaylistInfo, VideoInfo};
+
+/// Future renvoyée par les méthodes de [`MediaBackend`], qui doit rester utilisable via `dyn`.
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Paramètres d'un téléchargement, indépendants de l'outil qui l'exécute.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DownloadSpec {
+    pub url: String,
+    pub selector: FormatSelector,
+    /// Conserve les flux intermédiaires après fusion (`-k`)
+    pub keep_files: bool,
+    /// Passe l'audio dans Spleeter et ne garde que l'instrumental
+    pub extract_instrumental: bool,
+    /// Chemin relatif sans extension, à la place du nom choisi par yt-dlp
+    pub custom_filename: Option<String>,
+    pub cookies_browser: Option<String>,
+    pub download_playlist: bool,
+}
+
+impl DownloadSpec {
+    pub fn new(url: &str, selector: FormatSelector) -> Self {
+        Self {
+            url: url.to_string(),
+            selector,
+            keep_files: false,
+            extract_instrumental: false,
+            custom_filename: None,
+            cookies_browser: None,
+            download_playlist: false,
+        }
+    }
+}
+
+/// Outil qui récupère les métadonnées, liste les playlists et télécharge.
+///
+/// [`YtDlp`] est l'implémentation réelle ; [`FakeBackend`] répond en mémoire
+/// pour tester le reste de l'application sans réseau ni yt-dlp.
+pub trait MediaBackend: Send + Sync {
+    /// Métadonnées d'une seule vidéo, sans télécharger.
+    fn fetch_info<'a>(&'a self, url: &'a str, cookies_browser: Option<&'a str>) -> BoxFuture<'a, Result<VideoInfo>>;
+
+    /// Entrées d'une playlist ou d'une chaîne ; `None` si l'URL désigne une seule vidéo.
+    fn list_playlist<'a>(&'a self, url: &'a str, cookies_browser: Option<&'a str>) -> BoxFuture<'a, Result<Option<PlaylistInfo>>>;
+
+    /// Télécharge dans le dossier de travail puis déplace les fichiers finaux dans la bibliothèque.
+    fn download<'a>(&'a self, spec: &'a DownloadSpec, hooks: DownloadHooks) -> BoxFuture<'a, Result<DownloadOutcome>>;
+}
+
+/// Implémentation de [`MediaBackend`] qui lance yt-dlp.
+///
+/// Tous les arguments passés à yt-dlp sont construits ici.
+#[derive(Debug, Clone)]
+pub struct YtDlp {
+    program: PathBuf,
+}
+
+impl Default for YtDlp {
+    fn default() -> Self {
+        Self::new("yt-dlp")
+    }
+}
+
+impl YtDlp {
+    /// Exécutable à lancer, cherché dans le PATH s'il n'est pas un chemin.
+    pub fn new(program: impl Into<PathBuf>) -> Self {
+        Self { program: program.into() }
+    }
+
+    pub fn program(&self) -> &Path {
+        &self.program
+    }
+
+    /// Commande prête à être lancée avec les arguments donnés.
+    pub fn command(&self, args: Vec<String>) -> Command {
+        let mut command = Command::new(&self.program);
+        command.args(args);
+        command
+    }
+
+    /// `--dump-json` d'une seule vidéo.
+    pub fn info_args(url: &str, cookies_browser: Option<&str>) -> Vec<String> {
+        let mut args = vec!["--dump-json".to_string(), "--no-playlist".to_string()];
+        push_cookies(&mut args, cookies_browser);
+        args.push(url.to_string());
+        args
+    }
+
+    /// Liste à plat d'une playlist, sans rien télécharger.
+    pub fn playlist_args(url: &str, cookies_browser: Option<&str>) -> Vec<String> {
+        let mut args = vec!["--flat-playlist".to_string(), "--dump-single-json".to_string()];
+        push_cookies(&mut args, cookies_browser);
+        args.push(url.to_string());
+        args
+    }
+
+    /// Téléchargement interactif avec les cookies d'un navigateur (menu du CLI).
+    pub fn cookies_download_args(url: &str, browser: &str) -> Vec<String> {
+        let mut args = Vec::new();
+        push_cookies(&mut args, Some(browser));
+        args.push(url.to_string());
+        args
+    }
+
+    /// Arguments d'un téléchargement : dossier de travail, lignes `--print` suivies par
+    /// [`crate::outcome::OutputTracker`], options de la requête et sélection de format.
+    pub fn download_args(spec: &DownloadSpec, staging: &Staging, rate_limit: Option<u64>) -> Result<Vec<String>, FormatError> {
+        let mut args = staging.yt_dlp_args();
+        // Une ligne par progression et le chemin final de chaque fichier
+        args.extend(["--newline", "--no-quiet", "--print", INFO_PRINT_TEMPLATE, "--print", FILE_PRINT_TEMPLATE].map(String::from));
+        // Progression en JSON, les anciennes versions retombent sur la ligne texte `[download]`
+        args.extend(["--progress-template", DOWNLOAD_PROGRESS_TEMPLATE, "--progress-template", POSTPROCESS_PROGRESS_TEMPLATE].map(String::from));
+
+        if !spec.download_playlist {
+            args.push("--no-playlist".to_string());
+        }
+        if let Some(rate) = rate_limit {
+            args.extend(["--limit-rate".to_string(), rate.to_string()]);
+        }
+        if let Some(filename) = &spec.custom_filename {
+            // Le nom choisi garde l'extension décidée par yt-dlp ; `%` est doublé pour ne pas
+            // être lu comme un champ du gabarit
+            args.extend(["-o".to_string(), format!("{}.%(ext)s", filename.replace('%', "%%"))]);
+        }
+        if spec.keep_files {
+            args.push("-k".to_string());
+        }
+        push_cookies(&mut args, spec.cookies_browser.as_deref());
+
+        // Format, résolution et sous-titres ; mp4 sélectionne vidéo+audio puis fusionne
+        // pour éviter l'avertissement "pre-merged mp4 format"
+        args.extend(spec.selector.build()?);
+        args.push(spec.url.clone());
+        Ok(args)
+    }
+
+    /// Lance yt-dlp et lit le JSON qu'il écrit sur stdout.
+    async fn dump_json(&self, args: Vec<String>, action: &str) -> Result<serde_json::Value> {
+        let mut command = self.command(args);
+        command.stdout(Stdio::piped());
+        command.stderr(Stdio::piped());
+
+        let output = command
+            .output()
+            .await
+            .context("Failed to execute yt-dlp")?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            warn!("yt-dlp error ({}): {}", action, stderr);
+            // Même erreur typée qu'un téléchargement, pour que la cause soit classée
+            let lines: Vec<String> = stderr.lines().filter(|line| !line.trim().is_empty()).map(String::from).collect();
+            let stderr = lines[lines.len().saturating_sub(STDERR_TAIL)..].to_vec();
+            return Err(YtDlpFailed { code: output.status.code(), stderr }.into());
+        }
+
+        serde_json::from_slice(&output.stdout).context("Failed to parse yt-dlp JSON output")
+    }
+}
+
+impl MediaBackend for YtDlp {
+    fn fetch_info<'a>(&'a self, url: &'a str, cookies_browser: Option<&'a str>) -> BoxFuture<'a, Result<VideoInfo>> {
+        Box::pin(async move {
+            info!("Fetching video info for: {}", url);
+            let raw_info = self.dump_json(Self::info_args(url, cookies_browser), "get video info").await?;
+            Ok(parse_video_info(&raw_info))
+        })
+    }
+
+    fn list_playlist<'a>(&'a self, url: &'a str, cookies_browser: Option<&'a str>) -> BoxFuture<'a, Result<Option<PlaylistInfo>>> {
+        Box::pin(async move {
+            info!("Fetching playlist entries for: {}", url);
+            let raw_info = self.dump_json(Self::playlist_args(url, cookies_browser), "list playlist").await?;
+            Ok(parse_playlist_info(&raw_info))
+        })
+    }
+
+    fn download<'a>(&'a self, spec: &'a DownloadSpec, hooks: DownloadHooks) -> BoxFuture<'a, Result<DownloadOutcome>> {
+        Box::pin(crate::downloader::run(self, spec, hooks))
+    }
+}
+
+fn push_cookies(args: &mut Vec<String>, cookies_browser: Option<&str>) {
+    if let Some(browser) = cookies_browser {
+        args.extend(["--cookies-from-browser".to_string(), browser.to_string()]);
+    }
+}
+
+/// [`MediaBackend`] en mémoire pour les tests : aucune commande n'est lancée.
+///
+/// Seules les URL enregistrées existent ; les autres échouent comme une URL non
+/// prise en charge par yt-dlp. Un téléchargement écrit un petit fichier dans le
+/// dossier de travail fourni par les hooks puis le déplace dans la bibliothèque.
+#[derive(Default)]
+pub struct FakeBackend {
+    videos: Mutex<HashMap<String, VideoInfo>>,
+    playlists: Mutex<HashMap<String, PlaylistInfo>>,
+    downloads: Mutex<Vec<DownloadSpec>>,
+}
+
+impl FakeBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Déclare une vidéo disponible à cette URL.
+    pub fn with_video(mut self, url: &str, info: VideoInfo) -> Self {
+        self.videos.get_mut().unwrap().insert(url.to_string(), info);
+        self
+    }
+
+    /// Déclare une playlist disponible à cette URL.
+    pub fn with_playlist(mut self, url: &str, playlist: PlaylistInfo) -> Self {
+        self.playlists.get_mut().unwrap().insert(url.to_string(), playlist);
+        self
+    }
+
+    /// Téléchargements demandés jusqu'ici, dans l'ordre.
+    pub fn downloads(&self) -> Vec<DownloadSpec> {
+        self.downloads.lock().unwrap().clone()
+    }
+
+    fn video(&self, url: &str) -> Result<VideoInfo> {
+        self.videos.lock().unwrap().get(url).cloned().ok_or_else(|| unsupported_url(url))
+    }
+}
+
+impl MediaBackend for FakeBackend {
+    fn fetch_info<'a>(&'a self, url: &'a str, _cookies_browser: Option<&'a str>) -> BoxFuture<'a, Result<VideoInfo>> {
+        Box::pin(async move { self.video(url) })
+    }
+
+    fn list_playlist<'a>(&'a self, url: &'a str, _cookies_browser: Option<&'a str>) -> BoxFuture<'a, Result<Option<PlaylistInfo>>> {
+        Box::pin(async move {
+            if let Some(playlist) = self.playlists.lock().unwrap().get(url) {
+                return Ok(Some(playlist.clone()));
+            }
+            self.video(url).map(|_| None)
+        })
+    }
+
+    fn download<'a>(&'a self, spec: &'a DownloadSpec, hooks: DownloadHooks) -> BoxFuture<'a, Result<DownloadOutcome>> {
+        Box::pin(async move {
+            self.downloads.lock().unwrap().push(spec.clone());
+            let info = self.video(&spec.url)?;
+            if hooks.abort.as_ref().is_some_and(|abort| abort.is_aborted()) {
+                return Err(DownloadAborted { partial_files: Vec::new() }.into());
+            }
+
+            let staging = hooks.staging.clone().context("FakeBackend : un dossier de travail est requis")?;
+            staging.create()?;
+            let extension = if spec.selector.is_audio() { "mp3" } else { "mp4" };
+            let name = spec.custom_filename.clone().unwrap_or_else(|| info.title.clone());
+            let file = staging.dir().join(format!("{}.{}", name, extension));
+            if let Some(parent) = file.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&file, info.id.as_bytes())?;
+
+            if let Some(callback) = &hooks.progress {
+                let size = info.id.len() as u64;
+                callback(ProgressEvent::Downloading { bytes: size, total: Some(size), speed: None, eta: None, fragment: None });
+                callback(ProgressEvent::Finished);
+            }
+
+            staging.commit(DownloadOutcome {
+                files: vec![file.clone()],
+                entries: vec![DownloadedEntry { id: info.id.clone(), extractor: info.extractor_key.clone(), file }],
+                video_id: Some(info.id.clone()),
+                info: Some(info),
+                ..Default::default()
+            })
+        })
+    }
+}
+
+/// Erreur renvoyée par yt-dlp pour une URL qu'aucun extracteur ne reconnaît.
+fn unsupported_url(url: &str) -> anyhow::Error {
+    YtDlpFailed { code: Some(1), stderr: vec![format!("ERROR: Unsupported URL: {}", url)] }.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::DownloadError;
+
+    fn args_for(spec: &DownloadSpec) -> Vec<String> {
+        let staging = Staging::new("/tmp/downloads/.staging/job", "/tmp/downloads");
+        YtDlp::download_args(spec, &staging, None).unwrap()
+    }
+
+    #[test]
+    fn test_download_args_video_with_all_options() {
+        let mut spec = DownloadSpec::new("https://test.url/video", FormatSelector::video("mp4"));
+        spec.keep_files = true;
+        spec.download_playlist = true;
+        let staging = Staging::new("/tmp/downloads/.staging/job", "/tmp/downloads");
+        let args = YtDlp::download_args(&spec, &staging, Some(500_000)).unwrap();
+
+        assert_eq!(&args[..2], ["-P", "/tmp/downloads/.staging/job"]);
+        assert!(args.contains(&"temp:/tmp/downloads/.staging/job/parts".to_string()));
+        assert!(args.contains(&"-k".to_string()));
+        assert!(args.windows(2).any(|w| w == ["-f", "bv*+ba/b"]));
+        assert!(args.windows(2).any(|w| w == ["--merge-output-format", "mp4"]));
+        assert!(args.windows(2).any(|w| w == ["--limit-rate", "500000"]));
+        assert!(!args.contains(&"--no-playlist".to_string()));
+        assert!(args.windows(2).any(|w| w == ["--progress-template", DOWNLOAD_PROGRESS_TEMPLATE]));
+        assert_eq!(args.last().map(String::as_str), Some("https://test.url/video"));
+    }
+
+    #[test]
+    fn test_download_args_video_minimal() {
+        let args = args_for(&DownloadSpec::new("https://test.url/simple", FormatSelector::video("")));
+
+        assert!(args.contains(&"--no-playlist".to_string()));
+        assert!(!args.contains(&"-k".to_string()));
+        assert!(!args.contains(&"-f".to_string()));
+        assert!(!args.contains(&"-o".to_string()));
+        assert!(!args.contains(&"--limit-rate".to_string()));
+    }
+
+    #[test]
+    fn test_download_args_audio() {
+        let url = "https://audio.test";
+        let args = args_for(&DownloadSpec::new(url, FormatSelector::audio("mp3")));
+
+        assert!(args.windows(2).any(|w| w == ["-f", "bestaudio/best"]));
+        assert!(args.contains(&"--extract-audio".to_string()));
+        assert!(args.windows(2).any(|w| w == ["--audio-format", "mp3"]));
+        assert!(!args.contains(&"-o".to_string()));
+        // The URL is passed exactly once
+        assert_eq!(args.iter().filter(|arg| *arg == url).count(), 1);
+    }
+
+    #[test]
+    fn test_download_args_with_custom_filename_and_cookies() {
+        let mut spec = DownloadSpec::new("https://test.url/video", FormatSelector::video("mp4"));
+        spec.custom_filename = Some("Artist/100% live".to_string());
+        spec.cookies_browser = Some("chrome".to_string());
+        let args = args_for(&spec);
+        assert!(args.windows(2).any(|w| w == ["-o", "Artist/100%% live.%(ext)s"]));
+        assert!(args.windows(2).any(|w| w == ["--cookies-from-browser", "chrome"]));
+
+        spec.selector = FormatSelector::audio("flac");
+        let args = args_for(&spec);
+        assert!(args.windows(2).any(|w| w == ["--cookies-from-browser", "chrome"]));
+
+        // An invalid selector is rejected before anything runs
+        spec.selector = FormatSelector::audio("mp5");
+        let staging = Staging::new("/tmp/s", "/tmp");
+        assert!(YtDlp::download_args(&spec, &staging, None).is_err());
+    }
+
+    #[test]
+    fn test_info_and_playlist_args() {
+        assert_eq!(
+            YtDlp::info_args("https://x.test/v", Some("firefox")),
+            ["--dump-json", "--no-playlist", "--cookies-from-browser", "firefox", "https://x.test/v"]
+        );
+        assert_eq!(
+            YtDlp::playlist_args("https://x.test/list", None),
+            ["--flat-playlist", "--dump-single-json", "https://x.test/list"]
+        );
+        assert_eq!(
+            YtDlp::cookies_download_args("https://x.test/v", "brave"),
+            ["--cookies-from-browser", "brave", "https://x.test/v"]
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_info_failure_is_classified() {
+        use std::os::unix::fs::PermissionsExt;
+
+        // Faux yt-dlp qui échoue comme sur une vidéo privée
+        let script = std::env::temp_dir().join(format!("rmd-fake-yt-dlp-{}", uuid::Uuid::new_v4()));
+        std::fs::write(&script, "#!/bin/sh\necho 'ERROR: [youtube] abc: Private video' >&2\nexit 1\n").unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let error = YtDlp::new(&script).fetch_info("https://test.url/private", None).await.unwrap_err();
+        std::fs::remove_file(&script).ok();
+
+        let failed = error.downcast_ref::<YtDlpFailed>().expect("yt-dlp failure should stay typed");
+        assert_eq!(failed.code, Some(1));
+        assert_eq!(DownloadError::of(&error), DownloadError::Private);
+    }
+}
@@ -0,0 +1,76 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::LazyLock;
+
+/// Motifs d'URL reconnus sans appeler yt-dlp : (clé d'extracteur yt-dlp, motif capturant l'identifiant).
+static URL_PATTERNS: LazyLock<Vec<(&'static str, Regex)>> = LazyLock::new(|| {
+    [
+        ("Youtube", r"^https?://(?:www\.|m\.|music\.)?youtube(?:-nocookie)?\.com/(?:watch\?(?:.*&)?v=|shorts/|embed/|live/|v/)([A-Za-z0-9_-]{11})"),
+        ("Youtube", r"^https?://youtu\.be/([A-Za-z0-9_-]{11})"),
+        ("Vimeo", r"^https?://(?:www\.|player\.)?vimeo\.com/(?:video/)?(\d+)"),
+        ("Dailymotion", r"^https?://(?:www\.)?dailymotion\.com/video/([A-Za-z0-9]+)"),
+        ("Dailymotion", r"^https?://dai\.ly/([A-Za-z0-9]+)"),
+    ]
+    .into_iter()
+    .map(|(extractor, pattern)| (extractor, Regex::new(pattern).expect("motif d'URL invalide")))
+    .collect()
+});
+
+/// Identité d'un média côté site : clé d'extracteur yt-dlp et identifiant de la vidéo.
+///
+/// Deux URL différentes (`youtu.be/x`, `youtube.com/watch?v=x&t=10`) désignant la
+/// même vidéo ont la même identité, comme dans une archive `--download-archive`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct MediaId {
+    pub extractor: String,
+    pub id: String,
+}
+
+impl MediaId {
+    pub fn new(extractor: impl Into<String>, id: impl Into<String>) -> Self {
+        Self { extractor: extractor.into(), id: id.into() }
+    }
+
+    /// Identité déduite de l'URL pour les sites courants, `None` sinon.
+    pub fn from_url(url: &str) -> Option<Self> {
+        let url = url.trim();
+        URL_PATTERNS.iter().find_map(|(extractor, pattern)| {
+            pattern.captures(url).map(|captures| Self::new(*extractor, &captures[1]))
+        })
+    }
+}
+
+impl fmt::Display for MediaId {
+    /// Même format qu'une ligne d'archive yt-dlp : `youtube dQw4w9WgXcQ`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.extractor.to_lowercase(), self.id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_url_variants_share_an_identity() {
+        let expected = Some(MediaId::new("Youtube", "dQw4w9WgXcQ"));
+        for url in [
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+            "https://youtube.com/watch?feature=share&v=dQw4w9WgXcQ&t=42",
+            "https://m.youtube.com/watch?v=dQw4w9WgXcQ",
+            "https://music.youtube.com/watch?v=dQw4w9WgXcQ&list=RD",
+            "https://youtu.be/dQw4w9WgXcQ?si=abc",
+            "https://www.youtube.com/shorts/dQw4w9WgXcQ",
+            "https://www.youtube-nocookie.com/embed/dQw4w9WgXcQ",
+        ] {
+            assert_eq!(MediaId::from_url(url), expected, "{}", url);
+        }
+
+        assert_eq!(MediaId::from_url("https://vimeo.com/76979871"), Some(MediaId::new("Vimeo", "76979871")));
+        assert_eq!(MediaId::from_url("https://dai.ly/x8abc12"), Some(MediaId::new("Dailymotion", "x8abc12")));
+        assert_eq!(MediaId::from_url("https://www.youtube.com/playlist?list=PL123"), None);
+        assert_eq!(MediaId::from_url("https://example.com/video.mp4"), None);
+        assert_eq!(MediaId::new("Youtube", "dQw4w9WgXcQ").to_string(), "youtube dQw4w9WgXcQ");
+    }
+}
@@ -0,0 +1,242 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use crate::video_info::{parse_video_info, VideoInfo};
+
+/// Préfixe des lignes émises par `--print after_move:...` pour repérer les fichiers finaux.
+const FILE_PRINT_PREFIX: &str = "rmd-file:";
+
+/// Gabarit passé à `--print` : une ligne par entrée, une fois le fichier à sa place définitive.
+pub const FILE_PRINT_TEMPLATE: &str = "after_move:rmd-file:%(extractor_key)s:%(id)s:%(filepath)s";
+
+/// Préfixe des lignes portant les métadonnées de l'entrée (JSON sur une ligne).
+const INFO_PRINT_PREFIX: &str = "rmd-info:";
+
+/// Gabarit `--print` des métadonnées : évite un second appel `--dump-json` après le téléchargement.
+pub const INFO_PRINT_TEMPLATE: &str =
+    "after_move:rmd-info:%(.{id,extractor_key,title,thumbnail,duration,uploader,uploader_id,view_count,description,chapters})j";
+
+/// Fichier final produit pour une entrée (vidéo seule ou élément de playlist).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DownloadedEntry {
+    pub id: String,
+    pub extractor: Option<String>,
+    pub file: PathBuf,
+}
+
+/// Résultat d'un téléchargement yt-dlp : ce qui a réellement été écrit sur le disque.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DownloadOutcome {
+    /// Fichiers finaux (après fusion, extraction audio, Spleeter…)
+    pub files: Vec<PathBuf>,
+    /// Flux intermédiaires (`.f137.mp4`, `.f251.webm`…) téléchargés avant fusion/conversion
+    pub intermediate_files: Vec<PathBuf>,
+    /// Une entrée par vidéo téléchargée (plusieurs pour une playlist)
+    pub entries: Vec<DownloadedEntry>,
+    /// Identifiant de la vidéo côté extracteur (première entrée)
+    pub video_id: Option<String>,
+    /// Métadonnées de la première entrée, issues du même appel à yt-dlp
+    pub info: Option<VideoInfo>,
+}
+
+impl DownloadOutcome {
+    /// Fichier principal du téléchargement (le premier fichier final)
+    pub fn primary_file(&self) -> Option<&PathBuf> {
+        self.files.first()
+    }
+}
+
+/// Suit la sortie standard de yt-dlp pour reconstituer un [`DownloadOutcome`].
+///
+/// Les lignes `--print` (voir [`FILE_PRINT_TEMPLATE`]) font foi ; les lignes
+/// `Destination:` / `Merging formats into` servent de repli pour les anciennes
+/// versions de yt-dlp et pour connaître les fichiers partiels en cours.
+pub struct OutputTracker {
+    download_directory: PathBuf,
+    destinations: Vec<PathBuf>,
+    post_processed: Vec<PathBuf>,
+    entries: Vec<DownloadedEntry>,
+    infos: Vec<VideoInfo>,
+}
+
+impl OutputTracker {
+    pub fn new(download_directory: impl Into<PathBuf>) -> Self {
+        Self {
+            download_directory: download_directory.into(),
+            destinations: Vec::new(),
+            post_processed: Vec::new(),
+            entries: Vec::new(),
+            infos: Vec::new(),
+        }
+    }
+
+    /// Analyse une ligne de stdout ; retourne `true` si elle a été reconnue.
+    pub fn observe(&mut self, line: &str) -> bool {
+        let line = line.trim();
+
+        if let Some(rest) = line.strip_prefix(FILE_PRINT_PREFIX) {
+            // extractor:id:path — le chemin peut lui-même contenir des ':' (C:\...)
+            let mut parts = rest.splitn(3, ':');
+            if let (Some(extractor), Some(id), Some(path)) = (parts.next(), parts.next(), parts.next()) {
+                let file = self.resolve(path);
+                self.entries.push(DownloadedEntry {
+                    id: id.to_string(),
+                    extractor: (!extractor.is_empty() && extractor != "NA").then(|| extractor.to_string()),
+                    file,
+                });
+                return true;
+            }
+        }
+
+        if let Some(json) = line.strip_prefix(INFO_PRINT_PREFIX) {
+            match serde_json::from_str::<serde_json::Value>(json) {
+                Ok(raw_info) => self.infos.push(parse_video_info(&raw_info)),
+                Err(e) => log::warn!("Métadonnées yt-dlp illisibles : {}", e),
+            }
+            return true;
+        }
+
+        if let Some(path) = line.strip_prefix("[download] Destination: ") {
+            let path = self.resolve(path);
+            self.destinations.push(path);
+            return true;
+        }
+
+        if let Some(path) = line.strip_prefix("[download] ").and_then(|l| l.strip_suffix(" has already been downloaded")) {
+            let path = self.resolve(path);
+            self.destinations.push(path);
+            return true;
+        }
+
+        if let Some(path) = line.strip_prefix("[Merger] Merging formats into ") {
+            let path = self.resolve(path.trim_matches('"'));
+            self.post_processed.push(path);
+            return true;
+        }
+
+        if let Some(path) = line.strip_prefix("[ExtractAudio] Destination: ") {
+            let path = self.resolve(path);
+            self.post_processed.push(path);
+            return true;
+        }
+
+        false
+    }
+
+    /// Destinations annoncées jusqu'ici (utile pour retrouver les fichiers partiels)
+    pub fn destinations(&self) -> &[PathBuf] {
+        &self.destinations
+    }
+
+    pub fn finish(self) -> DownloadOutcome {
+        let files: Vec<PathBuf> = if !self.entries.is_empty() {
+            self.entries.iter().map(|e| e.file.clone()).collect()
+        } else if !self.post_processed.is_empty() {
+            dedup(self.post_processed)
+        } else {
+            dedup(self.destinations.clone())
+        };
+
+        let intermediate_files = dedup(
+            self.destinations
+                .into_iter()
+                .filter(|d| !files.contains(d))
+                .collect(),
+        );
+
+        DownloadOutcome {
+            video_id: self.entries.first().map(|e| e.id.clone()),
+            files,
+            intermediate_files,
+            entries: self.entries,
+            info: self.infos.into_iter().next(),
+        }
+    }
+
+    /// yt-dlp peut afficher un chemin relatif à `-P` ou un chemin absolu
+    fn resolve(&self, path: &str) -> PathBuf {
+        let path = path.trim();
+        if Path::new(path).is_absolute() {
+            PathBuf::from(path)
+        } else {
+            self.download_directory.join(path)
+        }
+    }
+}
+
+fn dedup(paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut unique = Vec::with_capacity(paths.len());
+    for path in paths {
+        if !unique.contains(&path) {
+            unique.push(path);
+        }
+    }
+    unique
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merged_video_uses_print_line() {
+        let mut tracker = OutputTracker::new("/dl");
+        for line in [
+            "[download] Destination: /dl/Clip [abc].f137.mp4",
+            "[download] Destination: /dl/Clip [abc].f140.m4a",
+            "[Merger] Merging formats into \"/dl/Clip [abc].mp4\"",
+            "rmd-file:Youtube:abc:/dl/Clip [abc].mp4",
+        ] {
+            assert!(tracker.observe(line));
+        }
+
+        let outcome = tracker.finish();
+        assert_eq!(outcome.files, vec![PathBuf::from("/dl/Clip [abc].mp4")]);
+        assert_eq!(outcome.intermediate_files, vec![
+            PathBuf::from("/dl/Clip [abc].f137.mp4"),
+            PathBuf::from("/dl/Clip [abc].f140.m4a"),
+        ]);
+        assert_eq!(outcome.video_id.as_deref(), Some("abc"));
+        assert_eq!(outcome.entries[0].extractor.as_deref(), Some("Youtube"));
+    }
+
+    #[test]
+    fn test_info_line_is_parsed() {
+        let mut tracker = OutputTracker::new("/dl");
+        assert!(tracker.observe(r#"rmd-info:{"id": "abc", "title": "Clip", "duration": 212.4, "uploader": "Someone", "thumbnail": "https://i.ytimg.com/vi/abc/hq.jpg", "description": "line\nbreak"}"#));
+        tracker.observe("rmd-file:Youtube:abc:/dl/Clip.mp4");
+
+        let info = tracker.finish().info.expect("info line should be parsed");
+        assert_eq!(info.title, "Clip");
+        assert_eq!(info.duration, Some(212));
+        assert_eq!(info.uploader.as_deref(), Some("Someone"));
+        assert_eq!(info.description.as_deref(), Some("line\nbreak"));
+    }
+
+    #[test]
+    fn test_fallback_without_print_lines() {
+        let mut tracker = OutputTracker::new("/dl");
+        tracker.observe("[download] Destination: Song.webm");
+        tracker.observe("[ExtractAudio] Destination: Song.mp3");
+        tracker.observe("[download]  10.0% of 3.00MiB at 1.00MiB/s ETA 00:02");
+
+        let outcome = tracker.finish();
+        assert_eq!(outcome.files, vec![PathBuf::from("/dl/Song.mp3")]);
+        assert_eq!(outcome.intermediate_files, vec![PathBuf::from("/dl/Song.webm")]);
+        assert!(outcome.video_id.is_none());
+    }
+
+    #[test]
+    fn test_already_downloaded_and_playlist_entries() {
+        let mut tracker = OutputTracker::new("/dl");
+        tracker.observe("[download] /dl/One.mp4 has already been downloaded");
+        tracker.observe("rmd-file:Youtube:one:/dl/One.mp4");
+        tracker.observe("[download] Destination: /dl/Two.mp4");
+        tracker.observe("rmd-file:Youtube:two:/dl/Two: Live.mp4");
+
+        let outcome = tracker.finish();
+        assert_eq!(outcome.entries.len(), 2);
+        assert_eq!(outcome.entries[1].file, PathBuf::from("/dl/Two: Live.mp4"));
+        assert_eq!(outcome.files, vec![PathBuf::from("/dl/One.mp4"), PathBuf::from("/dl/Two: Live.mp4")]);
+        assert_eq!(outcome.intermediate_files, vec![PathBuf::from("/dl/Two.mp4")]);
+    }
+}
@@ -0,0 +1,297 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Champs utilisables dans un gabarit, entre accolades : `{uploader}/{title}.{ext}`.
+const FIELDS: &[&str] = &["title", "uploader", "id", "extractor", "playlist", "index", "type", "date", "ext"];
+
+/// Noms de fichiers réservés par Windows, quelle que soit l'extension.
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL",
+    "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9",
+    "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Extensions des fichiers temporaires de yt-dlp, ignorées par la détection de collision.
+const TEMPORARY_EXTENSIONS: &[&str] = &["part", "ytdl", "temp"];
+
+/// Que faire quand le chemin résolu existe déjà.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CollisionPolicy {
+    /// Ajouter ` (1)`, ` (2)`… au nom du fichier
+    #[default]
+    Suffix,
+    /// Remplacer le fichier existant
+    Overwrite,
+    /// Ne pas télécharger et garder le fichier existant
+    Skip,
+}
+
+/// Gabarits de chemin par type de téléchargement, relatifs au dossier de téléchargement.
+///
+/// Absent : yt-dlp garde son nommage par défaut (`titre [id].ext` à la racine).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OutputTemplates {
+    pub video: Option<String>,
+    pub audio: Option<String>,
+    pub instrumental: Option<String>,
+}
+
+/// Valeurs substituées dans un gabarit ; un champ absent est rendu vide.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TemplateValues {
+    pub title: Option<String>,
+    pub uploader: Option<String>,
+    pub id: Option<String>,
+    pub extractor: Option<String>,
+    pub playlist: Option<String>,
+    pub index: Option<u32>,
+    pub media_type: Option<String>,
+    pub date: Option<String>,
+}
+
+/// Décision prise pour un chemin résolu, selon la [`CollisionPolicy`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Collision {
+    /// Télécharger vers ce chemin (sans extension), libre ou à écraser
+    Download(PathBuf),
+    /// Un fichier existe déjà à ce chemin : ne rien télécharger
+    Skip(PathBuf),
+}
+
+/// Vérifie la syntaxe d'un gabarit : champs connus, accolades fermées, `{ext}` en fin seulement.
+pub fn validate_template(template: &str) -> Result<(), String> {
+    if template.trim().is_empty() {
+        return Err("Le gabarit de sortie est vide".to_string());
+    }
+    let body = strip_extension(template);
+    if body.contains("{ext}") {
+        return Err("{ext} n'est accepté qu'en fin de gabarit (.{ext})".to_string());
+    }
+    if Path::new(body).is_absolute() || body.starts_with(['/', '\\']) {
+        return Err("Le gabarit doit être relatif au dossier de téléchargement".to_string());
+    }
+    for field in fields(body)? {
+        let (name, spec) = field.split_once(':').unwrap_or((field, ""));
+        if !FIELDS.contains(&name) {
+            return Err(format!("Champ inconnu dans le gabarit : {{{}}}", name));
+        }
+        if !spec.is_empty() && (name != "index" || parse_width(spec).is_none()) {
+            return Err(format!("Format invalide pour {{{}}} (seul {{index:02}} est accepté)", field));
+        }
+    }
+    Ok(())
+}
+
+/// Chemin relatif (sans extension) produit par le gabarit.
+///
+/// Chaque segment est nettoyé pour tous les systèmes et tronqué à `max_length` octets ;
+/// les segments vides (ex. `{playlist}` hors playlist) disparaissent.
+pub fn render_template(template: &str, values: &TemplateValues, max_length: usize) -> Result<PathBuf, String> {
+    validate_template(template)?;
+    let body = strip_extension(template);
+
+    let mut path = PathBuf::new();
+    for segment in body.split(['/', '\\']) {
+        let mut rendered = String::new();
+        let mut rest = segment;
+        while let Some(start) = rest.find('{') {
+            rendered.push_str(&rest[..start]);
+            let end = rest[start..].find('}').map(|end| start + end).unwrap_or(rest.len());
+            rendered.push_str(&field_value(&rest[start + 1..end], values));
+            rest = rest.get(end + 1..).unwrap_or("");
+        }
+        rendered.push_str(rest);
+
+        let component = sanitize_component(&rendered, max_length);
+        if !component.is_empty() {
+            path.push(component);
+        }
+    }
+
+    if path.as_os_str().is_empty() {
+        return Err("Le gabarit ne produit aucun nom de fichier".to_string());
+    }
+    Ok(path)
+}
+
+/// Nettoie un segment de chemin pour Windows, macOS et Linux.
+///
+/// Remplace les caractères interdits et de contrôle par `_`, retire les points et
+/// espaces en bordure, évite les noms réservés et coupe à `max_length` octets.
+pub fn sanitize_component(component: &str, max_length: usize) -> String {
+    let replaced: String = component
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+
+    let trim = |s: &str| s.trim_matches(|c: char| c.is_whitespace() || c == '.' || c == '-').to_string();
+    let mut cleaned = trim(&replaced);
+
+    let stem = cleaned.split('.').next().unwrap_or("").to_uppercase();
+    if RESERVED_NAMES.contains(&stem.as_str()) {
+        cleaned.insert(0, '_');
+    }
+
+    if cleaned.len() > max_length {
+        let mut cut = max_length;
+        while !cleaned.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        cleaned.truncate(cut);
+        cleaned = trim(&cleaned);
+    }
+    cleaned
+}
+
+/// Applique la politique de collision à `relative` (chemin sans extension) sous `root`.
+///
+/// Un fichier « existe » dès qu'un fichier final porte ce nom, quelle que soit son
+/// extension, puisque celle-ci n'est connue qu'une fois le téléchargement terminé.
+pub fn resolve_collision(root: &Path, relative: &Path, policy: CollisionPolicy) -> Collision {
+    let existing = existing_files(&root.join(relative));
+    match (policy, existing.first()) {
+        (_, None) | (CollisionPolicy::Overwrite, _) => Collision::Download(relative.to_path_buf()),
+        (CollisionPolicy::Skip, Some(file)) => Collision::Skip(file.clone()),
+        (CollisionPolicy::Suffix, Some(_)) => {
+            let name = relative.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            let candidate = (1..)
+                .map(|n| relative.with_file_name(format!("{} ({})", name, n)))
+                .find(|candidate| existing_files(&root.join(candidate)).is_empty())
+                .expect("suffixe libre introuvable");
+            Collision::Download(candidate)
+        }
+    }
+}
+
+/// Fichiers finaux `stem.*` existants pour un chemin sans extension.
+pub fn existing_files(stem_path: &Path) -> Vec<PathBuf> {
+    let (Some(dir), Some(stem)) = (stem_path.parent(), stem_path.file_name()) else {
+        return Vec::new();
+    };
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let prefix = format!("{}.", stem.to_string_lossy());
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            let extension = name.strip_prefix(&prefix);
+            // `titre.mp4` oui ; `titre.f137.mp4.part` ou `titre (1).mp4` non
+            extension.is_some_and(|ext| !ext.is_empty() && !ext.contains('.') && !TEMPORARY_EXTENSIONS.contains(&ext))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+fn strip_extension(template: &str) -> &str {
+    template.trim().strip_suffix(".{ext}").unwrap_or(template.trim())
+}
+
+fn fields(template: &str) -> Result<Vec<&str>, String> {
+    let mut fields = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find(['{', '}']) {
+        if rest[start..].starts_with('}') {
+            return Err("Accolade fermante sans ouverture dans le gabarit".to_string());
+        }
+        let end = rest[start..].find('}').ok_or("Accolade non fermée dans le gabarit")? + start;
+        let field = &rest[start + 1..end];
+        if field.contains('{') {
+            return Err("Accolade non fermée dans le gabarit".to_string());
+        }
+        fields.push(field);
+        rest = &rest[end + 1..];
+    }
+    Ok(fields)
+}
+
+fn parse_width(spec: &str) -> Option<usize> {
+    spec.strip_prefix('0').and_then(|width| width.parse().ok()).filter(|width| (1..=6).contains(width))
+}
+
+fn field_value(field: &str, values: &TemplateValues) -> String {
+    let (name, spec) = field.split_once(':').unwrap_or((field, ""));
+    let text = |value: &Option<String>| value.clone().unwrap_or_default();
+    match name {
+        "title" => text(&values.title),
+        "uploader" => text(&values.uploader),
+        "id" => text(&values.id),
+        "extractor" => text(&values.extractor),
+        "playlist" => text(&values.playlist),
+        "type" => text(&values.media_type),
+        "date" => text(&values.date),
+        "index" => match (values.index, parse_width(spec)) {
+            (Some(index), Some(width)) => format!("{:0width$}", index, width = width),
+            (Some(index), None) => index.to_string(),
+            (None, _) => String::new(),
+        },
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values() -> TemplateValues {
+        TemplateValues {
+            title: Some("Live: Paris / 2024?".to_string()),
+            uploader: Some("The Band".to_string()),
+            id: Some("abc123".to_string()),
+            playlist: Some("Tour".to_string()),
+            index: Some(3),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_render_template() {
+        let template = "{uploader}/{playlist}/{index:02} - {title}.{ext}";
+        assert_eq!(render_template(template, &values(), 200), Ok(PathBuf::from("The Band/Tour/03 - Live_ Paris _ 2024_")));
+
+        // Hors playlist, le dossier et le préfixe disparaissent
+        let single = TemplateValues { playlist: None, index: None, ..values() };
+        assert_eq!(render_template(template, &single, 200), Ok(PathBuf::from("The Band/Live_ Paris _ 2024_")));
+
+        assert!(validate_template("{artist}/{title}").is_err());
+        assert!(validate_template("{title:02}").is_err());
+        assert!(validate_template("{title}.{ext}/x").is_err());
+        assert!(validate_template("/tmp/{title}").is_err());
+        assert!(validate_template("{title").is_err());
+    }
+
+    #[test]
+    fn test_sanitize_component() {
+        assert_eq!(sanitize_component("  ..hidden. ", 50), "hidden");
+        assert_eq!(sanitize_component("CON", 50), "_CON");
+        assert_eq!(sanitize_component("a\u{0}b", 50), "a_b");
+        // Coupé sur une frontière de caractère
+        assert_eq!(sanitize_component("ééé", 5), "éé");
+    }
+
+    #[test]
+    fn test_resolve_collision() {
+        let root = std::env::temp_dir().join(format!("rmd-template-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(root.join("Band")).unwrap();
+        std::fs::write(root.join("Band/Song.mp3"), b"").unwrap();
+        std::fs::write(root.join("Band/Other.mp3.part"), b"").unwrap();
+        let relative = Path::new("Band/Song");
+
+        assert_eq!(resolve_collision(&root, relative, CollisionPolicy::Suffix), Collision::Download(PathBuf::from("Band/Song (1)")));
+        assert_eq!(resolve_collision(&root, relative, CollisionPolicy::Overwrite), Collision::Download(relative.to_path_buf()));
+        assert_eq!(resolve_collision(&root, relative, CollisionPolicy::Skip), Collision::Skip(root.join("Band/Song.mp3")));
+        assert_eq!(resolve_collision(&root, Path::new("Band/Other"), CollisionPolicy::Skip), Collision::Download(PathBuf::from("Band/Other")));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
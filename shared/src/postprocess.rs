@@ -0,0 +1,117 @@
+use serde::{Deserialize, Serialize};
+
+use crate::format::{FormatError, SUBTITLE_CONTAINERS};
+
+/// Catégories de segments connues de SponsorBlock (`--sponsorblock-mark`).
+pub const SPONSORBLOCK_CATEGORIES: &[&str] = &[
+    "all", "default", "sponsor", "intro", "outro", "selfpromo", "preview", "filler",
+    "interaction", "music_offtopic", "poi_highlight", "chapter",
+];
+
+/// Catégories qui ne désignent pas un passage à couper : refusées par `--sponsorblock-remove`.
+const SPONSORBLOCK_MARK_ONLY: &[&str] = &["poi_highlight", "chapter"];
+
+/// Conteneurs dans lesquels yt-dlp sait intégrer une miniature (`--embed-thumbnail`).
+const THUMBNAIL_CONTAINERS: &[&str] = &["mp3", "m4a", "alac", "flac", "opus", "vorbis", "mp4", "mkv", "mov"];
+
+/// Post-traitements appliqués par yt-dlp une fois le fichier téléchargé.
+///
+/// Mêmes noms de champs dans la requête de l'API et dans les préréglages.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PostProcessing {
+    /// Titre, artiste, date… écrits dans les tags du fichier
+    pub embed_metadata: bool,
+    pub embed_thumbnail: bool,
+    pub embed_chapters: bool,
+    /// Sous-titres intégrés au fichier vidéo (langue par défaut de yt-dlp)
+    pub embed_subs: bool,
+    /// Catégories SponsorBlock coupées du fichier
+    pub sponsorblock_remove: Vec<String>,
+    /// Catégories SponsorBlock ajoutées comme chapitres
+    pub sponsorblock_mark: Vec<String>,
+}
+
+impl PostProcessing {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Arguments yt-dlp des post-traitements pour le fichier de sortie décrit.
+    ///
+    /// `container` vaut `None` quand il dépend du format choisi par yt-dlp ;
+    /// `subtitles_embedded` évite de répéter `--embed-subs` déjà demandé par la sélection de format.
+    pub(crate) fn build(&self, audio: bool, container: Option<&str>, subtitles_embedded: bool) -> Result<Vec<String>, FormatError> {
+        let mut args = Vec::new();
+
+        if self.embed_metadata {
+            args.push("--embed-metadata".to_string());
+        }
+        if self.embed_thumbnail {
+            if container.is_some_and(|c| !THUMBNAIL_CONTAINERS.contains(&c)) {
+                return Err(FormatError::Conflict("a thumbnail cannot be embedded in this format"));
+            }
+            args.push("--embed-thumbnail".to_string());
+        }
+        if self.embed_chapters {
+            args.push("--embed-chapters".to_string());
+        }
+        if self.embed_subs {
+            if audio {
+                return Err(FormatError::Conflict("subtitles are not available for audio downloads"));
+            }
+            if container.is_some_and(|c| !SUBTITLE_CONTAINERS.contains(&c)) {
+                return Err(FormatError::Conflict("subtitles cannot be embedded in this container"));
+            }
+            if !subtitles_embedded {
+                args.extend(["--write-subs".to_string(), "--embed-subs".to_string()]);
+            }
+        }
+
+        if !self.sponsorblock_remove.is_empty() {
+            let categories = categories(&self.sponsorblock_remove)?;
+            if let Some(category) = self.sponsorblock_remove.iter().find(|c| SPONSORBLOCK_MARK_ONLY.contains(&c.as_str())) {
+                return Err(FormatError::InvalidSponsorBlockCategory(category.clone()));
+            }
+            args.extend(["--sponsorblock-remove".to_string(), categories]);
+        }
+        if !self.sponsorblock_mark.is_empty() {
+            args.extend(["--sponsorblock-mark".to_string(), categories(&self.sponsorblock_mark)?]);
+        }
+
+        Ok(args)
+    }
+}
+
+/// Étapes présentes dans des arguments yt-dlp, telles qu'enregistrées sur le téléchargement
+/// (`metadata`, `subtitles`, `sponsorblock_remove:sponsor,intro`…).
+///
+/// Lues dans les arguments finaux plutôt que dans les options demandées : les
+/// sous-titres intégrés via `download_subtitles` y figurent aussi.
+pub fn applied_steps(args: &[String]) -> Vec<String> {
+    let mut steps = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let step = match arg.as_str() {
+            "--embed-metadata" => "metadata".to_string(),
+            "--embed-thumbnail" => "thumbnail".to_string(),
+            "--embed-chapters" => "chapters".to_string(),
+            "--embed-subs" => "subtitles".to_string(),
+            "--sponsorblock-remove" | "--sponsorblock-mark" => {
+                let Some(categories) = args.next() else { continue };
+                format!("{}:{}", arg.trim_start_matches("--").replace('-', "_"), categories)
+            }
+            _ => continue,
+        };
+        steps.push(step);
+    }
+    steps
+}
+
+/// Liste séparée par des virgules, après vérification de chaque catégorie.
+fn categories(categories: &[String]) -> Result<String, FormatError> {
+    if let Some(unknown) = categories.iter().find(|c| !SPONSORBLOCK_CATEGORIES.contains(&c.as_str())) {
+        return Err(FormatError::InvalidSponsorBlockCategory(unknown.clone()));
+    }
+    Ok(categories.join(","))
+}
@@ -0,0 +1,165 @@
+use serde::{Deserialize, Serialize};
+use crate::format::{FormatError, FormatSelector};
+use crate::postprocess::PostProcessing;
+
+/// Type de téléchargement auquel un préréglage est destiné.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PresetKind {
+    Video,
+    Audio,
+    Instrumental,
+}
+
+impl PresetKind {
+    /// Vrai pour les préréglages qui extraient l'audio (audio et instrumental).
+    pub fn is_audio(self) -> bool {
+        matches!(self, PresetKind::Audio | PresetKind::Instrumental)
+    }
+}
+
+/// Options d'un préréglage ; un champ absent laisse la valeur par défaut.
+///
+/// Mêmes noms de champs que la requête de téléchargement de l'API.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PresetOptions {
+    pub format: Option<String>,
+    pub resolution: Option<String>,
+    pub audio_quality: Option<String>,
+    pub download_subtitles: Option<bool>,
+    pub subtitle_language: Option<String>,
+    pub download_playlist: Option<bool>,
+    pub rate_limit: Option<String>,
+    /// Gabarit de chemin, prioritaire sur celui du type dans la configuration
+    pub output_template: Option<String>,
+    /// Métadonnées, miniature, chapitres et SponsorBlock appliqués après le téléchargement
+    pub post_processing: Option<PostProcessing>,
+    /// Un fichier par chapitre, rattaché au téléchargement d'origine
+    pub split_chapters: Option<bool>,
+}
+
+/// Combinaison d'options réutilisable, intégrée ou créée par l'utilisateur.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Preset {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(rename = "type")]
+    pub kind: PresetKind,
+    #[serde(flatten)]
+    pub options: PresetOptions,
+    /// Préréglage fourni avec l'application : ni modifiable ni supprimable
+    #[serde(default)]
+    pub builtin: bool,
+}
+
+impl Preset {
+    /// Sélecteur de format correspondant au préréglage (mp4 / mp3 par défaut).
+    pub fn format_selector(&self) -> Result<FormatSelector, FormatError> {
+        let options = &self.options;
+        let mut selector = if self.kind.is_audio() {
+            FormatSelector::audio(options.format.as_deref().unwrap_or("mp3"))
+        } else {
+            FormatSelector::video(options.format.as_deref().unwrap_or("mp4"))
+        };
+
+        if let Some(resolution) = &options.resolution {
+            selector = selector.resolution(resolution);
+        }
+        if let Some(audio_quality) = &options.audio_quality {
+            selector = selector.audio_quality(audio_quality);
+        }
+        selector = selector.subtitles(options.download_subtitles.unwrap_or(false), options.subtitle_language.as_deref());
+        if let Some(post_processing) = &options.post_processing {
+            // Spleeter remplace le fichier de yt-dlp par un nouveau wav : rien de ce qui y a été intégré ne resterait
+            if self.kind == PresetKind::Instrumental && !post_processing.is_empty() {
+                return Err(FormatError::Conflict("post-processing is not available for instrumental downloads"));
+            }
+            selector = selector.post_processing(post_processing.clone());
+        }
+
+        selector.validate()?;
+        Ok(selector)
+    }
+}
+
+/// Préréglages livrés avec l'application, partagés par le CLI et le serveur.
+pub fn builtin_presets() -> Vec<Preset> {
+    let builtin = |id: &str, name: &str, description: &str, kind: PresetKind, options: PresetOptions| Preset {
+        id: id.to_string(),
+        name: name.to_string(),
+        description: Some(description.to_string()),
+        kind,
+        options,
+        builtin: true,
+    };
+    let text = |value: &str| Some(value.to_string());
+
+    vec![
+        builtin("video-1080p-mp4", "1080p MP4", "Vidéo jusqu'à 1080p fusionnée en MP4", PresetKind::Video, PresetOptions {
+            format: text("mp4"),
+            resolution: text("1080p"),
+            ..Default::default()
+        }),
+        builtin("video-1080p-mp4-fr-subs", "1080p MP4 + sous-titres FR", "Vidéo jusqu'à 1080p en MP4 avec les sous-titres français intégrés", PresetKind::Video, PresetOptions {
+            format: text("mp4"),
+            resolution: text("1080p"),
+            download_subtitles: Some(true),
+            subtitle_language: text("fr"),
+            ..Default::default()
+        }),
+        builtin("video-720p-mp4", "720p MP4", "Vidéo jusqu'à 720p en MP4, plus légère", PresetKind::Video, PresetOptions {
+            format: text("mp4"),
+            resolution: text("720p"),
+            ..Default::default()
+        }),
+        builtin("audio-mp3-320k", "MP3 320k", "Audio extrait en MP3 à 320 kb/s", PresetKind::Audio, PresetOptions {
+            format: text("mp3"),
+            audio_quality: text("320k"),
+            ..Default::default()
+        }),
+        builtin("audio-flac", "FLAC", "Audio extrait sans perte en FLAC", PresetKind::Audio, PresetOptions {
+            format: text("flac"),
+            ..Default::default()
+        }),
+        builtin("instrumental-mp3-320k", "MP3 320k instrumental", "Piste instrumentale (Spleeter) en MP3 à 320 kb/s", PresetKind::Instrumental, PresetOptions {
+            format: text("mp3"),
+            audio_quality: text("320k"),
+            ..Default::default()
+        }),
+    ]
+}
+
+/// Préréglage intégré portant cet identifiant.
+pub fn find_builtin_preset(id: &str) -> Option<Preset> {
+    builtin_presets().into_iter().find(|preset| preset.id == id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_presets_are_valid() {
+        let presets = builtin_presets();
+        for preset in &presets {
+            assert!(preset.format_selector().is_ok(), "{}", preset.id);
+            assert_eq!(presets.iter().filter(|p| p.id == preset.id).count(), 1, "{}", preset.id);
+        }
+
+        let json = serde_json::to_value(find_builtin_preset("video-1080p-mp4-fr-subs").unwrap()).unwrap();
+        assert_eq!(json["type"], "video");
+        assert_eq!(json["subtitle_language"], "fr");
+    }
+
+    #[test]
+    fn test_instrumental_rejects_post_processing() {
+        let mut preset = find_builtin_preset("instrumental-mp3-320k").unwrap();
+        preset.options.post_processing = Some(PostProcessing { embed_metadata: true, ..Default::default() });
+        assert!(matches!(preset.format_selector(), Err(FormatError::Conflict(_))));
+
+        // Les mêmes options restent valables pour l'audio simple
+        preset.kind = PresetKind::Audio;
+        assert!(preset.format_selector().is_ok());
+    }
+}
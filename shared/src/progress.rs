@@ -1,5 +1,37 @@
-/// Tente d'extraire la progression à partir d'une ligne de progression
-/// et retourne la valeur extraite sans l'afficher directement.
+use std::sync::LazyLock;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Préfixe des lignes de progression JSON émises par `--progress-template download:`.
+const DOWNLOAD_PROGRESS_PREFIX: &str = "rmd-progress:";
+
+/// Gabarit `--progress-template` du téléchargement : une ligne JSON par mise à jour.
+pub const DOWNLOAD_PROGRESS_TEMPLATE: &str =
+    "download:rmd-progress:%(progress.{status,downloaded_bytes,total_bytes,total_bytes_estimate,speed,eta,fragment_index,fragment_count})j";
+
+/// Préfixe des lignes JSON émises par `--progress-template postprocess:`.
+const POSTPROCESS_PROGRESS_PREFIX: &str = "rmd-postprocess:";
+
+/// Gabarit `--progress-template` des post-traitements (fusion, extraction audio…).
+pub const POSTPROCESS_PROGRESS_TEMPLATE: &str = "postprocess:rmd-postprocess:%(progress.{status,postprocessor})j";
+
+/// Ligne texte `[download]` des versions de yt-dlp sans `--progress-template`.
+static PROGRESS_LINE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?x)
+            \[download\] \s*                                   # Préfixe
+            (?P<percent>\d+(?:\.\d+)?)% \s+ of \s+ ~? \s*      # `~` : taille estimée
+            (?P<size>\d+(?:\.\d+)?) (?P<unit>B|KiB|MiB|GiB|TiB)
+            (?: \s+ in \s+ [\d:]+ )?                              # Ligne finale : durée totale
+            (?: \s+ at \s+ (?P<speed>\d+(?:\.\d+)?)(?P<speed_unit>B|KiB|MiB|GiB|TiB)/s )?
+            (?: \s+ ETA \s+ (?P<eta>[\d:]+) )?
+            (?: \s+ \(frag \s+ (?P<frag_index>\d+)/(?P<frag_count>\d+)\) )?
+        "
+    ).expect("regex de progression invalide")
+});
+
+// Tente d'extraire la progression à partir d'une ligne de progression
+// et retourne la valeur extraite sans l'afficher directement.
 /*
 pub fn afficher_progression_ligne(line: &str) -> Option<(u64, u64)> {
     if let Some((current, total)) = parse_progress(line) {
@@ -11,6 +43,124 @@ pub fn afficher_progression_ligne(line: &str) -> Option<(u64, u64)> {
 }
 */
 
+/// Progression réelle d'un téléchargement, telle que rapportée par yt-dlp.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DownloadProgress {
+    pub percent: f32,
+    pub downloaded_bytes: u64,
+    pub total_bytes: u64,
+    pub speed: Option<f64>, // octets par seconde
+    pub eta: Option<u64>,   // secondes restantes
+}
+
+/// Fragment en cours d'un flux fragmenté (HLS, DASH).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Fragment {
+    pub index: u32,
+    pub count: u32,
+}
+
+/// Étape d'un téléchargement rapportée par yt-dlp.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "phase", rename_all = "snake_case")]
+pub enum ProgressEvent {
+    /// `total` est la taille annoncée, ou à défaut son estimation
+    Downloading {
+        bytes: u64,
+        total: Option<u64>,
+        speed: Option<f64>, // octets par seconde
+        eta: Option<u64>,   // secondes restantes
+        fragment: Option<Fragment>,
+    },
+    /// Un post-traitement a commencé (`Merger`, `ExtractAudio`, `FixupM3u8`…)
+    PostProcessing { step: String },
+    /// Un fichier est entièrement téléchargé
+    Finished,
+}
+
+impl ProgressEvent {
+    /// Progression chiffrée d'un événement `Downloading`.
+    pub fn download_progress(&self) -> Option<DownloadProgress> {
+        let ProgressEvent::Downloading { bytes, total, speed, eta, .. } = self else {
+            return None;
+        };
+        let total_bytes = total.unwrap_or(0);
+        let percent = if total_bytes > 0 { (*bytes as f64 / total_bytes as f64 * 100.0).min(100.0) as f32 } else { 0.0 };
+        Some(DownloadProgress { percent, downloaded_bytes: *bytes, total_bytes, speed: *speed, eta: *eta })
+    }
+}
+
+/// Callback appelé à chaque événement de progression lu dans la sortie de yt-dlp.
+pub type ProgressCallback = Box<dyn Fn(ProgressEvent) + Send + Sync>;
+
+/// Champs du dictionnaire de progression de yt-dlp repris par les gabarits.
+#[derive(Deserialize)]
+struct RawProgress {
+    status: Option<String>,
+    downloaded_bytes: Option<f64>,
+    total_bytes: Option<f64>,
+    total_bytes_estimate: Option<f64>,
+    speed: Option<f64>,
+    eta: Option<f64>,
+    fragment_index: Option<f64>,
+    fragment_count: Option<f64>,
+    postprocessor: Option<String>,
+}
+
+/// Analyse une ligne de stdout : JSON de `--progress-template` en priorité, sinon la
+/// ligne texte `[download]` des anciennes versions de yt-dlp.
+///
+/// # Exemple
+///
+/// ```
+/// use rust_media_downloader_shared::progress::{parse_progress_event, ProgressEvent};
+///
+/// let line = r#"rmd-postprocess:{"status": "started", "postprocessor": "Merger"}"#;
+/// assert_eq!(parse_progress_event(line), Some(ProgressEvent::PostProcessing { step: "Merger".to_string() }));
+/// ```
+pub fn parse_progress_event(line: &str) -> Option<ProgressEvent> {
+    let line = line.trim();
+    if let Some(json) = line.strip_prefix(DOWNLOAD_PROGRESS_PREFIX) {
+        let raw: RawProgress = serde_json::from_str(json).ok()?;
+        return match raw.status.as_deref() {
+            Some("downloading") => Some(ProgressEvent::Downloading {
+                bytes: raw.downloaded_bytes.unwrap_or(0.0) as u64,
+                total: raw.total_bytes.or(raw.total_bytes_estimate).map(|total| total as u64),
+                speed: raw.speed,
+                eta: raw.eta.map(|eta| eta as u64),
+                fragment: match (raw.fragment_index, raw.fragment_count) {
+                    (Some(index), Some(count)) => Some(Fragment { index: index as u32, count: count as u32 }),
+                    _ => None,
+                },
+            }),
+            Some("finished") => Some(ProgressEvent::Finished),
+            _ => None,
+        };
+    }
+    if let Some(json) = line.strip_prefix(POSTPROCESS_PROGRESS_PREFIX) {
+        let raw: RawProgress = serde_json::from_str(json).ok()?;
+        return match (raw.status.as_deref(), raw.postprocessor) {
+            (Some("started"), Some(step)) => Some(ProgressEvent::PostProcessing { step }),
+            _ => None,
+        };
+    }
+
+    let progress = parse_progress_details(line)?;
+    let fragment = PROGRESS_LINE.captures(line).and_then(|caps| {
+        Some(Fragment {
+            index: caps.name("frag_index")?.as_str().parse().ok()?,
+            count: caps.name("frag_count")?.as_str().parse().ok()?,
+        })
+    });
+    Some(ProgressEvent::Downloading {
+        bytes: progress.downloaded_bytes,
+        total: (progress.total_bytes > 0).then_some(progress.total_bytes),
+        speed: progress.speed,
+        eta: progress.eta,
+        fragment,
+    })
+}
+
 /// Analyse une ligne contenant l'information de téléchargement pour en extraire
 /// la progression et la taille totale en octets. Retourne `None` si la ligne
 /// ne contient pas de données valides.
@@ -18,40 +168,72 @@ pub fn afficher_progression_ligne(line: &str) -> Option<(u64, u64)> {
 /// # Exemple
 ///
 /// ```
+/// use rust_media_downloader_shared::progress::parse_progress;
+///
 /// let line = "[download]   50% of 100MiB at 1.23MiB/s ETA 00:01";
 /// if let Some((current, total)) = parse_progress(line) {
 ///     println!("Progression : {}/{}", current, total);
 /// }
 /// ```
 pub fn parse_progress(line: &str) -> Option<(u64, u64)> {
-    if !line.contains("[download]") { return None; }
-    // Cherche le pattern : pourcentage + "of" + taille
-    let re = regex::Regex::new(
-        r"(?x)
-            \[download\] \s*     # Préfixe
-            (?P<percent>\d+(?:\.\d+)?)% \s+ of \s+
-            (?P<size>\d+(?:\.\d+)?)           # Valeur nombre
-            (?P<unit>KiB|MiB|GiB)             # Unité
-        "
-    ).ok()?;
+    parse_progress_details(line).map(|p| (p.downloaded_bytes, p.total_bytes))
+}
 
-    let caps = re.captures(line)?;
+/// Variante de [`parse_progress`] qui conserve aussi le pourcentage, la vitesse et l'ETA.
+///
+/// # Exemple
+///
+/// ```
+/// use rust_media_downloader_shared::progress::parse_progress_details;
+///
+/// let line = "[download]  50.0% of 100.00MiB at 2.00MiB/s ETA 00:25";
+/// let progress = parse_progress_details(line).unwrap();
+/// assert_eq!(progress.eta, Some(25));
+/// ```
+pub fn parse_progress_details(line: &str) -> Option<DownloadProgress> {
+    if !line.contains("[download]") { return None; }
+    let caps = PROGRESS_LINE.captures(line)?;
 
     let percent = caps.name("percent")?.as_str().parse::<f64>().ok()?;
     let size = caps.name("size")?.as_str().parse::<f64>().ok()?;
-    let unit = caps.name("unit")?.as_str();
-
-    let multiplier = match unit {
-        "GiB" => 1024.0 * 1024.0 * 1024.0,
-        "MiB" => 1024.0 * 1024.0,
-        "KiB" => 1024.0,
-        _ => return None,
-    };
+    let multiplier = unit_multiplier(caps.name("unit")?.as_str())?;
 
     let total_bytes = (size * multiplier) as u64;
     let downloaded_bytes = ((percent / 100.0) * size * multiplier) as u64;
 
-    Some((downloaded_bytes, total_bytes))
+    let speed = match (caps.name("speed"), caps.name("speed_unit")) {
+        (Some(value), Some(unit)) => {
+            let value = value.as_str().parse::<f64>().ok()?;
+            Some(value * unit_multiplier(unit.as_str())?)
+        }
+        _ => None,
+    };
+    let eta = caps.name("eta").and_then(|m| parse_eta(m.as_str()));
+
+    Some(DownloadProgress {
+        percent: percent as f32,
+        downloaded_bytes,
+        total_bytes,
+        speed,
+        eta,
+    })
+}
+
+fn unit_multiplier(unit: &str) -> Option<f64> {
+    match unit {
+        "TiB" => Some(1024.0 * 1024.0 * 1024.0 * 1024.0),
+        "GiB" => Some(1024.0 * 1024.0 * 1024.0),
+        "MiB" => Some(1024.0 * 1024.0),
+        "KiB" => Some(1024.0),
+        "B" => Some(1.0),
+        _ => None,
+    }
+}
+
+/// Convertit un ETA au format `SS`, `MM:SS` ou `HH:MM:SS` en secondes.
+fn parse_eta(eta: &str) -> Option<u64> {
+    eta.split(':')
+        .try_fold(0u64, |acc, part| part.parse::<u64>().ok().map(|v| acc * 60 + v))
 }
 
 /// Formate les informations de progression pour un affichage convivial.
@@ -61,8 +243,10 @@ pub fn parse_progress(line: &str) -> Option<(u64, u64)> {
 /// # Exemple
 ///
 /// ```
+/// use rust_media_downloader_shared::progress::formater_progression;
+///
 /// let (current, total) = (50_000_000, 100_000_000);
-/// let message = format_progress_display(current, total);
+/// let message = formater_progression(current, total);
 /// println!("{}", message);
 /// // Possible sortie : [#####.....] 50% (50.0MB / 100.0MB)
 /// ```
@@ -92,3 +276,77 @@ pub fn formater_progression(current_bytes: u64, total_bytes: u64) -> String {
         barre, pourcentage, octets_actuels_mb, octets_totaux_mb
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_progress_with_speed_and_eta() {
+        let line = "[download]  25.0% of 4.00MiB at 512.00KiB/s ETA 01:02";
+        let progress = parse_progress_details(line).unwrap();
+
+        assert_eq!(progress.percent, 25.0);
+        assert_eq!(progress.total_bytes, 4 * 1024 * 1024);
+        assert_eq!(progress.downloaded_bytes, 1024 * 1024);
+        assert_eq!(progress.speed, Some(512.0 * 1024.0));
+        assert_eq!(progress.eta, Some(62));
+    }
+
+    #[test]
+    fn test_parse_progress_without_speed() {
+        let line = "[download] 100% of 10.00MiB";
+        let progress = parse_progress_details(line).unwrap();
+
+        assert_eq!(progress.percent, 100.0);
+        assert_eq!(progress.speed, None);
+        assert_eq!(progress.eta, None);
+        assert_eq!(parse_progress(line), Some((10 * 1024 * 1024, 10 * 1024 * 1024)));
+    }
+
+    #[test]
+    fn test_parse_progress_estimates_bytes_and_fragments() {
+        let line = "[download]  50.0% of ~  2.00TiB at  10.00MiB/s ETA 01:00:00 (frag 5/10)";
+        let progress = parse_progress_details(line).unwrap();
+        assert_eq!(progress.total_bytes, 2 * 1024 * 1024 * 1024 * 1024);
+        assert_eq!(progress.eta, Some(3600));
+        assert_eq!(parse_progress_event(line), Some(ProgressEvent::Downloading {
+            bytes: 1024 * 1024 * 1024 * 1024,
+            total: Some(2 * 1024 * 1024 * 1024 * 1024),
+            speed: Some(10.0 * 1024.0 * 1024.0),
+            eta: Some(3600),
+            fragment: Some(Fragment { index: 5, count: 10 }),
+        }));
+
+        let progress = parse_progress_details("[download] 100% of  512.00B in 00:00:01 at 1.00KiB/s").unwrap();
+        assert_eq!((progress.total_bytes, progress.speed), (512, Some(1024.0)));
+    }
+
+    #[test]
+    fn test_parse_progress_template_events() {
+        let line = r#"rmd-progress:{"status": "downloading", "downloaded_bytes": 1048576, "total_bytes": null, "total_bytes_estimate": 4194304.5, "speed": 2048.0, "eta": 3, "fragment_index": 2, "fragment_count": 8}"#;
+        let event = parse_progress_event(line).unwrap();
+        assert_eq!(event, ProgressEvent::Downloading {
+            bytes: 1048576,
+            total: Some(4194304),
+            speed: Some(2048.0),
+            eta: Some(3),
+            fragment: Some(Fragment { index: 2, count: 8 }),
+        });
+        assert_eq!(event.download_progress().unwrap().percent, 25.0);
+
+        assert_eq!(parse_progress_event(r#"rmd-progress:{"status": "finished", "downloaded_bytes": 10}"#), Some(ProgressEvent::Finished));
+        assert_eq!(
+            parse_progress_event(r#"rmd-postprocess:{"status": "started", "postprocessor": "ExtractAudio"}"#),
+            Some(ProgressEvent::PostProcessing { step: "ExtractAudio".to_string() })
+        );
+        assert_eq!(parse_progress_event(r#"rmd-postprocess:{"status": "finished", "postprocessor": "ExtractAudio"}"#), None);
+        assert_eq!(parse_progress_event("rmd-progress:not json"), None);
+    }
+
+    #[test]
+    fn test_parse_progress_ignores_other_lines() {
+        assert!(parse_progress_details("[youtube] abc: Downloading webpage").is_none());
+        assert!(parse_progress_details("[download] Destination: video.mp4").is_none());
+    }
+}
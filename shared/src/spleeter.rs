@@ -7,14 +7,18 @@ use indicatif::{ProgressBar, ProgressStyle};
 use log::{info, warn, error};
 use anyhow::{Result, Context};
 
-pub async fn extract_instrumental(original_downloaded_full_path: &PathBuf) -> Result<()> {
+/// Sépare l'instrumental du fichier audio donné.
+///
+/// Retourne le chemin du fichier `<nom>_instrumental.wav` créé, ou `None` si
+/// l'extraction n'a pas pu se faire (le fichier original est alors conservé).
+pub async fn extract_instrumental(original_downloaded_full_path: &PathBuf) -> Result<Option<PathBuf>> {
     info!("⚙️  Extraction de l'instrumental avec Spleeter en cours (cela peut prendre du temps)...");
 
     if Command::new("spleeter").arg("--version").output().await.is_err() {
         error!("❌ Spleeter n'est pas installé ou n'est pas dans le PATH.");
         info!("   Veuillez l'installer pour utiliser l'extraction instrumentale.");
         info!("   Le fichier audio original a été conservé ici : {:?}", original_downloaded_full_path);
-        return Ok(());
+        return Ok(None);
     }
 
     let input_audio_path_for_spleeter = original_downloaded_full_path
@@ -103,6 +107,7 @@ pub async fn extract_instrumental(original_downloaded_full_path: &PathBuf) -> Re
                             warn!("⚠️ Impossible de supprimer le dossier de Spleeter {:?}: {}", spleeter_output_subdir, e);
                         }
                     }
+                    return Ok(Some(final_instrumental_full_path));
                 }
                 Err(e) => {
                     error!("❌ Erreur lors du renommage/déplacement du fichier instrumental: {}", e);
@@ -119,5 +124,5 @@ pub async fn extract_instrumental(original_downloaded_full_path: &PathBuf) -> Re
         info!("   Chemin du fichier original : {:?}", original_downloaded_full_path);
     }
 
-    Ok(())
+    Ok(None)
 }
@@ -0,0 +1,194 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use anyhow::{bail, Context, Result};
+use log::{info, warn};
+use crate::config::Config;
+use crate::outcome::DownloadOutcome;
+
+/// Dossier de travail par défaut, dans `download_directory` pour rester sur le même disque
+pub const DEFAULT_STAGING_DIR: &str = ".staging";
+
+/// Sous-dossier où yt-dlp écrit `.part`, `.ytdl` et fragments (`-P temp:`)
+const PARTS_DIR: &str = "parts";
+
+/// Racine des dossiers de travail : `staging_directory` ou `<download_directory>/.staging`.
+pub fn staging_root(config: &Config) -> PathBuf {
+    match config.staging_directory.as_deref().map(str::trim) {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => Path::new(&config.download_directory).join(DEFAULT_STAGING_DIR),
+    }
+}
+
+/// Dossier de travail d'un téléchargement.
+///
+/// yt-dlp y écrit tout ce qu'il produit ; seuls les fichiers finaux vérifiés sont
+/// ensuite déplacés dans la bibliothèque, au même chemin relatif.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Staging {
+    dir: PathBuf,
+    library: PathBuf,
+}
+
+impl Staging {
+    pub fn new(dir: impl Into<PathBuf>, library: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into(), library: library.into() }
+    }
+
+    /// Dossier de travail d'un job : le même à chaque reprise, pour retrouver ses fichiers partiels.
+    pub fn for_job(config: &Config, job_id: &str) -> Self {
+        Self::new(staging_root(config).join(job_id), &config.download_directory)
+    }
+
+    /// Dossier de travail à usage unique (CLI), nommé d'après le processus et l'heure.
+    pub fn unique(config: &Config) -> Self {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or_default();
+        Self::for_job(config, &format!("cli-{}-{}", std::process::id(), nanos))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Arguments `-P` de yt-dlp : fichiers terminés dans le dossier de travail, partiels dans `parts/`.
+    pub fn yt_dlp_args(&self) -> Vec<String> {
+        vec![
+            "-P".to_string(),
+            self.dir.to_string_lossy().to_string(),
+            "-P".to_string(),
+            format!("temp:{}", self.dir.join(PARTS_DIR).to_string_lossy()),
+        ]
+    }
+
+    pub fn create(&self) -> Result<()> {
+        fs::create_dir_all(self.dir.join(PARTS_DIR))
+            .with_context(|| format!("Impossible de créer le dossier de travail {:?}", self.dir))
+    }
+
+    /// Chemin dans la bibliothèque d'un fichier du dossier de travail.
+    pub fn library_path(&self, file: &Path) -> PathBuf {
+        match file.strip_prefix(&self.dir) {
+            Ok(relative) => self.library.join(relative),
+            // Déjà hors du dossier de travail (chemin absolu imposé) : laissé en place
+            Err(_) => file.to_path_buf(),
+        }
+    }
+
+    /// Vérifie les fichiers finaux puis les déplace dans la bibliothèque et supprime le dossier de travail.
+    ///
+    /// Un fichier manquant ou vide fait échouer l'opération avant tout déplacement.
+    /// Les fichiers intermédiaires ne sont déplacés que s'ils existent encore (`-k`).
+    pub fn commit(&self, mut outcome: DownloadOutcome) -> Result<DownloadOutcome> {
+        for file in &outcome.files {
+            verify_output(file)?;
+        }
+
+        for file in outcome.files.iter_mut() {
+            let target = self.library_path(file);
+            move_atomically(file, &target)?;
+            info!("Fichier déplacé dans la bibliothèque : {:?}", target);
+            *file = target;
+        }
+        for entry in outcome.entries.iter_mut() {
+            entry.file = self.library_path(&entry.file);
+        }
+        for file in outcome.intermediate_files.iter_mut() {
+            let target = self.library_path(file);
+            if file.is_file() {
+                move_atomically(file, &target)?;
+            }
+            *file = target;
+        }
+
+        self.discard();
+        Ok(outcome)
+    }
+
+    /// Supprime le dossier de travail et tout ce qu'il contient.
+    pub fn discard(&self) {
+        if let Err(e) = fs::remove_dir_all(&self.dir) {
+            if e.kind() != io::ErrorKind::NotFound {
+                warn!("Impossible de supprimer le dossier de travail {:?} : {}", self.dir, e);
+            }
+        }
+    }
+}
+
+/// Un fichier final doit exister et ne pas être vide.
+fn verify_output(file: &Path) -> Result<()> {
+    match fs::metadata(file) {
+        Ok(metadata) if metadata.is_file() && metadata.len() > 0 => Ok(()),
+        Ok(_) => bail!("Fichier final vide ou invalide : {:?}", file),
+        Err(e) => bail!("Fichier final introuvable {:?} : {}", file, e),
+    }
+}
+
+/// Déplace un fichier sans jamais exposer de fichier incomplet à sa destination.
+///
+/// `rename` est atomique sur un même système de fichiers ; sinon le fichier est copié
+/// sous un nom caché dans le dossier cible, synchronisé, puis renommé.
+pub fn move_atomically(from: &Path, to: &Path) -> Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent).with_context(|| format!("Impossible de créer le dossier {:?}", parent))?;
+    }
+
+    match fs::rename(from, to) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            let name = to.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            let temporary = to.with_file_name(format!(".{}.rmd-move", name));
+            let copy = || -> io::Result<()> {
+                fs::copy(from, &temporary)?;
+                fs::File::open(&temporary)?.sync_all()?;
+                fs::rename(&temporary, to)
+            };
+            if let Err(e) = copy() {
+                let _ = fs::remove_file(&temporary);
+                return Err(e).with_context(|| format!("Impossible de déplacer {:?} vers {:?}", from, to));
+            }
+            fs::remove_file(from).with_context(|| format!("Impossible de supprimer {:?} après copie", from))
+        }
+        Err(e) => Err(e).with_context(|| format!("Impossible de déplacer {:?} vers {:?}", from, to)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::outcome::DownloadedEntry;
+
+    #[test]
+    fn test_commit_moves_verified_outputs() {
+        let base = std::env::temp_dir().join(format!("rmd-staging-{}", uuid::Uuid::new_v4()));
+        let staging = Staging::new(base.join(".staging/job"), base.join("library"));
+        staging.create().unwrap();
+        fs::create_dir_all(staging.dir().join("Artist")).unwrap();
+        fs::write(staging.dir().join("Artist/Song.mp3"), b"audio").unwrap();
+        fs::write(staging.dir().join("parts/Song.webm.part"), b"partial").unwrap();
+
+        let args = staging.yt_dlp_args();
+        assert_eq!(args[3], format!("temp:{}", staging.dir().join("parts").display()));
+
+        let outcome = DownloadOutcome {
+            files: vec![staging.dir().join("Artist/Song.mp3")],
+            entries: vec![DownloadedEntry { id: "abc".to_string(), extractor: None, file: staging.dir().join("Artist/Song.mp3") }],
+            ..Default::default()
+        };
+        let committed = staging.commit(outcome).unwrap();
+        let target = base.join("library/Artist/Song.mp3");
+        assert_eq!(committed.files, vec![target.clone()]);
+        assert_eq!(committed.entries[0].file, target);
+        assert_eq!(fs::read(&target).unwrap(), b"audio");
+        assert!(!staging.dir().exists());
+
+        // An empty output is never moved into the library
+        staging.create().unwrap();
+        fs::write(staging.dir().join("Empty.mp3"), b"").unwrap();
+        let outcome = DownloadOutcome { files: vec![staging.dir().join("Empty.mp3")], ..Default::default() };
+        assert!(staging.commit(outcome).is_err());
+        assert!(!base.join("library/Empty.mp3").exists());
+
+        let _ = fs::remove_dir_all(&base);
+    }
+}
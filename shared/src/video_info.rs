@@ -1,13 +1,12 @@
 use serde::{Deserialize, Serialize};
-use tokio::process::Command;
-use std::process::Stdio;
-use anyhow::{Result, Context};
-use log::{info, warn};
+use anyhow::Result;
+use crate::media_backend::{MediaBackend, YtDlp};
 
 /// Metadata structure for video information from yt-dlp
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct VideoInfo {
     pub id: String,
+    pub extractor_key: Option<String>, // e.g. "Youtube", with `id` it identifies the media
     pub title: String,
     pub thumbnail: Option<String>,
     pub duration: Option<u64>, // in seconds
@@ -17,9 +16,18 @@ pub struct VideoInfo {
     pub description: Option<String>,
     pub formats: Option<Vec<FormatInfo>>,
     pub subtitles: Option<serde_json::Value>,
+    pub chapters: Option<Vec<Chapter>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Chapter of a video, times in seconds from the start
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Chapter {
+    pub start_time: f64,
+    pub end_time: Option<f64>,
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct FormatInfo {
     pub format_id: String,
     pub resolution: Option<String>,
@@ -34,42 +42,11 @@ pub struct FormatInfo {
 
 /// Fetch video metadata using yt-dlp --dump-json
 pub async fn get_video_info(url: &str, cookies_browser: Option<&str>) -> Result<VideoInfo> {
-    let mut command = Command::new("yt-dlp");
-    
-    // Get JSON dump of video info
-    command.args(&[
-        "--dump-json",
-        "--no-playlist", // Only get single video info
-    ]);
-    
-    if let Some(browser) = cookies_browser {
-        command.args(&["--cookies-from-browser", browser]);
-    }
-    
-    command.arg(url);
-    command.stdout(Stdio::piped());
-    command.stderr(Stdio::piped());
-    
-    info!("Fetching video info for: {}", url);
-    
-    let output = command
-        .output()
-        .await
-        .context("Failed to execute yt-dlp")?;
-    
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        warn!("yt-dlp error: {}", stderr);
-        anyhow::bail!("Failed to get video info: {}", stderr);
-    }
-    
-    let json_output = String::from_utf8(output.stdout)
-        .context("Failed to parse yt-dlp output as UTF-8")?;
-    
-    // Parse the JSON response from yt-dlp
-    let raw_info: serde_json::Value = serde_json::from_str(&json_output)
-        .context("Failed to parse yt-dlp JSON output")?;
-    
+    YtDlp::default().fetch_info(url, cookies_browser).await
+}
+
+/// Build a [`VideoInfo`] from a yt-dlp info dict (`--dump-json`, `--print %()j`)
+pub fn parse_video_info(raw_info: &serde_json::Value) -> VideoInfo {
     // Extract formats for quality selection
     let formats = raw_info.get("formats")
         .and_then(|f| f.as_array())
@@ -81,12 +58,14 @@ pub async fn get_video_info(url: &str, cookies_browser: Option<&str>) -> Result<
                 .collect::<Vec<_>>()
         });
     
-    // Build VideoInfo from raw JSON
-    Ok(VideoInfo {
+    VideoInfo {
         id: raw_info.get("id")
             .and_then(|v| v.as_str())
             .unwrap_or("")
             .to_string(),
+        extractor_key: raw_info.get("extractor_key")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
         title: raw_info.get("title")
             .and_then(|v| v.as_str())
             .unwrap_or("Unknown")
@@ -110,6 +89,111 @@ pub async fn get_video_info(url: &str, cookies_browser: Option<&str>) -> Result<
             .map(|s| s.to_string()),
         formats,
         subtitles: raw_info.get("subtitles").cloned(),
+        chapters: raw_info.get("chapters")
+            .and_then(|c| serde_json::from_value::<Vec<Chapter>>(c.clone()).ok())
+            .filter(|chapters| !chapters.is_empty()),
+    }
+}
+
+
+/// Playlist listing obtained without downloading anything (`--flat-playlist`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaylistInfo {
+    pub id: String,
+    pub title: Option<String>,
+    pub entries: Vec<PlaylistEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaylistEntry {
+    pub id: String,
+    pub url: String,
+    pub title: Option<String>,
+    pub duration: Option<u64>, // in seconds
+}
+
+/// List the entries of a playlist URL.
+///
+/// Returns `None` when the URL points to a single video rather than a playlist.
+pub async fn get_playlist_info(url: &str, cookies_browser: Option<&str>) -> Result<Option<PlaylistInfo>> {
+    YtDlp::default().list_playlist(url, cookies_browser).await
+}
+
+/// Build a [`PlaylistInfo`] from `--flat-playlist --dump-single-json` output
+pub fn parse_playlist_info(raw_info: &serde_json::Value) -> Option<PlaylistInfo> {
+    if raw_info.get("_type").and_then(|v| v.as_str()) != Some("playlist") {
+        return None;
+    }
+
+    let entries = raw_info.get("entries")
+        .and_then(|e| e.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|entry| {
+                    let id = entry.get("id").and_then(|v| v.as_str())?.to_string();
+                    // Flat entries carry the video URL in "url"; some extractors only give "webpage_url"
+                    let url = entry.get("url")
+                        .or_else(|| entry.get("webpage_url"))
+                        .and_then(|v| v.as_str())?
+                        .to_string();
+                    Some(PlaylistEntry {
+                        id,
+                        url,
+                        title: entry.get("title").and_then(|v| v.as_str()).map(|s| s.to_string()),
+                        duration: entry.get("duration").and_then(|v| v.as_f64()).map(|d| d as u64),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Some(PlaylistInfo {
+        id: raw_info.get("id").and_then(|v| v.as_str()).unwrap_or("").to_string(),
+        title: raw_info.get("title").and_then(|v| v.as_str()).map(|s| s.to_string()),
+        entries,
     })
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_flat_playlist() {
+        let raw = serde_json::json!({
+            "_type": "playlist",
+            "id": "PL123",
+            "title": "Mix",
+            "entries": [
+                {"id": "a1", "url": "https://www.youtube.com/watch?v=a1", "title": "One", "duration": 61.0},
+                {"id": "b2", "webpage_url": "https://www.youtube.com/watch?v=b2"},
+                {"title": "No id, skipped"}
+            ]
+        });
+
+        let playlist = parse_playlist_info(&raw).unwrap();
+        assert_eq!(playlist.title.as_deref(), Some("Mix"));
+        assert_eq!(playlist.entries.len(), 2);
+        assert_eq!(playlist.entries[0].duration, Some(61));
+        assert_eq!(playlist.entries[1].url, "https://www.youtube.com/watch?v=b2");
+
+        assert!(parse_playlist_info(&serde_json::json!({"_type": "video", "id": "x"})).is_none());
+    }
+
+    #[test]
+    fn test_parse_chapters() {
+        let raw = serde_json::json!({
+            "id": "album",
+            "title": "Full album",
+            "chapters": [
+                {"start_time": 0.0, "end_time": 185.5, "title": "Intro"},
+                {"start_time": 185.5, "end_time": 402.0, "title": "Second song"}
+            ]
+        });
+
+        let chapters = parse_video_info(&raw).chapters.unwrap();
+        assert_eq!(chapters.len(), 2);
+        assert_eq!(chapters[1], Chapter { start_time: 185.5, end_time: Some(402.0), title: Some("Second song".to_string()) });
+        assert!(parse_video_info(&serde_json::json!({"id": "x", "chapters": []})).chapters.is_none());
+    }
+}